@@ -26,7 +26,7 @@ ez() {
 
     # If any flags are passed, bypass wrapper and call binary directly
     case "$1" in
-        --*|init)
+        --*|init|config|context|index|profile|eval)
             "$EZ_BIN" "$@"
             return $?
             ;;
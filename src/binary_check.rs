@@ -0,0 +1,233 @@
+//! Check that every program a generated command runs exists on this machine
+//!
+//! A program counts as available if it is in `PATH`, is a shell builtin or
+//! keyword, or is an alias or function from the user's shell startup files.
+//! Missing ones are reported with the package that provides them for the
+//! detected package manager.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::flag_check::invoked_programs;
+use crate::process_util::find_in_path;
+use crate::providers::builtin::shell_definitions;
+use crate::system_info::SystemInfo;
+
+/// Builtins and keywords of bash, zsh and fish that have no binary in `PATH`
+const SHELL_BUILTINS: [&str; 104] = [
+    "!", ".", ":", "[", "[[", "]]", "{", "}", "abbr", "alias", "and", "argparse", "autoload", "begin", "bg", "bind",
+    "bindkey", "break", "builtin", "caller", "case", "cd", "command", "compgen", "complete", "contains", "continue",
+    "coproc", "count", "declare", "dirs", "disown", "do", "done", "echo", "elif", "else", "emulate", "enable",
+    "end", "esac", "eval", "exec", "exit", "export", "false", "fc", "fg", "fi", "for", "function", "functions",
+    "getopts", "hash", "help", "history", "if", "jobs", "kill", "let", "local", "logout", "mapfile", "math",
+    "noglob", "not", "or", "popd", "print", "printf", "pushd", "read", "readonly", "rehash", "repeat", "return",
+    "select", "set", "set_color", "setopt", "shift", "shopt", "source", "status", "string", "suspend", "switch",
+    "test", "then", "time", "times", "trap", "true", "type", "typeset", "ulimit", "umask", "unalias", "unset",
+    "until", "wait", "whence", "while", "zmodload",
+];
+
+/// `(binary, package, [(package manager, package)])`; the middle entry is the
+/// name used by package managers not listed
+type PackageEntry = (&'static str, &'static str, &'static [(&'static str, &'static str)]);
+
+/// Binaries whose package is named differently, per package manager
+const PACKAGES: [PackageEntry; 40] = [
+    ("7z", "p7zip", &[("apt", "p7zip-full"), ("brew", "sevenzip")]),
+    ("ag", "the_silver_searcher", &[("apt", "silversearcher-ag")]),
+    ("aws", "awscli", &[("brew", "awscli"), ("nix", "awscli2")]),
+    ("bat", "bat", &[]),
+    ("convert", "imagemagick", &[("dnf", "ImageMagick"), ("zypper", "ImageMagick")]),
+    ("delta", "git-delta", &[("pacman", "git-delta")]),
+    ("dig", "bind-utils", &[("apt", "dnsutils"), ("pacman", "bind"), ("apk", "bind-tools"), ("brew", "bind"), ("nix", "dig")]),
+    ("dust", "du-dust", &[("brew", "dust"), ("pacman", "dust"), ("nix", "du-dust")]),
+    ("eza", "eza", &[]),
+    ("fd", "fd", &[("apt", "fd-find"), ("dnf", "fd-find"), ("nix", "fd")]),
+    ("fdfind", "fd-find", &[]),
+    ("ffmpeg", "ffmpeg", &[]),
+    ("fzf", "fzf", &[]),
+    ("gh", "gh", &[("nix", "gh")]),
+    ("go", "golang", &[("brew", "go"), ("pacman", "go"), ("apk", "go"), ("nix", "go")]),
+    ("http", "httpie", &[]),
+    ("ip", "iproute2", &[("dnf", "iproute"), ("zypper", "iproute2"), ("brew", "iproute2mac")]),
+    ("jq", "jq", &[]),
+    ("kubectl", "kubectl", &[("brew", "kubernetes-cli"), ("nix", "kubectl")]),
+    ("lsof", "lsof", &[]),
+    ("ncdu", "ncdu", &[]),
+    ("netstat", "net-tools", &[]),
+    ("nmap", "nmap", &[]),
+    ("node", "nodejs", &[("brew", "node"), ("nix", "nodejs")]),
+    ("parallel", "parallel", &[]),
+    ("pip3", "python3-pip", &[("dnf", "python3-pip"), ("pacman", "python-pip"), ("apk", "py3-pip"), ("brew", "python")]),
+    ("python3", "python3", &[("pacman", "python"), ("brew", "python")]),
+    ("rg", "ripgrep", &[]),
+    ("rsync", "rsync", &[]),
+    ("sd", "sd", &[]),
+    ("shellcheck", "shellcheck", &[("dnf", "ShellCheck"), ("zypper", "ShellCheck")]),
+    ("sqlite3", "sqlite3", &[("dnf", "sqlite"), ("pacman", "sqlite"), ("apk", "sqlite"), ("brew", "sqlite"), ("nix", "sqlite")]),
+    ("ss", "iproute2", &[("dnf", "iproute")]),
+    ("tldr", "tldr", &[("brew", "tlrc")]),
+    ("tree", "tree", &[]),
+    ("unzip", "unzip", &[]),
+    ("watch", "procps", &[("dnf", "procps-ng"), ("pacman", "procps-ng"), ("brew", "watch")]),
+    ("wget", "wget", &[]),
+    ("yq", "yq", &[("apt", "yq"), ("nix", "yq-go")]),
+    ("zip", "zip", &[]),
+];
+
+/// A program the command runs that isn't installed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingBinary {
+    pub binary: String,
+    /// Command that installs it with the detected package manager
+    pub install: Option<String>,
+}
+
+impl fmt::Display for MissingBinary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.install {
+            Some(install) => write!(f, "{} is not installed (install it with: {})", self.binary, install),
+            None => write!(f, "{} is not installed", self.binary),
+        }
+    }
+}
+
+/// Resolves the programs of generated commands against this machine
+#[derive(Debug, Default)]
+pub struct BinaryCheck {
+    /// Aliases and functions from the shell's startup files
+    definitions: HashSet<String>,
+    package_manager: Option<String>,
+    /// `sudo` or `doas`, for package managers that need root
+    privilege_command: Option<String>,
+}
+
+impl BinaryCheck {
+    pub fn new(home_dir: Option<&Path>, system_info: &SystemInfo) -> Self {
+        let package_manager = system_info
+            .system_package_manager()
+            .or_else(|| {
+                system_info
+                    .package_managers
+                    .iter()
+                    .map(String::as_str)
+                    .find(|pm| matches!(*pm, "brew" | "nix"))
+            })
+            .map(str::to_string);
+
+        Self {
+            definitions: home_dir
+                .map(|home| shell_definitions(home, system_info.shell.as_deref()))
+                .unwrap_or_default(),
+            package_manager,
+            privilege_command: system_info.privilege_command.clone(),
+        }
+    }
+
+    /// Programs in `command` that are neither installed, builtins nor shell definitions
+    pub fn missing(&self, command: &str) -> Vec<MissingBinary> {
+        self.missing_with(command, find_in_path)
+    }
+
+    fn missing_with(&self, command: &str, installed: impl Fn(&str) -> bool) -> Vec<MissingBinary> {
+        let mut seen = HashSet::new();
+        invoked_programs(command)
+            .into_iter()
+            .filter(|program| seen.insert(program.clone()))
+            // Paths and variables (`./build.sh`, `$EDITOR`) can't be judged here
+            .filter(|program| !program.contains(['/', '$', '=', '(', '`']))
+            .filter(|program| {
+                !SHELL_BUILTINS.contains(&program.as_str()) && !self.definitions.contains(program) && !installed(program)
+            })
+            .map(|binary| MissingBinary {
+                install: self.install_command(&binary),
+                binary,
+            })
+            .collect()
+    }
+
+    /// e.g. `sudo apt install fd-find` for `fd` on Debian
+    fn install_command(&self, binary: &str) -> Option<String> {
+        let pm = self.package_manager.as_deref()?;
+        let package = package_for(binary, pm);
+        let sudo = self.privilege_command.as_deref().unwrap_or("sudo");
+
+        Some(match pm {
+            "apt" | "dnf" | "yum" | "zypper" => format!("{} {} install {}", sudo, pm, package),
+            "pacman" => format!("{} pacman -S {}", sudo, package),
+            "apk" => format!("{} apk add {}", sudo, package),
+            "brew" => format!("brew install {}", package),
+            "nix" => format!("nix profile install nixpkgs#{}", package),
+            _ => return None,
+        })
+    }
+}
+
+/// The package providing `binary` under `package_manager`, defaulting to the binary's name
+pub fn package_for<'a>(binary: &'a str, package_manager: &str) -> &'a str {
+    match PACKAGES.iter().find(|(name, _, _)| *name == binary) {
+        Some((_, package, overrides)) => overrides
+            .iter()
+            .find(|(pm, _)| *pm == package_manager)
+            .map_or(*package, |(_, package)| *package),
+        None => binary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(package_manager: Option<&str>) -> BinaryCheck {
+        BinaryCheck {
+            definitions: ["ll".to_string(), "mkcd".to_string()].into_iter().collect(),
+            package_manager: package_manager.map(str::to_string),
+            privilege_command: Some("doas".to_string()),
+        }
+    }
+
+    fn installed(program: &str) -> bool {
+        ["ls", "find", "grep", "sleep", "sudo", "xargs"].contains(&program)
+    }
+
+    #[test]
+    fn test_missing_binaries() {
+        let check = check(Some("apt"));
+        assert!(check.missing_with("cd src && ll | grep foo; mkcd out", installed).is_empty());
+        assert!(check.missing_with("./build.sh && $EDITOR notes.txt", installed).is_empty());
+        assert!(check.missing_with("for f in *; do echo \"$f\"; done", installed).is_empty());
+        assert!(check.missing_with("while read -r l; do echo \"$l\"; done < f", installed).is_empty());
+        assert!(check.missing_with("until false; do sleep 1; done", installed).is_empty());
+        assert!(check.missing_with("sleep 5 & wait", installed).is_empty());
+
+        assert_eq!(
+            check.missing_with("sudo fd -e log | xargs jq . && fd -e txt", installed),
+            vec![
+                MissingBinary {
+                    binary: "fd".to_string(),
+                    install: Some("doas apt install fd-find".to_string()),
+                },
+                MissingBinary {
+                    binary: "jq".to_string(),
+                    install: Some("doas apt install jq".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_install_suggestions() {
+        assert_eq!(check(Some("brew")).install_command("rg").as_deref(), Some("brew install ripgrep"));
+        assert_eq!(
+            check(Some("nix")).install_command("yq").as_deref(),
+            Some("nix profile install nixpkgs#yq-go")
+        );
+        assert_eq!(check(Some("pacman")).install_command("dig").as_deref(), Some("doas pacman -S bind"));
+        assert_eq!(check(None).install_command("fd"), None);
+        assert_eq!(
+            check(None).missing_with("fd", installed)[0].to_string(),
+            "fd is not installed"
+        );
+        assert_eq!(package_for("htop", "apt"), "htop");
+    }
+}
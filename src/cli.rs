@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "ez")]
@@ -17,6 +18,10 @@ pub struct Cli {
     #[arg(short, long)]
     pub model: Option<String>,
 
+    /// Profile to use (see `ez profile list`); overrides EZ_PROFILE and directory matching
+    #[arg(short, long, value_name = "NAME")]
+    pub profile: Option<String>,
+
     /// List available backends
     #[arg(long)]
     pub list_backends: bool,
@@ -33,6 +38,18 @@ pub struct Cli {
     #[arg(long)]
     pub set_model: Option<String>,
 
+    /// Run a read-only preview of destructive commands (find -delete, rm, mv, sed -i)
+    #[arg(long)]
+    pub preview: bool,
+
+    /// Trial-run the generated command in a throwaway sandbox and report what it changed (Linux)
+    #[arg(long = "try")]
+    pub try_run: bool,
+
+    /// Report context providers that were skipped (not detected, timed out) on stderr
+    #[arg(short, long)]
+    pub verbose: bool,
+
     /// Update ez to the latest version
     #[arg(long)]
     pub update: bool,
@@ -49,4 +66,130 @@ pub struct Cli {
 pub enum Commands {
     /// Run interactive setup wizard to configure ez-term
     Init,
+    /// Inspect the context that is sent to the LLM
+    Context {
+        /// Print exactly what would be sent to the backend (after secret redaction)
+        #[arg(long)]
+        show: bool,
+
+        /// Query to gather agentic context for
+        #[arg(value_name = "QUERY")]
+        query: Option<String>,
+    },
+    /// Inspect and change the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// List, select and create named profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Score the current backend, model and prompt against a YAML corpus of queries
+    Eval(EvalArgs),
+    /// Manage the offline index of installed tools' flags
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the effective configuration
+    Show {
+        /// Annotate each value with the layer that set it
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Print the effective value of a key (e.g. backend, privacy.groq)
+    Get {
+        key: String,
+    },
+    /// Set a key in your config file, after checking it against the schema
+    Set {
+        key: String,
+        /// Value; lists and tables use TOML inline syntax
+        value: String,
+    },
+    /// Remove a key from your config file
+    Unset {
+        key: String,
+    },
+    /// List every key with its type and effective value
+    List,
+    /// Open your config file in $VISUAL or $EDITOR and validate it before saving
+    Edit,
+    /// Check the system, user and project config files
+    Validate,
+    /// Print the path of your config file
+    Path,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileAction {
+    /// List profiles and show which one is active here
+    List,
+    /// Make a profile the default when no flag, variable or directory selects one
+    Use {
+        name: String,
+    },
+    /// Add a profile to your config file
+    Create {
+        name: String,
+        /// Backend (ollama, groq, openai)
+        #[arg(long)]
+        backend: Option<String>,
+        #[arg(long)]
+        model: Option<String>,
+        #[arg(long, value_name = "URL")]
+        ollama_url: Option<String>,
+        /// OpenAI-compatible endpoint, e.g. a vLLM server
+        #[arg(long, value_name = "URL")]
+        openai_url: Option<String>,
+        /// Privacy level for the profile's backend (full, minimal, none)
+        #[arg(long, value_name = "LEVEL")]
+        privacy: Option<String>,
+        #[arg(long, value_name = "MODE")]
+        flag_check: Option<String>,
+        #[arg(long, value_name = "MODE")]
+        binary_check: Option<String>,
+        /// Select the profile inside this directory (repeatable)
+        #[arg(long = "path", value_name = "DIR")]
+        paths: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexAction {
+    /// Index the man pages and --help output of installed tools
+    Build {
+        /// Also embed each entry with this Ollama model (e.g. nomic-embed-text) for semantic search
+        #[arg(long, value_name = "MODEL")]
+        embeddings: Option<String>,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct EvalArgs {
+    /// YAML corpus of queries and expectations (see eval/corpus.yaml)
+    #[arg(value_name = "CORPUS")]
+    pub corpus: PathBuf,
+
+    /// Only run cases whose id contains this
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    /// Exit with an error if fewer than this percentage of cases pass
+    #[arg(long, value_name = "PERCENT")]
+    pub min_pass_rate: Option<f64>,
+
+    /// Don't trial-run commands for `sandbox: true` expectations (left out of the report)
+    #[arg(long)]
+    pub no_sandbox: bool,
 }
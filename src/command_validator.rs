@@ -1,12 +1,23 @@
 use anyhow::{anyhow, Result};
+use serde::Deserialize;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RiskLevel {
     Safe,      // Normal commands, no warning
     Medium,    // Potentially risky, warn user
     Critical,  // Extremely dangerous, block completely
 }
 
+/// A single simple command extracted from a shell command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Arguments as written, with quoting preserved (used to rebuild commands)
+    pub raw_args: Vec<String>,
+}
+
 pub struct CommandValidator {
     critical_patterns: Vec<&'static str>,
     risky_patterns: Vec<&'static str>,
@@ -96,6 +107,156 @@ impl CommandValidator {
         Ok(RiskLevel::Safe)
     }
 
+    /// Split a command line into its simple commands
+    ///
+    /// Segments are separated by pipes, `&&`, `||`, `;` and `&`. Quotes are
+    /// honored when splitting words, and leading `VAR=value` assignments are
+    /// skipped so `program` is the binary that actually runs. Redirections
+    /// (`> out.txt`, `2>&1`, `&>> log`, `< in.txt`) are dropped along with
+    /// their targets, so `args` only holds what the program itself receives.
+    pub fn parse(command: &str) -> Vec<ParsedCommand> {
+        let mut commands = Vec::new();
+        let mut words: Vec<(String, String)> = Vec::new();
+        let mut raw = String::new();
+        let mut value = String::new();
+        let mut in_word = false;
+        // Set after a redirection operator: the next word is its target
+        let mut redirect = false;
+        let mut chars = command.chars().peekable();
+
+        fn finish_word(
+            words: &mut Vec<(String, String)>,
+            raw: &mut String,
+            value: &mut String,
+            in_word: &mut bool,
+            redirect: &mut bool,
+        ) {
+            if *in_word {
+                let word = (std::mem::take(raw), std::mem::take(value));
+                if *redirect {
+                    *redirect = false;
+                } else {
+                    words.push(word);
+                }
+                *in_word = false;
+            }
+        }
+
+        fn finish_command(words: &mut Vec<(String, String)>, commands: &mut Vec<ParsedCommand>) {
+            let mut iter = words
+                .drain(..)
+                .skip_while(|(raw, _)| CommandValidator::is_assignment(raw));
+            if let Some((_, program)) = iter.next() {
+                let (raw_args, args) = iter.unzip();
+                commands.push(ParsedCommand { program, args, raw_args });
+            }
+        }
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' => {
+                    in_word = true;
+                    raw.push(c);
+                    for q in chars.by_ref() {
+                        raw.push(q);
+                        if q == '\'' {
+                            break;
+                        }
+                        value.push(q);
+                    }
+                }
+                '"' => {
+                    in_word = true;
+                    raw.push(c);
+                    while let Some(q) = chars.next() {
+                        raw.push(q);
+                        match q {
+                            '"' => break,
+                            '\\' => {
+                                if let Some(escaped) = chars.next() {
+                                    raw.push(escaped);
+                                    value.push(escaped);
+                                }
+                            }
+                            _ => value.push(q),
+                        }
+                    }
+                }
+                '\\' => {
+                    in_word = true;
+                    raw.push(c);
+                    if let Some(escaped) = chars.next() {
+                        raw.push(escaped);
+                        value.push(escaped);
+                    }
+                }
+                '>' | '<' => {
+                    // A number right before the operator is the file descriptor (`2>`)
+                    if in_word && raw == value && value.chars().all(|d| d.is_ascii_digit()) {
+                        raw.clear();
+                        value.clear();
+                        in_word = false;
+                    }
+                    finish_word(&mut words, &mut raw, &mut value, &mut in_word, &mut redirect);
+                    // `>>`, `>|`, `>&`, `<<`, `<<<`, `<>` and `<&`
+                    while let Some(&next) = chars.peek() {
+                        let continues = match c {
+                            '>' => matches!(next, '>' | '|' | '&'),
+                            _ => matches!(next, '<' | '>' | '&'),
+                        };
+                        if !continues {
+                            break;
+                        }
+                        chars.next();
+                    }
+                    redirect = true;
+                }
+                '&' if chars.peek() == Some(&'>') => {
+                    // `&>` and `&>>` redirect both stdout and stderr
+                    finish_word(&mut words, &mut raw, &mut value, &mut in_word, &mut redirect);
+                    chars.next();
+                    if chars.peek() == Some(&'>') {
+                        chars.next();
+                    }
+                    redirect = true;
+                }
+                '|' | '&' | ';' => {
+                    finish_word(&mut words, &mut raw, &mut value, &mut in_word, &mut redirect);
+                    // `&&`, `||`, `;;` and `|&` are single separators
+                    if chars.peek() == Some(&c) || (c == '|' && chars.peek() == Some(&'&')) {
+                        chars.next();
+                    }
+                    redirect = false;
+                    finish_command(&mut words, &mut commands);
+                }
+                c if c.is_whitespace() => {
+                    finish_word(&mut words, &mut raw, &mut value, &mut in_word, &mut redirect);
+                }
+                _ => {
+                    in_word = true;
+                    raw.push(c);
+                    value.push(c);
+                }
+            }
+        }
+
+        finish_word(&mut words, &mut raw, &mut value, &mut in_word, &mut redirect);
+        finish_command(&mut words, &mut commands);
+        commands
+    }
+
+    /// Check whether a word is a `VAR=value` environment assignment
+    fn is_assignment(word: &str) -> bool {
+        match word.split_once('=') {
+            Some((name, _)) => {
+                !name.is_empty()
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    && !name.starts_with(|c: char| c.is_ascii_digit())
+            }
+            None => false,
+        }
+    }
+
     /// Check if command matches a pattern (supports regex)
     fn matches_pattern(command: &str, pattern: &str) -> bool {
         // Try as regex first (patterns with \s, $, \\, etc.)
@@ -243,6 +404,38 @@ mod tests {
         assert_eq!(validator.validate("mkdir test").unwrap(), RiskLevel::Safe);
     }
 
+    #[test]
+    fn test_parse_splits_segments() {
+        let parsed = CommandValidator::parse("find . -name '*.log' | xargs wc -l && echo done; ls");
+        let programs: Vec<&str> = parsed.iter().map(|c| c.program.as_str()).collect();
+        assert_eq!(programs, vec!["find", "xargs", "echo", "ls"]);
+        assert_eq!(parsed[0].args, vec![".", "-name", "*.log"]);
+        assert_eq!(parsed[0].raw_args, vec![".", "-name", "'*.log'"]);
+    }
+
+    #[test]
+    fn test_parse_quotes_and_assignments() {
+        let parsed = CommandValidator::parse("LC_ALL=C sed -i \"s/a b/c/\" \"my file.txt\"");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].program, "sed");
+        assert_eq!(parsed[0].args, vec!["-i", "s/a b/c/", "my file.txt"]);
+        assert!(CommandValidator::parse("   ").is_empty());
+    }
+
+    #[test]
+    fn test_parse_drops_redirections() {
+        let parsed = CommandValidator::parse("make 2>&1 | grep -v warning > build.log && cat<in.txt &>>all.log");
+        let programs: Vec<&str> = parsed.iter().map(|c| c.program.as_str()).collect();
+        assert_eq!(programs, vec!["make", "grep", "cat"]);
+        assert!(parsed[0].args.is_empty());
+        assert_eq!(parsed[1].args, vec!["-v", "warning"]);
+        assert!(parsed[2].args.is_empty());
+
+        let parsed = CommandValidator::parse("rm -rf build >/dev/null 2>& 1 & echo 2 '>' x");
+        assert_eq!(parsed[0].args, vec!["-rf", "build"]);
+        assert_eq!(parsed[1].args, vec!["2", ">", "x"]);
+    }
+
     #[test]
     fn test_pattern_matching() {
         // Test that risky pattern "rm -rf" matches "rm -rf /tmp/test"
@@ -0,0 +1,611 @@
+//! Schema of `config.toml`, recorded from `Config`'s `Deserialize` impl
+//!
+//! Rather than keeping a second list of keys in sync with the struct, the
+//! schema is read off serde: a recording deserializer hands `Config` a
+//! placeholder for every field it asks for and notes what shape it asked for
+//! (string, enum variants, nested table, list). `ez config` uses it to check
+//! keys and values before anything is written.
+
+use anyhow::{anyhow, bail, Result};
+use serde::de::{self, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use toml::{Table, Value};
+
+use crate::config::Config;
+use crate::llm_client::Backend;
+use crate::process_util::is_bare_name;
+
+/// Keys whose allowed values aren't visible in their type, by last segment
+const KEY_HINTS: [(&str, &str); 3] = [
+    ("backend", "ollama | groq | openai"),
+    ("ollama_url", "http(s) URL"),
+    ("openai_url", "http(s) URL"),
+];
+
+/// Keys `ez config set` refuses, with the reason
+const READ_ONLY_KEYS: [(&str, &str); 4] = [
+    ("groq_api_key", "API keys belong in the system keyring: run `ez init`"),
+    ("openai_api_key", "API keys belong in the system keyring: run `ez init`"),
+    ("migration_completed", "it is managed by ez"),
+    ("migration_declined", "it is managed by ez"),
+];
+
+/// What a config value has to look like
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Bool,
+    Integer,
+    Float,
+    String,
+    /// One of a unit enum's variants, e.g. `CheckMode`
+    Choice(Vec<&'static str>),
+    List(Box<Shape>),
+    Table(Vec<(&'static str, Shape)>),
+    /// A table with keys of the user's choosing, such as `[profiles.<name>]`
+    Map(Box<Shape>),
+    /// Anything; not checked further
+    Any,
+}
+
+impl Shape {
+    /// The shape `T` deserializes from
+    pub fn of<'de, T: Deserialize<'de>>() -> Shape {
+        let slot = Rc::new(RefCell::new(Shape::Any));
+        // The placeholder values may not make a valid `T`; only the recording matters
+        let _ = T::deserialize(Recorder(slot.clone()));
+        let shape = slot.borrow().clone();
+        shape
+    }
+
+    /// The shape at a dotted key such as `privacy.groq`
+    pub fn lookup(&self, key: &str) -> Option<&Shape> {
+        key.split('.').try_fold(self, |shape, part| match shape {
+            Shape::Table(fields) => fields.iter().find(|(name, _)| *name == part).map(|(_, shape)| shape),
+            Shape::Map(value) => Some(&**value),
+            _ => None,
+        })
+    }
+
+    /// Human-readable type, as shown by `ez config list`
+    pub fn describe(&self) -> String {
+        match self {
+            Shape::Bool => "true | false".to_string(),
+            Shape::Integer => "integer".to_string(),
+            Shape::Float => "number".to_string(),
+            Shape::String => "string".to_string(),
+            Shape::Choice(variants) => variants.join(" | "),
+            Shape::List(element) if matches!(**element, Shape::Table(_)) => "list of tables".to_string(),
+            Shape::List(element) => format!("list of {}", element.describe()),
+            Shape::Table(_) => "table".to_string(),
+            Shape::Map(value) => format!("table of {}", value.describe()),
+            Shape::Any => "any".to_string(),
+        }
+    }
+
+    /// Append a message to `errors` for every part of `value` that doesn't fit
+    fn check(&self, value: &Value, path: &str, errors: &mut Vec<String>) {
+        let fits = match (self, value) {
+            (Shape::Any, _) | (Shape::Bool, Value::Boolean(_)) | (Shape::String, Value::String(_)) => true,
+            (Shape::Integer, Value::Integer(_)) | (Shape::Float, Value::Float(_) | Value::Integer(_)) => true,
+            (Shape::Choice(variants), Value::String(s)) => {
+                if !variants.contains(&s.as_str()) {
+                    errors.push(format!("{}: expected one of {}, got \"{}\"", path, variants.join(", "), s));
+                }
+                true
+            }
+            (Shape::List(element), Value::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    element.check(item, &format!("{}[{}]", path, i), errors);
+                }
+                true
+            }
+            (Shape::Table(fields), Value::Table(table)) => {
+                for (key, value) in table {
+                    let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                    match fields.iter().find(|(name, _)| name == key) {
+                        Some((_, shape)) => shape.check(value, &key_path, errors),
+                        None => errors.push(format!("unknown key {}", key_path)),
+                    }
+                }
+                true
+            }
+            (Shape::Map(shape), Value::Table(table)) => {
+                for (key, value) in table {
+                    shape.check(value, &format!("{}.{}", path, key), errors);
+                }
+                true
+            }
+            _ => false,
+        };
+        if !fits {
+            errors.push(format!("{}: expected {}, got {}", path, self.describe(), value.type_str()));
+        }
+    }
+}
+
+/// The schema of `Config`
+pub fn schema() -> &'static Shape {
+    static SCHEMA: OnceLock<Shape> = OnceLock::new();
+    SCHEMA.get_or_init(Shape::of::<Config>)
+}
+
+/// Every settable key with its type: tables are expanded, lists are set whole
+pub fn keys() -> Vec<(String, String)> {
+    fn walk(shape: &Shape, prefix: &str, out: &mut Vec<(String, String)>) {
+        let Shape::Table(fields) = shape else { return };
+        for (name, shape) in fields {
+            let key = if prefix.is_empty() { name.to_string() } else { format!("{}.{}", prefix, name) };
+            match shape {
+                Shape::Table(_) => walk(shape, &key, out),
+                Shape::Map(value) => walk(value, &format!("{}.<name>", key), out),
+                _ => {
+                    let hint = KEY_HINTS.iter().find(|(k, _)| k == name).map(|(_, hint)| hint.to_string());
+                    out.push((key, hint.unwrap_or_else(|| shape.describe())));
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(schema(), "", &mut out);
+    out
+}
+
+/// Parse the command-line `raw` into a value for `key`
+///
+/// Strings and enum values are taken as typed; lists and tables use TOML
+/// inline syntax (`'["deploy", "rollout"]'`).
+pub fn parse_value(key: &str, raw: &str) -> Result<Value> {
+    let shape = schema()
+        .lookup(key)
+        .ok_or_else(|| anyhow!("Unknown config key '{}' (see `ez config list`)", key))?;
+    if let Some((_, reason)) = READ_ONLY_KEYS.iter().find(|(k, _)| *k == key) {
+        bail!("Can't set {}: {}", key, reason);
+    }
+
+    let value = match shape {
+        Shape::String | Shape::Choice(_) => Value::String(raw.to_string()),
+        Shape::Bool => Value::Boolean(raw.parse().map_err(|_| anyhow!("{}: expected true or false", key))?),
+        Shape::Integer => Value::Integer(raw.parse().map_err(|_| anyhow!("{}: expected an integer", key))?),
+        Shape::Float => Value::Float(raw.parse().map_err(|_| anyhow!("{}: expected a number", key))?),
+        Shape::List(_) | Shape::Table(_) | Shape::Map(_) | Shape::Any => {
+            let mut parsed: Table = toml::from_str(&format!("value = {}", raw))
+                .map_err(|e| anyhow!("{}: not a TOML value: {}", key, e.message()))?;
+            parsed.remove("value").unwrap_or(Value::String(raw.to_string()))
+        }
+    };
+
+    let mut errors = Vec::new();
+    shape.check(&value, key, &mut errors);
+    match errors.is_empty() {
+        true => Ok(value),
+        false => bail!(errors.join("\n")),
+    }
+}
+
+/// Everything wrong with a config file's contents: unknown keys, values of
+/// the wrong type, an unknown backend or a malformed URL
+pub fn validate(table: &Table) -> Vec<String> {
+    let mut errors = Vec::new();
+    schema().check(&Value::Table(table.clone()), "", &mut errors);
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    let config: Config = match Value::Table(table.clone()).try_into() {
+        Ok(config) => config,
+        Err(e) => return vec![e.to_string()],
+    };
+
+    let endpoints = std::iter::once((String::new(), &config.backend, &config.model, &config.ollama_url, &config.openai_url))
+        .chain(config.profiles.iter().map(|(name, p)| {
+            (format!("profiles.{}.", name), &p.backend, &p.model, &p.ollama_url, &p.openai_url)
+        }));
+    for (prefix, backend, model, ollama_url, openai_url) in endpoints {
+        check_endpoint(
+            &prefix,
+            backend.as_deref(),
+            model.as_deref(),
+            [("ollama_url", ollama_url.as_deref()), ("openai_url", openai_url.as_deref())],
+            &mut errors,
+        );
+    }
+    errors.extend(config.prompts.validate());
+    errors.extend(config.tools.iter().enumerate().filter(|(_, tool)| !is_bare_name(&tool.name)).map(|(i, tool)| {
+        format!("tools[{}].name: {:?} must be a plain file name, not a path", i, tool.name)
+    }));
+    errors
+}
+
+/// Check the backend name, model and URLs set at the top level or in a profile
+fn check_endpoint(
+    prefix: &str,
+    backend: Option<&str>,
+    model: Option<&str>,
+    urls: [(&str, Option<&str>); 2],
+    errors: &mut Vec<String>,
+) {
+    if let Some(backend) = backend {
+        if Backend::from_str(backend).is_err() {
+            errors.push(format!("{}backend: unknown backend \"{}\" (expected ollama, groq or openai)", prefix, backend));
+        }
+    }
+    if model.is_some_and(|model| model.trim().is_empty()) {
+        errors.push(format!("{}model: must not be empty", prefix));
+    }
+    for (key, url) in urls {
+        let Some(url) = url else { continue };
+        if let Err(reason) = check_url(url) {
+            errors.push(format!("{}{}: \"{}\" {}", prefix, key, url, reason));
+        }
+    }
+}
+
+fn check_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("is not a valid URL ({})", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("must start with http:// or https://".to_string());
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err("has no host".to_string());
+    }
+    Ok(())
+}
+
+/// Insert `value` at the dotted `key`, creating intermediate tables
+pub fn set_key(table: &mut Table, key: &str, value: Value) {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let entry = table.entry(head).or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            if let Value::Table(inner) = entry {
+                set_key(inner, rest, value);
+            }
+        }
+        None => {
+            table.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Remove the dotted `key`, dropping tables it leaves empty; returns whether it was set
+pub fn remove_key(table: &mut Table, key: &str) -> bool {
+    match key.split_once('.') {
+        Some((head, rest)) => {
+            let Some(Value::Table(inner)) = table.get_mut(head) else { return false };
+            let removed = remove_key(inner, rest);
+            if inner.is_empty() {
+                table.remove(head);
+            }
+            removed
+        }
+        None => table.remove(key).is_some(),
+    }
+}
+
+/// Error from the recording deserializer; never shown to users
+#[derive(Debug)]
+struct RecordError(String);
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl de::Error for RecordError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        RecordError(msg.to_string())
+    }
+}
+
+/// Deserializer that writes the shape it is asked for into its slot and
+/// answers with a placeholder of that shape
+struct Recorder(Rc<RefCell<Shape>>);
+
+impl Recorder {
+    fn record(&self, shape: Shape) {
+        *self.0.borrow_mut() = shape;
+    }
+}
+
+macro_rules! record_integers {
+    ($($method:ident => $visit:ident($zero:expr)),*) => {
+        $(fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+            self.record(Shape::Integer);
+            visitor.$visit($zero)
+        })*
+    };
+}
+
+impl<'de> Deserializer<'de> for Recorder {
+    type Error = RecordError;
+
+    record_integers!(
+        deserialize_i8 => visit_i64(0), deserialize_i16 => visit_i64(0), deserialize_i32 => visit_i64(0),
+        deserialize_i64 => visit_i64(0), deserialize_u8 => visit_u64(0), deserialize_u16 => visit_u64(0),
+        deserialize_u32 => visit_u64(0), deserialize_u64 => visit_u64(0)
+    );
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.record(Shape::Any);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.record(Shape::Bool);
+        visitor.visit_bool(false)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.record(Shape::Float);
+        visitor.visit_f64(0.0)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.record(Shape::String);
+        visitor.visit_str("")
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.record(Shape::Any);
+        visitor.visit_bytes(&[])
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        // An optional value has the shape of what it wraps
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, RecordError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        let element = Rc::new(RefCell::new(Shape::Any));
+        let result = visitor.visit_seq(OneElement(Some(element.clone())));
+        self.record(Shape::List(Box::new(element.borrow().clone())));
+        result
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        // One entry, so the shape of the values gets recorded
+        let value = Rc::new(RefCell::new(Shape::Any));
+        let result = visitor.visit_map(Fields { fields: &["<name>"], slots: vec![value.clone()], next: 0 });
+        self.record(Shape::Map(Box::new(value.borrow().clone())));
+        result
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        let slots: Vec<_> = fields.iter().map(|_| Rc::new(RefCell::new(Shape::Any))).collect();
+        let result = visitor.visit_map(Fields { fields, slots: slots.clone(), next: 0 });
+        let shapes = fields.iter().zip(&slots).map(|(name, slot)| (*name, slot.borrow().clone()));
+        self.record(Shape::Table(shapes.collect()));
+        result
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        self.record(Shape::Choice(variants.to_vec()));
+        let first = variants.first().copied().ok_or_else(|| RecordError("enum without variants".to_string()))?;
+        visitor.visit_enum(FirstVariant(first))
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        visitor.visit_unit()
+    }
+}
+
+/// A struct's fields, each answered by a `Recorder`
+struct Fields {
+    fields: &'static [&'static str],
+    slots: Vec<Rc<RefCell<Shape>>>,
+    next: usize,
+}
+
+impl<'de> MapAccess<'de> for Fields {
+    type Error = RecordError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, RecordError> {
+        match self.fields.get(self.next) {
+            Some(field) => seed.deserialize(field.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, RecordError> {
+        let slot = self.slots[self.next].clone();
+        self.next += 1;
+        seed.deserialize(Recorder(slot))
+    }
+}
+
+/// A sequence of one element, so the element's shape gets recorded
+struct OneElement(Option<Rc<RefCell<Shape>>>);
+
+impl<'de> SeqAccess<'de> for OneElement {
+    type Error = RecordError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, RecordError> {
+        match self.0.take() {
+            Some(slot) => seed.deserialize(Recorder(slot)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Answers an enum with its first variant
+struct FirstVariant(&'static str);
+
+impl<'de> EnumAccess<'de> for FirstVariant {
+    type Error = RecordError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), RecordError> {
+        let variant = seed.deserialize(self.0.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for FirstVariant {
+    type Error = RecordError;
+
+    fn unit_variant(self) -> Result<(), RecordError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, RecordError> {
+        seed.deserialize(Recorder(Rc::new(RefCell::new(Shape::Any))))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, RecordError> {
+        Err(RecordError("tuple variants are not supported".to_string()))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        Err(RecordError("struct variants are not supported".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(toml: &str) -> Table {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn test_schema_from_config() {
+        let schema = schema();
+        assert_eq!(schema.lookup("backend"), Some(&Shape::String));
+        assert_eq!(schema.lookup("migration_completed"), Some(&Shape::Bool));
+        assert_eq!(schema.lookup("flag_check"), Some(&Shape::Choice(vec!["off", "warn", "regenerate"])));
+        assert_eq!(schema.lookup("privacy.groq"), Some(&Shape::Choice(vec!["none", "minimal", "full"])));
+        assert!(matches!(schema.lookup("context_providers"), Some(Shape::List(_))));
+        assert_eq!(
+            schema.lookup("tools").map(Shape::describe).as_deref(),
+            Some("list of tables")
+        );
+        assert_eq!(schema.lookup("privacy.nope"), None);
+
+        let keys = keys();
+        assert!(keys.contains(&("backend".to_string(), "ollama | groq | openai".to_string())));
+        assert!(keys.contains(&("privacy.overrides".to_string(), "list of tables".to_string())));
+        assert!(!keys.iter().any(|(key, _)| key == "privacy"));
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("model", "llama3").unwrap(), Value::String("llama3".to_string()));
+        assert_eq!(parse_value("binary_check", "regenerate").unwrap(), Value::String("regenerate".to_string()));
+        assert!(parse_value("binary_check", "always").unwrap_err().to_string().contains("off, warn, regenerate"));
+        assert!(parse_value("bakend", "ollama").unwrap_err().to_string().contains("Unknown config key"));
+        assert!(parse_value("groq_api_key", "gsk_x").unwrap_err().to_string().contains("keyring"));
+
+        let tools = parse_value("tools", r#"[{ name = "deployctl", keywords = ["deploy"] }]"#).unwrap();
+        assert_eq!(tools.as_array().map(Vec::len), Some(1));
+        assert!(parse_value("tools", r#"[{ name = "x", colour = "red" }]"#)
+            .unwrap_err()
+            .to_string()
+            .contains("unknown key tools[0].colour"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&table("backend = \"ollama\"\nollama_url = \"http://gpu-box:11434\"\n")).is_empty());
+
+        assert_eq!(
+            validate(&table("backend = \"olama\"\nollama_url = \"gpu-box:11434\"\n")),
+            vec![
+                "backend: unknown backend \"olama\" (expected ollama, groq or openai)",
+                "ollama_url: \"gpu-box:11434\" must start with http:// or https://",
+            ]
+        );
+        assert_eq!(validate(&table("modle = \"x\"\n")), vec!["unknown key modle"]);
+        assert_eq!(
+            validate(&table("[privacy]\ngroq = \"low\"\n")),
+            vec!["privacy.groq: expected one of none, minimal, full, got \"low\""]
+        );
+        assert_eq!(validate(&table("model = 3\n")), vec!["model: expected string, got integer"]);
+        assert!(validate(&table("[[tools]]\ncategory = \"x\"\n"))[0].contains("name"));
+        assert_eq!(
+            validate(&table("[[tools]]\nname = \"/opt/bin/deployctl\"\n")),
+            vec!["tools[0].name: \"/opt/bin/deployctl\" must be a plain file name, not a path"]
+        );
+    }
+
+    #[test]
+    fn test_set_and_remove_keys() {
+        let mut config = table("backend = \"groq\"\n");
+        set_key(&mut config, "privacy.groq", Value::String("minimal".to_string()));
+        assert_eq!(config["privacy"]["groq"].as_str(), Some("minimal"));
+
+        assert!(remove_key(&mut config, "privacy.groq"));
+        assert!(!config.contains_key("privacy"));
+        assert!(!remove_key(&mut config, "model"));
+        assert_eq!(config, table("backend = \"groq\"\n"));
+    }
+}
@@ -0,0 +1,230 @@
+//! Which implementation of sed, find, date, grep and awk is installed
+//!
+//! GNU, BSD and BusyBox versions of these disagree on flags (`sed -i`,
+//! `find -printf`, `date -d`), so the OS alone is not enough to pick them.
+
+use regex::Regex;
+use std::fmt;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use crate::process_util::{output_with_timeout, which};
+
+/// How long a `--version` probe may take
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Utility probed for each package, e.g. `date` stands in for coreutils
+const PROBES: [(&str, &str); 5] = [
+    ("coreutils", "date"),
+    ("findutils", "find"),
+    ("sed", "sed"),
+    ("grep", "grep"),
+    ("awk", "awk"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    Gnu,
+    Bsd,
+    BusyBox,
+    /// The Rust rewrite some distributions ship as coreutils
+    Uutils,
+    Mawk,
+    Unknown,
+}
+
+impl fmt::Display for Flavor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Flavor::Gnu => "GNU",
+            Flavor::Bsd => "BSD",
+            Flavor::BusyBox => "BusyBox",
+            Flavor::Uutils => "uutils",
+            Flavor::Mawk => "mawk",
+            Flavor::Unknown => "unknown",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreUtil {
+    /// Package name, e.g. `findutils`
+    pub package: String,
+    /// Binary that was probed, e.g. `find`
+    pub binary: String,
+    pub flavor: Flavor,
+    pub version: Option<String>,
+}
+
+impl CoreUtil {
+    /// `sed is BusyBox 1.36.1`
+    pub fn describe(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} is {} {}", self.binary, self.flavor, version),
+            None => format!("{} is {}", self.binary, self.flavor),
+        }
+    }
+
+    /// Flag advice for implementations that differ from GNU
+    pub fn hint(&self) -> Option<&'static str> {
+        match (self.binary.as_str(), self.flavor) {
+            ("sed", Flavor::Bsd) => Some("BSD sed: in-place edits need an explicit suffix, sed -i '' 's/a/b/' file"),
+            ("find", Flavor::Bsd) => Some("BSD find: no -printf; use -exec stat or -print0 | xargs -0"),
+            ("date", Flavor::Bsd) => Some("BSD date: no -d; use date -v-1d or date -j -f"),
+            ("find", Flavor::BusyBox) => Some("BusyBox find: no -printf or -regextype; use -exec stat -c"),
+            ("date", Flavor::BusyBox) => Some("BusyBox date: -d only parses a few formats, e.g. date -d @1700000000"),
+            _ => None,
+        }
+    }
+}
+
+/// Classify `--version` output (stdout and stderr together)
+///
+/// BSD tools reject `--version`, so anything unrecognised on a BSD-derived
+/// OS counts as BSD.
+pub fn classify(output: &str, bsd_os: bool) -> (Flavor, Option<String>) {
+    static VERSION: OnceLock<Regex> = OnceLock::new();
+    let version_re = VERSION.get_or_init(|| Regex::new(r"\d+(?:\.\d+)+").unwrap());
+
+    let first_line = output.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+    let version = version_re.find(first_line).map(|m| m.as_str().to_string());
+
+    let flavor = if output.contains("BusyBox") || output.contains("not GNU sed") {
+        Flavor::BusyBox
+    } else if output.contains("uutils") {
+        Flavor::Uutils
+    } else if first_line.contains("GNU") {
+        Flavor::Gnu
+    } else if first_line.contains("mawk") {
+        Flavor::Mawk
+    } else if first_line.starts_with("awk version") || first_line.contains("BSD") || bsd_os {
+        // "awk version 20200816" is the one-true-awk macOS and the BSDs ship
+        Flavor::Bsd
+    } else {
+        Flavor::Unknown
+    };
+
+    match flavor {
+        Flavor::Unknown => (flavor, None),
+        Flavor::Bsd if !first_line.starts_with("awk version") => (flavor, None),
+        _ => (flavor, version),
+    }
+}
+
+fn probe(package: &str, binary: &str, bsd_os: bool) -> Option<CoreUtil> {
+    let path = which(binary)?;
+
+    // A symlink into busybox needs no probe
+    let is_busybox = path
+        .canonicalize()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().starts_with("busybox")))
+        .unwrap_or(false);
+    let (flavor, version) = if is_busybox {
+        (Flavor::BusyBox, None)
+    } else {
+        let output = output_with_timeout(Command::new(&path).arg("--version"), PROBE_TIMEOUT).ok()??;
+        let text = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        classify(&text, bsd_os)
+    };
+
+    Some(CoreUtil {
+        package: package.to_string(),
+        binary: binary.to_string(),
+        flavor,
+        version,
+    })
+}
+
+/// Probe the core utilities in parallel; missing or hanging ones are left out
+pub fn detect(os: &str) -> Vec<CoreUtil> {
+    if os == "windows" {
+        return Vec::new();
+    }
+    let bsd_os = os == "macos" || os.ends_with("bsd") || os == "dragonfly";
+
+    let handles: Vec<_> = PROBES
+        .iter()
+        .map(|(package, binary)| thread::spawn(move || probe(package, binary, bsd_os)))
+        .collect();
+
+    handles.into_iter().filter_map(|h| h.join().ok().flatten()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify("sed (GNU sed) 4.9\nPackaged by Debian\n", false),
+            (Flavor::Gnu, Some("4.9".to_string()))
+        );
+        assert_eq!(
+            classify("date (GNU coreutils) 9.4\n", false),
+            (Flavor::Gnu, Some("9.4".to_string()))
+        );
+        assert_eq!(
+            classify("GNU Awk 5.2.1, API 3.2, PMA Avon 8-g1\n", false),
+            (Flavor::Gnu, Some("5.2.1".to_string()))
+        );
+        assert_eq!(
+            classify("This is not GNU sed version 4.0\n", false).0,
+            Flavor::BusyBox
+        );
+        assert_eq!(
+            classify("\nBusyBox v1.36.1 (2024-06-10 07:11:47 UTC) multi-call binary.\n", false).0,
+            Flavor::BusyBox
+        );
+        assert_eq!(
+            classify("date (uutils coreutils) 0.0.30\n", false),
+            (Flavor::Uutils, Some("0.0.30".to_string()))
+        );
+        assert_eq!(
+            classify("mawk 1.3.4 20240123\n", false),
+            (Flavor::Mawk, Some("1.3.4".to_string()))
+        );
+        assert_eq!(classify("awk version 20200816\n", true).0, Flavor::Bsd);
+        assert_eq!(
+            classify("sed: illegal option -- -\nusage: sed script [-Ealnru]\n", true),
+            (Flavor::Bsd, None)
+        );
+        assert_eq!(classify("something else\n", false), (Flavor::Unknown, None));
+    }
+
+    #[test]
+    fn test_describe_and_hint() {
+        let sed = CoreUtil {
+            package: "sed".to_string(),
+            binary: "sed".to_string(),
+            flavor: Flavor::Bsd,
+            version: None,
+        };
+        assert_eq!(sed.describe(), "sed is BSD");
+        assert!(sed.hint().unwrap().contains("sed -i ''"));
+
+        let find = CoreUtil {
+            package: "findutils".to_string(),
+            binary: "find".to_string(),
+            flavor: Flavor::Gnu,
+            version: Some("4.9.0".to_string()),
+        };
+        assert_eq!(find.describe(), "find is GNU 4.9.0");
+        assert_eq!(find.hint(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_detect_finds_sed() {
+        let utils = detect(std::env::consts::OS);
+        assert!(utils.iter().any(|u| u.binary == "sed"));
+        assert!(detect("windows").is_empty());
+    }
+}
@@ -0,0 +1,585 @@
+//! Offline index of installed tools' flags, built from man pages and `--help`
+//!
+//! `ez index build` splits each tool's documentation into one chunk per
+//! option and stores them in `~/.cache/ez-term/doc-index.json`. When a query
+//! names an indexed tool, the chunks that best match the query are added to
+//! the prompt so the model picks flags that exist in the installed version.
+//! Chunks are ranked with BM25, or by cosine similarity when the index was
+//! built with an Ollama embedding model.
+
+use anyhow::{anyhow, Context, Result};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use regex::Regex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use crate::history::estimate_tokens;
+use crate::paths;
+use crate::process_util::{output_with_timeout, which};
+
+/// Classic utilities indexed alongside the installed catalogue tools
+pub const CORE_TOOLS: [&str; 30] = [
+    "awk", "chmod", "chown", "cp", "curl", "cut", "date", "df", "du", "find", "git", "grep", "head", "ln", "ls", "mv",
+    "ps", "rm", "rsync", "sed", "sort", "ssh", "tail", "tar", "tr", "uniq", "wc", "wget", "xargs", "zip",
+];
+
+/// How long `man` or `--help` may take for one tool
+const DOC_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Longest chunk kept; long option descriptions are cut here
+const MAX_CHUNK_LEN: usize = 400;
+
+/// At most this many flag descriptions are added to a prompt
+const MAX_RESULTS: usize = 6;
+
+/// Prompt budget for retrieved flag descriptions
+const RESULT_TOKEN_BUDGET: usize = 400;
+
+/// Concurrent embedding requests while building
+const EMBED_CONCURRENCY: usize = 8;
+
+/// BM25 parameters
+const K1: f32 = 1.2;
+const B: f32 = 0.75;
+
+/// One option's documentation, e.g. `-size n[cwbkMG] File uses n units of space`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocChunk {
+    pub tool: String,
+    pub text: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DocIndex {
+    chunks: Vec<DocChunk>,
+    /// Number of chunks each term appears in
+    doc_freq: HashMap<String, usize>,
+    avg_len: f32,
+    /// Ollama model the chunk embeddings were made with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embedding_model: Option<String>,
+    /// One vector per chunk, stored next to the index as raw little-endian f32s
+    #[serde(skip)]
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
+impl DocIndex {
+    /// `$XDG_CACHE_HOME/ez-term/doc-index.json`, falling back to `~/.cache`
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(paths::cache_dir()?.join("doc-index.json"))
+    }
+
+    /// Index the documentation of `tools` (name and binary path), reading them in parallel
+    pub fn build(tools: &[(String, PathBuf)]) -> Self {
+        let handles: Vec<_> = tools
+            .iter()
+            .cloned()
+            .map(|(tool, path)| thread::spawn(move || tool_chunks(&tool, &path)))
+            .collect();
+
+        let chunks = handles.into_iter().filter_map(|h| h.join().ok()).flatten().collect();
+        Self::from_chunks(chunks)
+    }
+
+    fn from_chunks(chunks: Vec<DocChunk>) -> Self {
+        let mut doc_freq = HashMap::new();
+        let mut total_len = 0;
+        for chunk in &chunks {
+            let terms = tokens(&chunk.text);
+            total_len += terms.len();
+            for term in terms.into_iter().collect::<HashSet<_>>() {
+                *doc_freq.entry(term).or_insert(0) += 1;
+            }
+        }
+        let avg_len = if chunks.is_empty() { 0.0 } else { total_len as f32 / chunks.len() as f32 };
+
+        Self {
+            chunks,
+            doc_freq,
+            avg_len,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Number of distinct tools with indexed documentation
+    pub fn tool_count(&self) -> usize {
+        self.chunks.iter().map(|c| &c.tool).collect::<HashSet<_>>().len()
+    }
+
+    /// Embed every chunk with an Ollama model so retrieval can rank by meaning
+    pub async fn embed(&mut self, ollama_url: &str, model: &str) -> Result<()> {
+        let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        let embeddings: Vec<Vec<f32>> = stream::iter(&self.chunks)
+            .map(|chunk| embed_text(&client, ollama_url, model, &chunk.text))
+            .buffered(EMBED_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        self.embeddings = embeddings;
+        self.embedding_model = Some(model.to_string());
+        Ok(())
+    }
+
+    /// Load the index; `None` if it was never built or cannot be read
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut index: Self = serde_json::from_str(&content).ok()?;
+
+        if index.embedding_model.is_some() {
+            index.embeddings = fs::read(vectors_path(path))
+                .ok()
+                .map(|bytes| decode_vectors(&bytes, index.chunks.len()))
+                .unwrap_or_default();
+            // Vectors that don't line up with the chunks are useless; fall back to BM25
+            if index.embeddings.len() != index.chunks.len() {
+                index.embedding_model = None;
+                index.embeddings.clear();
+            }
+        }
+        Some(index)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create cache directory")?;
+        }
+        fs::write(path, serde_json::to_string(self)?).context("Failed to write index")?;
+
+        let vectors = vectors_path(path);
+        if self.embeddings.is_empty() {
+            let _ = fs::remove_file(vectors);
+        } else {
+            fs::write(vectors, encode_vectors(&self.embeddings)).context("Failed to write embeddings")?;
+        }
+        Ok(())
+    }
+
+    /// Indexed tools named in `query`
+    pub fn mentioned_tools(&self, query: &str) -> HashSet<&str> {
+        let words: HashSet<String> = query
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+            .collect();
+        self.chunks
+            .iter()
+            .map(|c| c.tool.as_str())
+            .filter(|tool| words.contains(*tool))
+            .collect()
+    }
+
+    /// Flag documentation for the tools `query` names, best match first
+    ///
+    /// Uses embeddings when the index has them and Ollama answers, BM25 otherwise.
+    pub async fn retrieve(&self, query: &str, ollama_url: &str) -> Vec<&DocChunk> {
+        let tools = self.mentioned_tools(query);
+        if tools.is_empty() {
+            return Vec::new();
+        }
+        let candidates: Vec<usize> = (0..self.chunks.len())
+            .filter(|&i| tools.contains(self.chunks[i].tool.as_str()))
+            .collect();
+
+        let mut scored = match self.embedding_scores(query, ollama_url, &candidates).await {
+            Some(scored) => scored,
+            None => self.bm25_scores(query, &candidates),
+        };
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let mut results = Vec::new();
+        let mut used = 0;
+        for (_, i) in scored.into_iter().take(MAX_RESULTS) {
+            let chunk = &self.chunks[i];
+            let cost = estimate_tokens(&chunk.text);
+            if used + cost > RESULT_TOKEN_BUDGET {
+                continue;
+            }
+            used += cost;
+            results.push(chunk);
+        }
+        results
+    }
+
+    /// The retrieved chunks as a prompt section, one `tool: option text` line each
+    pub async fn format_for_query(&self, query: &str, ollama_url: &str) -> Option<String> {
+        let chunks = self.retrieve(query, ollama_url).await;
+        if chunks.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = chunks.iter().map(|c| format!("{}: {}", c.tool, c.text)).collect();
+        Some(lines.join("\n"))
+    }
+
+    fn bm25_scores(&self, query: &str, candidates: &[usize]) -> Vec<(f32, usize)> {
+        let query_terms: HashSet<String> = tokens(query).into_iter().collect();
+        let total = self.chunks.len() as f32;
+
+        candidates
+            .iter()
+            .filter_map(|&i| {
+                let terms = tokens(&self.chunks[i].text);
+                let len = terms.len() as f32;
+                let score: f32 = query_terms
+                    .iter()
+                    .map(|term| {
+                        let tf = terms.iter().filter(|t| *t == term).count() as f32;
+                        if tf == 0.0 {
+                            return 0.0;
+                        }
+                        let df = self.doc_freq.get(term).copied().unwrap_or(0) as f32;
+                        let idf = (1.0 + (total - df + 0.5) / (df + 0.5)).ln();
+                        idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / self.avg_len.max(1.0)))
+                    })
+                    .sum();
+                (score > 0.0).then_some((score, i))
+            })
+            .collect()
+    }
+
+    async fn embedding_scores(&self, query: &str, ollama_url: &str, candidates: &[usize]) -> Option<Vec<(f32, usize)>> {
+        let model = self.embedding_model.as_deref()?;
+        let client = Client::builder().timeout(Duration::from_secs(2)).build().ok()?;
+        let query_vector = embed_text(&client, ollama_url, model, query).await.ok()?;
+
+        Some(
+            candidates
+                .iter()
+                .map(|&i| (cosine(&query_vector, &self.embeddings[i]), i))
+                .collect(),
+        )
+    }
+}
+
+async fn embed_text(client: &Client, ollama_url: &str, model: &str, text: &str) -> Result<Vec<f32>> {
+    let response = client
+        .post(format!("{}/api/embeddings", ollama_url))
+        .json(&EmbeddingRequest { model, prompt: text })
+        .send()
+        .await
+        .context("Failed to connect to Ollama")?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Ollama embedding request failed with status: {}", response.status()));
+    }
+    Ok(response.json::<EmbeddingResponse>().await?.embedding)
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 { 0.0 } else { dot / norm }
+}
+
+fn vectors_path(index: &Path) -> PathBuf {
+    index.with_extension("vectors")
+}
+
+fn encode_vectors(vectors: &[Vec<f32>]) -> Vec<u8> {
+    vectors.iter().flatten().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn decode_vectors(bytes: &[u8], count: usize) -> Vec<Vec<f32>> {
+    let floats: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    if count == 0 || !floats.len().is_multiple_of(count) {
+        return Vec::new();
+    }
+    floats.chunks(floats.len() / count).map(<[f32]>::to_vec).collect()
+}
+
+/// Lowercase words with a trailing plural `s` dropped; flags lose their dashes
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let w = w.to_lowercase();
+            match w.strip_suffix('s') {
+                Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+                _ => w,
+            }
+        })
+        .collect()
+}
+
+/// Remove the overstrike bold/underline and ANSI colours `man` and some `--help` output use
+pub fn strip_formatting(text: &str) -> String {
+    static ANSI: OnceLock<Regex> = OnceLock::new();
+    let ansi = ANSI.get_or_init(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+    let mut plain = String::with_capacity(text.len());
+    for c in ansi.replace_all(text, "").chars() {
+        if c == '\u{8}' {
+            plain.pop();
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Split documentation into one chunk per option
+///
+/// A chunk starts at a line whose first non-blank character is `-` and takes
+/// the following lines that are indented deeper, so both man pages (flag on
+/// its own line, description below) and `--help` tables (flag and description
+/// on one line) work. Text outside option descriptions is dropped.
+pub fn option_chunks(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+
+        if trimmed.starts_with('-') && trimmed.len() > 1 {
+            chunks.extend(current.take().map(|(_, lines)| join_chunk(&lines)));
+            current = Some((indent, vec![trimmed]));
+        } else if let Some((option_indent, lines)) = &mut current {
+            if trimmed.is_empty() {
+                continue;
+            }
+            if indent > *option_indent {
+                lines.push(trimmed);
+            } else {
+                chunks.extend(current.take().map(|(_, lines)| join_chunk(&lines)));
+            }
+        }
+    }
+    chunks.extend(current.map(|(_, lines)| join_chunk(&lines)));
+    chunks
+}
+
+fn join_chunk(lines: &[&str]) -> String {
+    let text = lines.join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(MAX_CHUNK_LEN) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// The flag a chunk documents, e.g. `--size` for `-s, --size=SIZE ...`
+fn chunk_flag(chunk: &str) -> &str {
+    chunk
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '=' || c == '[')
+        .find(|w| w.starts_with("--"))
+        .or_else(|| chunk.split_whitespace().next())
+        .unwrap_or(chunk)
+}
+
+/// Option chunks from a tool's man page, plus `--help` options the man page lacks
+fn tool_chunks(tool: &str, path: &Path) -> Vec<DocChunk> {
+    let mut chunks = Vec::new();
+    let mut seen = HashSet::new();
+
+    for text in [man_page(tool), help_output(path, &["--help"])].into_iter().flatten() {
+        for chunk in option_chunks(&text) {
+            if seen.insert(chunk_flag(&chunk).to_string()) {
+                chunks.push(DocChunk {
+                    tool: tool.to_string(),
+                    text: chunk,
+                });
+            }
+        }
+    }
+    chunks
+}
+
+/// A man page rendered as plain text, e.g. `man_page("git-log")`
+pub fn man_page(page: &str) -> Option<String> {
+    run_docs(Command::new("man").arg(page).env("MANPAGER", "cat").env("MANWIDTH", "100"))
+}
+
+/// Plain-text output of `binary args...`, e.g. `help_output(path, &["--help"])`
+pub fn help_output(binary: &Path, args: &[&str]) -> Option<String> {
+    run_docs(Command::new(binary).args(args))
+}
+
+/// Stdout (or stderr, where some tools print their help) of a documentation command
+fn run_docs(command: &mut Command) -> Option<String> {
+    let output = output_with_timeout(command.env("PAGER", "cat"), DOC_TIMEOUT).ok()??;
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    Some(strip_formatting(&String::from_utf8_lossy(&text))).filter(|t| !t.trim().is_empty())
+}
+
+/// `CORE_TOOLS` found in `PATH`, for indexing next to the catalogue tools
+pub fn core_tool_paths() -> Vec<(String, PathBuf)> {
+    CORE_TOOLS
+        .iter()
+        .filter_map(|tool| which(tool).map(|path| (tool.to_string(), path)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const FIND_MAN: &str = "\
+FIND(1)                General Commands Manual               FIND(1)
+
+NAME
+       find - search for files in a directory hierarchy
+
+TESTS
+       -mmin n
+              File's data was last modified less than, more than or
+              exactly n minutes ago.
+
+       -size n[cwbkMG]
+              File uses less than, more than or exactly n units of
+              space, rounding up.
+
+       -type c
+              File is of type c.
+
+SEE ALSO
+       locate(1)
+";
+
+    const LS_HELP: &str = "\
+Usage: ls [OPTION]... [FILE]...
+  -a, --all                  do not ignore entries starting with .
+  -S                         sort by file size, largest first
+      --sort=WORD            sort by WORD instead of name: none (-U), size (-S)
+  -t                         sort by time, newest first; see --time
+";
+
+    fn index() -> DocIndex {
+        let chunks = option_chunks(FIND_MAN)
+            .into_iter()
+            .map(|text| DocChunk { tool: "find".to_string(), text })
+            .chain(option_chunks(LS_HELP).into_iter().map(|text| DocChunk { tool: "ls".to_string(), text }))
+            .collect();
+        DocIndex::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_option_chunks_from_man_page() {
+        assert_eq!(
+            option_chunks(FIND_MAN),
+            vec![
+                "-mmin n File's data was last modified less than, more than or exactly n minutes ago.",
+                "-size n[cwbkMG] File uses less than, more than or exactly n units of space, rounding up.",
+                "-type c File is of type c.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_option_chunks_from_help() {
+        let chunks = option_chunks(LS_HELP);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[1], "-S sort by file size, largest first");
+        assert_eq!(chunk_flag(&chunks[0]), "--all");
+        assert_eq!(chunk_flag(&chunks[2]), "--sort");
+        assert_eq!(chunk_flag(&chunks[1]), "-S");
+    }
+
+    #[test]
+    fn test_strip_formatting() {
+        assert_eq!(strip_formatting("-\u{8}--\u{8}-s\u{8}si\u{8}iz\u{8}ze\u{8}e"), "--size");
+        assert_eq!(strip_formatting("\x1b[1m-a\x1b[0m all"), "-a all");
+    }
+
+    #[tokio::test]
+    async fn test_bm25_retrieval_for_mentioned_tool() {
+        let index = index();
+        let chunks = index.retrieve("use find to list files over 100 MB in size", "http://127.0.0.1:9").await;
+        assert!(chunks.iter().all(|c| c.tool == "find"));
+        assert!(chunks[0].text.starts_with("-size"));
+
+        let chunks = index.retrieve("ls sorted by size", "http://127.0.0.1:9").await;
+        assert!(chunks[0].text.contains("size"));
+        assert!(chunks.iter().all(|c| c.tool == "ls"));
+
+        assert!(index.retrieve("files over 100 MB", "http://127.0.0.1:9").await.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load_with_embeddings() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("ez-term/doc-index.json");
+
+        let mut index = index();
+        index.embedding_model = Some("nomic-embed-text".to_string());
+        index.embeddings = (0..index.len()).map(|i| vec![i as f32, 1.0, -2.5]).collect();
+        index.save(&path).unwrap();
+
+        let loaded = DocIndex::load(&path).unwrap();
+        assert_eq!(loaded.chunks, index.chunks);
+        assert_eq!(loaded.embeddings, index.embeddings);
+        assert_eq!(loaded.tool_count(), 2);
+
+        // Vectors that don't match the chunks are dropped rather than misused
+        fs::write(vectors_path(&path), [0u8; 12]).unwrap();
+        let loaded = DocIndex::load(&path).unwrap();
+        assert!(loaded.embedding_model.is_none());
+        assert!(loaded.embeddings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_embedding_retrieval_with_ollama() {
+        use httpmock::prelude::*;
+
+        let server = MockServer::start_async().await;
+        // Chunks about time point one way, everything else (and the query) the other
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/api/embeddings").body_contains("minutes");
+                then.status(200).json_body(serde_json::json!({"embedding": [1.0, 0.0]}));
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(POST).path("/api/embeddings");
+                then.status(200).json_body(serde_json::json!({"embedding": [0.0, 1.0]}));
+            })
+            .await;
+
+        let mut index = index();
+        index.embed(&server.base_url(), "nomic-embed-text").await.unwrap();
+        assert_eq!(index.embeddings.len(), index.len());
+
+        let chunks = index.retrieve("find things changed recently", &server.base_url()).await;
+        assert!(!chunks[0].text.starts_with("-mmin"));
+        assert_eq!(chunks.last().unwrap().text.split_whitespace().next(), Some("-mmin"));
+
+        // Without Ollama, retrieval falls back to BM25
+        let chunks = index.retrieve("find by size", "http://127.0.0.1:9").await;
+        assert!(chunks[0].text.starts_with("-size"));
+    }
+
+    #[test]
+    fn test_cosine() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine(&[1.0, 0.0], &[0.0, 3.0]), 0.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+    }
+}
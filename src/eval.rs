@@ -0,0 +1,430 @@
+//! `ez eval`: score a backend, model and prompt against a corpus of queries
+//!
+//! A corpus is a YAML file of cases, each a query plus expectations about the
+//! command generated for it:
+//!
+//! ```yaml
+//! cases:
+//!   - id: current-branch
+//!     query: show the current git branch
+//!     expect:
+//!       starts_with: git           # or a list: any of them
+//!       contains: [branch]         # all of them
+//!       not_contains: ["-delete"]  # none of them
+//!       matches: 'branch\s+--show-current|rev-parse'
+//!       risk: safe                 # safe, medium or critical
+//!       sandbox: true              # exits 0 when trial-run in the sandbox
+//! ```
+//!
+//! Every case is checked separately, so one report gives the pass rate per
+//! expectation as well as per case, with generation latency.
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use serde_json::json;
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::command_validator::RiskLevel;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Corpus {
+    pub cases: Vec<Case>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Case {
+    pub id: String,
+    pub query: String,
+    #[serde(default)]
+    pub expect: Expect,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expect {
+    /// The command starts with one of these
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub starts_with: Vec<String>,
+    /// The command contains all of these
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub contains: Vec<String>,
+    /// The command contains none of these
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub not_contains: Vec<String>,
+    /// A regex the command matches
+    #[serde(default)]
+    pub matches: Option<String>,
+    /// What the command validator rates it
+    #[serde(default)]
+    pub risk: Option<RiskLevel>,
+    /// The command exits 0 when trial-run in the sandbox
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+/// A string or a list of strings
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(one) => vec![one],
+        OneOrMany::Many(many) => many,
+    })
+}
+
+impl Corpus {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read corpus {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid corpus {}", path.display()))
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let corpus: Corpus = serde_yaml::from_str(content)?;
+        if corpus.cases.is_empty() {
+            bail!("no cases");
+        }
+
+        let mut ids = HashSet::new();
+        for case in &corpus.cases {
+            if !ids.insert(case.id.as_str()) {
+                bail!("duplicate case id '{}'", case.id);
+            }
+            if case.query.trim().is_empty() {
+                bail!("{}: empty query", case.id);
+            }
+            if let Some(pattern) = &case.expect.matches {
+                Regex::new(pattern).with_context(|| format!("{}: invalid regex in matches", case.id))?;
+            }
+        }
+        Ok(corpus)
+    }
+
+    /// The cases whose id contains `filter`
+    pub fn filter(&mut self, filter: &str) {
+        self.cases.retain(|case| case.id.contains(filter));
+    }
+}
+
+/// The outcome of one expectation (or of generating the command at all)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    /// Why it failed
+    pub failure: Option<String>,
+}
+
+impl Check {
+    pub fn new(name: &'static str, failure: Option<String>) -> Self {
+        Self { name, failure }
+    }
+}
+
+impl Expect {
+    /// Check `command` against everything but `sandbox`, which needs running it
+    pub fn check(&self, command: &str, risk: &RiskLevel) -> Vec<Check> {
+        let mut checks = Vec::new();
+
+        if !self.starts_with.is_empty() {
+            let command = command.trim_start();
+            let ok = self.starts_with.iter().any(|prefix| command.starts_with(prefix.as_str()));
+            checks.push(Check::new(
+                "starts_with",
+                (!ok).then(|| format!("does not start with {}", quoted(&self.starts_with, " or "))),
+            ));
+        }
+        if !self.contains.is_empty() {
+            let missing: Vec<String> = self.contains.iter().filter(|s| !command.contains(s.as_str())).cloned().collect();
+            checks.push(Check::new(
+                "contains",
+                (!missing.is_empty()).then(|| format!("does not contain {}", quoted(&missing, ", "))),
+            ));
+        }
+        if !self.not_contains.is_empty() {
+            let found: Vec<String> = self.not_contains.iter().filter(|s| command.contains(s.as_str())).cloned().collect();
+            checks.push(Check::new(
+                "not_contains",
+                (!found.is_empty()).then(|| format!("contains {}", quoted(&found, ", "))),
+            ));
+        }
+        if let Some(pattern) = &self.matches {
+            let failure = match Regex::new(pattern) {
+                Ok(re) if re.is_match(command) => None,
+                Ok(_) => Some(format!("does not match /{}/", pattern)),
+                Err(e) => Some(e.to_string()),
+            };
+            checks.push(Check::new("matches", failure));
+        }
+        if let Some(expected) = &self.risk {
+            checks.push(Check::new(
+                "risk",
+                (risk != expected).then(|| format!("risk is {}, expected {}", risk_name(risk), risk_name(expected))),
+            ));
+        }
+
+        checks
+    }
+}
+
+fn quoted(strings: &[String], separator: &str) -> String {
+    strings.iter().map(|s| format!("\"{}\"", s)).collect::<Vec<_>>().join(separator)
+}
+
+fn risk_name(risk: &RiskLevel) -> String {
+    format!("{:?}", risk).to_lowercase()
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseResult {
+    pub id: String,
+    /// `None` if the model's response had no command in it
+    pub command: Option<String>,
+    pub latency: Duration,
+    pub checks: Vec<Check>,
+}
+
+impl CaseResult {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.failure.is_none())
+    }
+
+    /// One line for the case plus one per failed check
+    pub fn format(&self) -> String {
+        let mut out = format!(
+            "{} {:<24} {:>6.2}s  {}",
+            if self.passed() { "✓" } else { "✗" },
+            self.id,
+            self.latency.as_secs_f64(),
+            self.command.as_deref().unwrap_or("-")
+        );
+        for check in &self.checks {
+            if let Some(failure) = &check.failure {
+                out.push_str(&format!("\n    {}: {}", check.name, failure));
+            }
+        }
+        out
+    }
+}
+
+/// Results of a whole run
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub backend: String,
+    pub model: String,
+    pub results: Vec<CaseResult>,
+}
+
+impl Report {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|result| result.passed()).count()
+    }
+
+    /// Percentage of cases that passed
+    pub fn pass_rate(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        100.0 * self.passed() as f64 / self.results.len() as f64
+    }
+
+    /// `(check, passed, total)` in the order checks first appear
+    fn check_rates(&self) -> Vec<(&'static str, usize, usize)> {
+        let mut rates: Vec<(&'static str, usize, usize)> = Vec::new();
+        for check in self.results.iter().flat_map(|result| &result.checks) {
+            let index = match rates.iter().position(|(name, _, _)| *name == check.name) {
+                Some(index) => index,
+                None => {
+                    rates.push((check.name, 0, 0));
+                    rates.len() - 1
+                }
+            };
+            rates[index].1 += check.failure.is_none() as usize;
+            rates[index].2 += 1;
+        }
+        rates
+    }
+
+    /// `(mean, p50, p95, max)` generation latency
+    fn latency(&self) -> (Duration, Duration, Duration, Duration) {
+        let mut latencies: Vec<Duration> = self.results.iter().map(|result| result.latency).collect();
+        if latencies.is_empty() {
+            return Default::default();
+        }
+        latencies.sort();
+        let percentile = |p: usize| latencies[(latencies.len() * p).div_ceil(100).saturating_sub(1)];
+        let mean = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+        (mean, percentile(50), percentile(95), latencies[latencies.len() - 1])
+    }
+
+    /// The summary printed after the per-case lines
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "Backend: {}  Model: {}\nPassed {}/{} cases ({:.1}%)\n",
+            self.backend,
+            self.model,
+            self.passed(),
+            self.results.len(),
+            self.pass_rate()
+        );
+        for (name, passed, total) in self.check_rates() {
+            out.push_str(&format!("  {:<14} {}/{}\n", name, passed, total));
+        }
+        let (mean, p50, p95, max) = self.latency();
+        out.push_str(&format!(
+            "Latency: mean {:.2}s, p50 {:.2}s, p95 {:.2}s, max {:.2}s\n",
+            mean.as_secs_f64(),
+            p50.as_secs_f64(),
+            p95.as_secs_f64(),
+            max.as_secs_f64()
+        ));
+        out
+    }
+
+    /// The report as JSON, for comparing runs with other tools
+    pub fn to_json(&self) -> serde_json::Value {
+        let ms = |d: Duration| d.as_millis() as u64;
+        let (mean, p50, p95, max) = self.latency();
+        json!({
+            "backend": self.backend,
+            "model": self.model,
+            "passed": self.passed(),
+            "total": self.results.len(),
+            "pass_rate": self.pass_rate(),
+            "checks": self.check_rates().into_iter().map(|(name, passed, total)| {
+                (name.to_string(), json!({"passed": passed, "total": total}))
+            }).collect::<serde_json::Map<_, _>>(),
+            "latency_ms": {"mean": ms(mean), "p50": ms(p50), "p95": ms(p95), "max": ms(max)},
+            "cases": self.results.iter().map(|result| json!({
+                "id": result.id,
+                "passed": result.passed(),
+                "command": result.command,
+                "latency_ms": ms(result.latency),
+                "failures": result.checks.iter().filter_map(|check| {
+                    Some(json!({"check": check.name, "reason": check.failure.as_ref()?}))
+                }).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &str = r#"
+cases:
+  - id: branch
+    query: show the current git branch
+    expect:
+      starts_with: git
+      contains: [branch]
+      risk: safe
+  - id: logs
+    query: delete old log files
+    expect:
+      starts_with: [find, rm]
+      not_contains: ["-delete", "rm -rf"]
+      matches: '\*\.log'
+      sandbox: true
+  - id: anything
+    query: list files
+"#;
+
+    fn failures(checks: &[Check]) -> Vec<String> {
+        checks.iter().filter_map(|c| Some(format!("{}: {}", c.name, c.failure.as_ref()?))).collect()
+    }
+
+    #[test]
+    fn test_parse_corpus() {
+        let corpus = Corpus::parse(CORPUS).unwrap();
+        assert_eq!(corpus.cases.len(), 3);
+        assert_eq!(corpus.cases[0].expect.starts_with, vec!["git"]);
+        assert_eq!(corpus.cases[0].expect.risk, Some(RiskLevel::Safe));
+        assert_eq!(corpus.cases[1].expect.starts_with, vec!["find", "rm"]);
+        assert!(corpus.cases[1].expect.sandbox);
+        assert!(corpus.cases[2].expect.check("ls", &RiskLevel::Safe).is_empty());
+
+        let errors = [
+            ("cases: []", "no cases"),
+            ("cases:\n  - {id: a, query: x}\n  - {id: a, query: y}", "duplicate case id 'a'"),
+            ("cases:\n  - {id: a, query: ' '}", "a: empty query"),
+            ("cases:\n  - {id: a, query: x, expect: {matches: '('}}", "a: invalid regex in matches"),
+            ("cases:\n  - {id: a, query: x, expect: {starts: git}}", "unknown field `starts`"),
+            ("cases:\n  - {id: a, query: x, expect: {risk: low}}", "unknown variant `low`"),
+        ];
+        for (content, error) in errors {
+            let message = format!("{:#}", Corpus::parse(content).unwrap_err());
+            assert!(message.contains(error), "{}: {}", content, message);
+        }
+
+        // The starter corpus shipped with the repository
+        assert!(Corpus::parse(include_str!("../eval/corpus.yaml")).unwrap().cases.len() >= 10);
+
+        let mut corpus = Corpus::parse(CORPUS).unwrap();
+        corpus.filter("log");
+        assert_eq!(corpus.cases.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), ["logs"]);
+    }
+
+    #[test]
+    fn test_expectations() {
+        let corpus = Corpus::parse(CORPUS).unwrap();
+        let (branch, logs) = (&corpus.cases[0].expect, &corpus.cases[1].expect);
+
+        assert!(failures(&branch.check("git branch --show-current", &RiskLevel::Safe)).is_empty());
+        assert_eq!(
+            failures(&branch.check("  echo main", &RiskLevel::Medium)),
+            [
+                "starts_with: does not start with \"git\"",
+                "contains: does not contain \"branch\"",
+                "risk: risk is medium, expected safe",
+            ]
+        );
+
+        assert!(failures(&logs.check("find . -name '*.log' -mtime +7 -exec rm {} +", &RiskLevel::Medium)).is_empty());
+        assert_eq!(
+            failures(&logs.check("rm -rf logs && find . -delete", &RiskLevel::Critical)),
+            ["not_contains: contains \"-delete\", \"rm -rf\"", "matches: does not match /\\*\\.log/"]
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let result = |id: &str, ms: u64, checks: Vec<Check>| CaseResult {
+            id: id.to_string(),
+            command: Some("ls".to_string()),
+            latency: Duration::from_millis(ms),
+            checks,
+        };
+        let report = Report {
+            backend: "ollama".to_string(),
+            model: "llama3.2".to_string(),
+            results: vec![
+                result("a", 1000, vec![Check::new("risk", None)]),
+                result("b", 3000, vec![Check::new("risk", Some("risk is medium, expected safe".to_string())), Check::new("sandbox", None)]),
+                result("c", 2000, vec![]),
+            ],
+        };
+
+        assert_eq!(report.passed(), 2);
+        assert_eq!(
+            report.summary(),
+            "Backend: ollama  Model: llama3.2\nPassed 2/3 cases (66.7%)\n  risk           1/2\n  sandbox        1/1\n\
+             Latency: mean 2.00s, p50 2.00s, p95 3.00s, max 3.00s\n"
+        );
+        assert_eq!(report.results[1].format(), "✗ b                          3.00s  ls\n    risk: risk is medium, expected safe");
+
+        let json = report.to_json();
+        assert_eq!(json["checks"]["risk"], json!({"passed": 1, "total": 2}));
+        assert_eq!(json["latency_ms"]["p50"], 2000);
+        assert_eq!(json["cases"][1]["failures"][0]["check"], "risk");
+    }
+}
@@ -0,0 +1,427 @@
+//! Verify a generated command's flags against the installed tools' own documentation
+//!
+//! Every option of every program in the command is looked up in that
+//! program's man page and `--help` output on this machine (and, for tools
+//! such as `git` and `kubectl`, in the subcommand's). Flags the documentation
+//! does not mention are reported so `ez` can warn or ask the model again.
+//! Programs whose documentation can't be read, or lists too few flags to be
+//! trusted, get no verdict rather than a false alarm.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::command_validator::CommandValidator;
+use crate::doc_index::{help_output, man_page, CORE_TOOLS};
+use crate::process_util::which;
+use crate::tool_catalog::CATALOG;
+
+/// Documentation listing fewer flags than this is too thin to judge by
+const MIN_FLAGS: usize = 3;
+
+/// Deepest subcommand chain followed, e.g. `docker compose up`
+const MAX_SUBCOMMAND_DEPTH: usize = 2;
+
+/// Programs whose first positional argument may be a subcommand with its own flags
+///
+/// Only these are ever run as `<program> <subcommand> --help`; running other
+/// programs with arguments taken from the generated command is not safe.
+const SUBCOMMAND_TOOLS: [&str; 28] = [
+    "apt", "apt-get", "az", "brew", "cargo", "dnf", "docker", "flatpak", "gh", "git", "glab", "go", "helm", "ip",
+    "kind", "kubectl", "minikube", "nerdctl", "nix", "npm", "pip", "pnpm", "podman", "poetry", "rustup",
+    "systemctl", "terraform", "yarn",
+];
+
+/// Programs that run another command, with their options that take a value
+const WRAPPERS: [(&str, &[&str]); 10] = [
+    ("sudo", &["-u", "-g", "-C", "-D", "-h", "-p", "-r", "-t", "-U"]),
+    ("doas", &["-u", "-C"]),
+    ("env", &["-u", "-C", "-S"]),
+    ("nice", &["-n"]),
+    ("nohup", &[]),
+    ("time", &["-f", "-o"]),
+    ("timeout", &["-s", "-k"]),
+    ("xargs", &["-I", "-n", "-P", "-L", "-d", "-E", "-s", "-a"]),
+    ("exec", &[]),
+    ("command", &[]),
+];
+
+/// `find` options whose arguments, up to `;` or `+`, are another command
+const FIND_EXEC: [&str; 4] = ["-exec", "-execdir", "-ok", "-okdir"];
+
+/// Extra documentation for tools whose `--help` leaves out common flags
+fn extra_help(program: &str) -> &'static [&'static str] {
+    match program {
+        // `kubectl get --help` points at `kubectl options` for -n, --context, ...
+        "kubectl" => &["options"],
+        _ => &[],
+    }
+}
+
+/// How `ez` reacts when a check of the generated command finds a problem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckMode {
+    /// Don't run the check
+    Off,
+    /// Print a warning above the command
+    #[default]
+    Warn,
+    /// Ask the model once more, naming what was wrong
+    Regenerate,
+}
+
+/// A flag the program's documentation does not mention
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnknownFlag {
+    /// Program plus subcommands, e.g. `git log`
+    pub program: String,
+    pub flag: String,
+}
+
+impl fmt::Display for UnknownFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} has no {} option", self.program, self.flag)
+    }
+}
+
+/// Check every flag of every program in `command` against local documentation
+pub fn check(command: &str) -> Vec<UnknownFlag> {
+    check_with(command, &installed_docs)
+}
+
+/// Man page and help output of `program` (plus `subcommands`) if it is a known, installed tool
+fn installed_docs(program: &str, subcommands: &[&str]) -> Option<String> {
+    let known_tool = CATALOG.iter().any(|entry| entry.name == program) || CORE_TOOLS.contains(&program);
+    if !known_tool || (!subcommands.is_empty() && !SUBCOMMAND_TOOLS.contains(&program)) {
+        return None;
+    }
+    let binary = which(program)?;
+
+    let page = std::iter::once(program).chain(subcommands.iter().copied()).collect::<Vec<_>>().join("-");
+    let mut help_args = subcommands.to_vec();
+    help_args.push("--help");
+
+    let mut docs = vec![man_page(&page), help_output(&binary, &help_args)];
+    if subcommands.is_empty() && !extra_help(program).is_empty() {
+        docs.push(help_output(&binary, extra_help(program)));
+    }
+    let docs: Vec<String> = docs.into_iter().flatten().collect();
+    (!docs.is_empty()).then(|| docs.join("\n"))
+}
+
+fn check_with<F>(command: &str, docs: &F) -> Vec<UnknownFlag>
+where
+    F: Fn(&str, &[&str]) -> Option<String>,
+{
+    let mut unknown = Vec::new();
+    for parsed in CommandValidator::parse(command) {
+        check_words(&parsed.program, &parsed.args, docs, &mut unknown);
+    }
+    let mut seen = HashSet::new();
+    unknown.retain(|u| seen.insert(u.clone()));
+    unknown
+}
+
+/// Every program `command` runs, including ones started by `sudo`, `xargs` or `find -exec`
+pub fn invoked_programs(command: &str) -> Vec<String> {
+    let mut programs = Vec::new();
+    for parsed in CommandValidator::parse(command) {
+        collect_programs(&parsed.program, &parsed.args, &mut programs);
+    }
+    programs
+}
+
+fn collect_programs(program: &str, args: &[String], programs: &mut Vec<String>) {
+    programs.push(program.to_string());
+    let name = program.rsplit('/').next().unwrap_or(program);
+
+    if let Some((_, value_flags)) = WRAPPERS.iter().find(|(wrapper, _)| *wrapper == name) {
+        if let Some(start) = wrapped_command(args, value_flags) {
+            collect_programs(&args[start], &args[start + 1..], programs);
+        }
+    } else if name == "find" {
+        for (nested, nested_args) in split_find_exec(&mut args.to_vec()) {
+            collect_programs(&nested, &nested_args, programs);
+        }
+    }
+}
+
+fn check_words<F>(program: &str, args: &[String], docs: &F, unknown: &mut Vec<UnknownFlag>)
+where
+    F: Fn(&str, &[&str]) -> Option<String>,
+{
+    let program = program.rsplit('/').next().unwrap_or(program);
+
+    if let Some((_, value_flags)) = WRAPPERS.iter().find(|(name, _)| *name == program) {
+        if let Some(start) = wrapped_command(args, value_flags) {
+            check_words(&args[start], &args[start + 1..], docs, unknown);
+        }
+        return;
+    }
+
+    let mut args = args.to_vec();
+    if program == "find" {
+        for (nested, nested_args) in split_find_exec(&mut args) {
+            check_words(&nested, &nested_args, docs, unknown);
+        }
+    }
+
+    let Some(top) = docs(program, &[]) else {
+        return;
+    };
+    let mut known = known_flags(&top);
+    if known.len() < MIN_FLAGS {
+        return;
+    }
+
+    // Follow subcommands (`git log`, `docker compose up`) listed in the help text
+    let mut path: Vec<&str> = Vec::new();
+    let mut listing = top;
+    let mut start = 0;
+    if SUBCOMMAND_TOOLS.contains(&program) {
+        for (i, arg) in args.iter().enumerate() {
+            if arg == "--" || path.len() == MAX_SUBCOMMAND_DEPTH {
+                break;
+            }
+            if arg.starts_with('-') || !lists_subcommand(&listing, arg) {
+                continue;
+            }
+            report(program, &path, &args[start..i], &known, unknown);
+            path.push(arg);
+            let Some(doc) = docs(program, &path) else {
+                return;
+            };
+            let sub_known = known_flags(&doc);
+            if sub_known.len() < MIN_FLAGS {
+                return;
+            }
+            known.extend(sub_known);
+            listing = doc;
+            start = i + 1;
+        }
+    }
+    report(program, &path, &args[start..], &known, unknown);
+}
+
+fn report(program: &str, path: &[&str], args: &[String], known: &HashSet<String>, unknown: &mut Vec<UnknownFlag>) {
+    let name = std::iter::once(program).chain(path.iter().copied()).collect::<Vec<_>>().join(" ");
+    for flag in unknown_options(args, known) {
+        unknown.push(UnknownFlag {
+            program: name.clone(),
+            flag,
+        });
+    }
+}
+
+/// Index of the wrapped program in a wrapper's arguments, e.g. `apt` in `sudo -u root apt install`
+fn wrapped_command(args: &[String], value_flags: &[&str]) -> Option<usize> {
+    let mut skip_value = false;
+    for (i, arg) in args.iter().enumerate() {
+        if skip_value {
+            skip_value = false;
+        } else if value_flags.contains(&arg.as_str()) {
+            skip_value = true;
+        } else if !arg.starts_with('-') && !arg.contains('=') && !is_duration(arg) {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// `5`, `1.5`, `10s`: the duration `timeout` and `sleep`-like wrappers take first
+fn is_duration(word: &str) -> bool {
+    let number = word.trim_end_matches(['s', 'm', 'h', 'd']);
+    !number.is_empty() && number.chars().all(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Remove `-exec cmd args... ;` sections from `find` arguments, returning the commands
+fn split_find_exec(args: &mut Vec<String>) -> Vec<(String, Vec<String>)> {
+    let mut nested = Vec::new();
+    let mut kept = Vec::new();
+    let mut iter = std::mem::take(args).into_iter();
+
+    while let Some(arg) = iter.next() {
+        let is_exec = FIND_EXEC.contains(&arg.as_str());
+        kept.push(arg);
+        if is_exec {
+            let words: Vec<String> = iter.by_ref().take_while(|w| w != ";" && w != "+").collect();
+            if let Some((program, rest)) = words.split_first() {
+                nested.push((program.clone(), rest.to_vec()));
+            }
+        }
+    }
+    *args = kept;
+    nested
+}
+
+/// Every `-x`, `--long` and `-long` option named in documentation text
+///
+/// `--[no-]flag` adds both spellings.
+pub fn known_flags(doc: &str) -> HashSet<String> {
+    static FLAG: OnceLock<Regex> = OnceLock::new();
+    let flag = FLAG.get_or_init(|| Regex::new(r"(?:^|[^\w-])(--?)(\[no-\])?([A-Za-z0-9?][\w-]*)").unwrap());
+
+    let mut flags = HashSet::new();
+    for caps in flag.captures_iter(doc) {
+        let dashes = &caps[1];
+        let name = caps[3].trim_end_matches('-');
+        flags.insert(format!("{}{}", dashes, name));
+        if caps.get(2).is_some() {
+            flags.insert(format!("{}no-{}", dashes, name));
+        }
+    }
+    flags
+}
+
+/// Whether an indented line of `listing` starts with `word`, as subcommand tables do
+fn lists_subcommand(listing: &str, word: &str) -> bool {
+    listing.lines().any(|line| {
+        line.starts_with(char::is_whitespace)
+            && line
+                .trim_start()
+                .strip_prefix(word)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', ',', ':']))
+    })
+}
+
+/// Options in `args` that `known` doesn't cover, up to a `--` end-of-options marker
+pub fn unknown_options(args: &[String], known: &HashSet<String>) -> Vec<String> {
+    args.iter()
+        .take_while(|arg| *arg != "--")
+        .filter(|arg| arg.starts_with('-') && arg.len() > 1)
+        .filter(|arg| !is_known(arg, known))
+        .map(|arg| arg.split('=').next().unwrap_or(arg).to_string())
+        .collect()
+}
+
+fn is_known(arg: &str, known: &HashSet<String>) -> bool {
+    if ["-h", "--help", "--version"].contains(&arg) {
+        return true;
+    }
+
+    if let Some(long) = arg.strip_prefix("--") {
+        let name = long.split('=').next().unwrap_or(long);
+        let flag = format!("--{}", name);
+        return known.contains(&flag)
+            || name.strip_prefix("no-").is_some_and(|rest| known.contains(&format!("--{}", rest)))
+            // getopt_long accepts unambiguous prefixes such as --verb for --verbose
+            || (name.len() >= 3 && known.iter().any(|k| k.starts_with(&flag)));
+    }
+
+    let rest = &arg[1..];
+    // Negative numbers and counts (`head -20`, `find -mtime -7`)
+    if rest.starts_with(|c: char| c.is_ascii_digit()) {
+        return true;
+    }
+    // A single-dash long option (`find -name`) or a short flag, possibly
+    // bundled with others or with its value attached (`-la`, `-n5`, `-t,`)
+    let first = rest.chars().next().map(|c| format!("-{}", c)).unwrap_or_default();
+    known.contains(arg) || known.contains(arg.split('=').next().unwrap_or(arg)) || known.contains(&first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LS_HELP: &str = "\
+Usage: ls [OPTION]... [FILE]...
+  -a, --all                  do not ignore entries starting with .
+  -l                         use a long listing format
+  -S                         sort by file size, largest first
+      --sort=WORD            sort by WORD instead of name
+      --color[=WHEN]         color the output WHEN
+";
+
+    const GIT_HELP: &str = "\
+usage: git [-v | --version] [-h | --help] [-C <path>] [-c <name>=<value>]
+           [--[no-]pager] [--git-dir=<path>]
+
+   clone     Clone a repository into a new directory
+   log       Show commit logs
+   stash     Stash the changes in a dirty working directory away
+";
+
+    const GIT_LOG: &str = "\
+OPTIONS
+       --follow
+           Continue listing the history of a file beyond renames.
+       --oneline
+           This is a shorthand for \"--pretty=oneline --abbrev-commit\" used together.
+       -n <number>, --max-count=<number>
+           Limit the number of commits to output.
+";
+
+    const FIND_MAN: &str = "\
+       -name pattern
+              Base of file name matches shell pattern pattern.
+       -mtime n
+              File's data was last modified n*24 hours ago.
+       -exec command ;
+              Execute command.
+       -type c
+              File is of type c.
+";
+
+    fn docs(program: &str, subcommands: &[&str]) -> Option<String> {
+        match (program, subcommands) {
+            ("ls", []) => Some(LS_HELP.to_string()),
+            ("git", []) => Some(GIT_HELP.to_string()),
+            ("git", ["log"]) => Some(GIT_LOG.to_string()),
+            ("find", []) => Some(FIND_MAN.to_string()),
+            _ => None,
+        }
+    }
+
+    fn unknown(command: &str) -> Vec<String> {
+        check_with(command, &docs).iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_known_flags() {
+        let flags = known_flags(GIT_HELP);
+        for flag in ["-v", "--version", "-C", "--pager", "--no-pager", "--git-dir"] {
+            assert!(flags.contains(flag), "missing {}", flag);
+        }
+        assert!(known_flags(LS_HELP).contains("--color"));
+        assert!(known_flags(FIND_MAN).contains("-mtime"));
+        assert!(!known_flags("a well-known file-name").contains("-known"));
+    }
+
+    #[test]
+    fn test_accepts_documented_flags() {
+        assert!(unknown("ls -la --sort=size --col").is_empty());
+        assert!(unknown("ls -S -- -weird-file-name").is_empty());
+        assert!(unknown("git -C repo log --oneline -n 5 --no-follow").is_empty());
+        assert!(unknown("find . -name '*.log' -mtime -7 -type f").is_empty());
+        assert!(unknown("cat file | grep --frobnicate x").is_empty());
+    }
+
+    #[test]
+    fn test_reports_undocumented_flags() {
+        assert_eq!(unknown("ls -la --human-sizes"), vec!["ls has no --human-sizes option"]);
+        assert_eq!(unknown("git log --oneline --graphs"), vec!["git log has no --graphs option"]);
+        assert_eq!(unknown("find . -nmae x -type f"), vec!["find has no -nmae option"]);
+        assert_eq!(unknown("sudo -u root ls -Z | sort"), vec!["ls has no -Z option"]);
+        assert_eq!(
+            unknown("find . -type f -exec ls -Q {} \\; -mtime 1"),
+            vec!["ls has no -Q option"]
+        );
+    }
+
+    #[test]
+    fn test_invoked_programs() {
+        assert_eq!(
+            invoked_programs("sudo -u www timeout 5s fd -e log | xargs -I{} rg TODO {} && find . -exec stat {} +"),
+            vec!["sudo", "timeout", "fd", "xargs", "rg", "find", "stat"]
+        );
+    }
+
+    #[test]
+    fn test_no_verdict_without_subcommand_docs() {
+        // `git stash` is listed but has no documentation here
+        assert!(unknown("git stash --keep-index").is_empty());
+    }
+}
@@ -1,12 +1,31 @@
 // Library exports for testing
+pub mod binary_check;
 pub mod command_validator;
 pub mod config;
+pub mod config_schema;
 pub mod context_agent;
+pub mod core_utils;
 pub mod credentials;
+pub mod doc_index;
+pub mod eval;
+pub mod flag_check;
+pub mod history;
 pub mod llm_client;
 pub mod migration;
+pub mod paths;
+pub mod preview;
+pub mod privacy;
+pub mod profile;
+pub mod process_util;
+pub mod project;
 pub mod prompt_sanitizer;
+pub mod prompt_template;
+pub mod providers;
+pub mod redact;
+pub mod sandbox;
 pub mod setup;
 pub mod system_info;
+pub mod tool_catalog;
 pub mod tool_detection;
+pub mod tool_versions;
 pub mod verification;
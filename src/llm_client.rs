@@ -3,6 +3,8 @@ use futures::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+use crate::redact::redact_secrets;
+
 #[derive(Debug, Clone)]
 pub enum Backend {
     Ollama,
@@ -91,12 +93,16 @@ struct Choice {
     delta: Option<ChatMessage>,
 }
 
+/// Base URL of the OpenAI API; other OpenAI-compatible servers can be set with `openai_url`
+pub const DEFAULT_OPENAI_URL: &str = "https://api.openai.com/v1";
+
 pub struct LLMClient {
     client: Client,
     backend: Backend,
     model: String,
     api_key: Option<String>,
     ollama_url: String,
+    openai_url: String,
 }
 
 impl LLMClient {
@@ -114,22 +120,66 @@ impl LLMClient {
             model,
             api_key,
             ollama_url,
+            openai_url: DEFAULT_OPENAI_URL.to_string(),
+        }
+    }
+
+    /// Send `openai` backend requests to an OpenAI-compatible server such as vLLM
+    pub fn with_openai_url(mut self, url: String) -> Self {
+        self.openai_url = url;
+        self
+    }
+
+    /// The OpenAI chat endpoint and the key to send; self-hosted servers often need none
+    fn openai_endpoint(&self) -> Result<(String, Option<&String>)> {
+        let base = self.openai_url.trim_end_matches('/');
+        if self.api_key.is_none() && base == DEFAULT_OPENAI_URL {
+            return Err(anyhow!("OpenAI API key not configured. Set OPENAI_API_KEY environment variable"));
         }
+        Ok((format!("{}/chat/completions", base), self.api_key.as_ref()))
     }
 
     pub async fn generate(&self, prompt: &str, system_context: &str) -> Result<()> {
+        let (prompt, system_context) = self.prepare(prompt, system_context);
+
         match self.backend {
-            Backend::Ollama => self.generate_ollama(prompt, system_context).await,
-            Backend::Groq => self.generate_groq(prompt, system_context).await,
-            Backend::OpenAI => self.generate_openai(prompt, system_context).await,
+            Backend::Ollama => self.generate_ollama(&prompt, &system_context).await,
+            Backend::Groq => self.generate_groq(&prompt, &system_context).await,
+            Backend::OpenAI => self.generate_openai(&prompt, &system_context).await,
         }
     }
 
     pub async fn generate_and_collect(&self, prompt: &str, system_context: &str) -> Result<String> {
+        let (prompt, system_context) = self.prepare(prompt, system_context);
+
+        match self.backend {
+            Backend::Ollama => self.generate_and_collect_ollama(&prompt, &system_context).await,
+            Backend::Groq => self.generate_and_collect_groq(&prompt, &system_context).await,
+            Backend::OpenAI => self.generate_and_collect_openai(&prompt, &system_context).await,
+        }
+    }
+
+    /// Final pass over everything that is sent to the backend: strip secrets
+    fn prepare(&self, prompt: &str, system_context: &str) -> (String, String) {
+        (redact_secrets(prompt), redact_secrets(system_context))
+    }
+
+    /// Render exactly what `generate_and_collect` would send, without sending it
+    pub fn render_request(&self, prompt: &str, system_context: &str) -> String {
+        let (prompt, system_context) = self.prepare(prompt, system_context);
+
         match self.backend {
-            Backend::Ollama => self.generate_and_collect_ollama(prompt, system_context).await,
-            Backend::Groq => self.generate_and_collect_groq(prompt, system_context).await,
-            Backend::OpenAI => self.generate_and_collect_openai(prompt, system_context).await,
+            Backend::Ollama => format!(
+                "POST {}/api/generate (model: {})\n\n{}\n\n{}",
+                self.ollama_url, self.model, system_context, prompt
+            ),
+            Backend::Groq | Backend::OpenAI => format!(
+                "POST {} chat completion (model: {})\n\n[system]\n{}\n\n[user]\n{}",
+                self.backend.as_str(),
+                self.model,
+                system_context,
+                prompt
+            ),
         }
     }
 
@@ -249,10 +299,7 @@ impl LLMClient {
     }
 
     async fn generate_openai(&self, prompt: &str, system_context: &str) -> Result<()> {
-        let api_key = self.api_key.as_ref()
-            .ok_or_else(|| anyhow!("OpenAI API key not configured. Set OPENAI_API_KEY environment variable"))?;
-
-        let url = "https://api.openai.com/v1/chat/completions";
+        let (url, api_key) = self.openai_endpoint()?;
 
         let request = ChatCompletionRequest {
             model: self.model.clone(),
@@ -269,11 +316,14 @@ impl LLMClient {
             stream: true,
         };
 
-        let response = self.client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", api_key))
+        let mut request = self.client
+            .post(&url)
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(&request);
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        let response = request
             .send()
             .await
             .context("Failed to connect to OpenAI API")?;
@@ -466,10 +516,7 @@ impl LLMClient {
     }
 
     async fn generate_and_collect_openai(&self, prompt: &str, system_context: &str) -> Result<String> {
-        let api_key = self.api_key.as_ref()
-            .ok_or_else(|| anyhow!("OpenAI API key not configured. Set OPENAI_API_KEY environment variable"))?;
-
-        let url = "https://api.openai.com/v1/chat/completions";
+        let (url, api_key) = self.openai_endpoint()?;
 
         let request = ChatCompletionRequest {
             model: self.model.clone(),
@@ -486,11 +533,14 @@ impl LLMClient {
             stream: true,
         };
 
-        let response = self.client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", api_key))
+        let mut request = self.client
+            .post(&url)
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(&request);
+        if let Some(api_key) = api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+        let response = request
             .send()
             .await
             .context("Failed to connect to OpenAI API")?;
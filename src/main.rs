@@ -1,23 +1,49 @@
+mod binary_check;
 mod cli;
 mod command_validator;
 mod config;
+mod config_schema;
 mod context_agent;
+mod core_utils;
 mod credentials;
+mod doc_index;
+mod eval;
+mod flag_check;
+mod history;
 mod llm_client;
 mod migration;
+mod paths;
+mod preview;
+mod privacy;
+mod profile;
+mod process_util;
+mod project;
 mod prompt_sanitizer;
+mod prompt_template;
+mod providers;
+mod redact;
+mod sandbox;
 mod setup;
 mod system_info;
+mod tool_catalog;
 mod tool_detection;
+mod tool_versions;
 mod update;
 mod verification;
 
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use cli::{Cli, Commands};
-use config::Config;
-use context_agent::ContextAgent;
+use dialoguer::Confirm;
+use binary_check::BinaryCheck;
+use cli::{Cli, Commands, ConfigAction, EvalArgs, IndexAction, ProfileAction};
+use config::{Config, ConfigSource, LayeredConfig};
+use context_agent::{ContextAgent, SkippedProvider};
+use doc_index::DocIndex;
+use eval::{CaseResult, Check, Corpus, Report};
+use flag_check::CheckMode;
 use llm_client::{Backend, LLMClient};
+use privacy::PrivacyLevel;
+use prompt_template::{Instructions, Prompts};
 use rustyline::error::ReadlineError;
 use rustyline::DefaultEditor;
 use serde::{Deserialize, Serialize};
@@ -32,77 +58,263 @@ struct CommandResponse {
     description: String,
 }
 
+/// Per-run options that change what happens after a command is generated
+#[derive(Debug, Clone, Copy, Default)]
+struct QueryOptions {
+    /// Run read-only previews of destructive commands
+    preview: bool,
+    /// Trial-run the command in a sandbox
+    try_run: bool,
+    /// Report context providers that were skipped
+    verbose: bool,
+    /// What to do about flags the installed tools don't document
+    flag_check: CheckMode,
+    /// What to do about programs that aren't installed
+    binary_check: CheckMode,
+}
+
+/// Interactive history, in the state directory; moved there from the config
+/// directory where older versions kept it
 fn history_path() -> Result<PathBuf> {
-    let home = std::env::var("HOME").context("HOME environment variable not set")?;
-    Ok(PathBuf::from(home).join(".config/ez-term/history.txt"))
+    let path = paths::state_dir()?.join("history.txt");
+    let legacy = paths::config_dir()?.join("history.txt");
+    if !path.exists() && legacy.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&legacy, &path)
+            .or_else(|_| std::fs::copy(&legacy, &path).map(|_| ()))
+            .context("Failed to move history to the state directory")?;
+    }
+    Ok(path)
 }
 
-async fn process_query(
+/// Build the (query, system prompt) pair sent to the LLM, plus the context providers that were skipped
+async fn build_prompt(
     query: &str,
-    client: &LLMClient,
-    context: &str,
+    prompts: &Prompts,
     agent: &ContextAgent,
-) -> Result<()> {
+    tools: &ToolDetection,
+) -> (String, String, Vec<SkippedProvider>) {
     // Step 1: Sanitize user input to prevent prompt injection
     let sanitized_query = prompt_sanitizer::sanitize_user_input(query);
 
-    // Step 2: Get agentic context based on the query
-    let agentic_context = agent.get_relevant_context(&sanitized_query);
+    // Step 2: Get agentic context based on the query (providers run concurrently, time-bounded)
+    let gathered = agent.gather(&sanitized_query).await;
 
-    // Step 3: Create structured system prompt with clear boundaries
-    let system_prompt = prompt_sanitizer::create_system_prompt(context);
+    // Step 3: Fill in the system prompt template, calling out the installed tools that fit this query
+    let relevant_tools = tools.format_for_query(&sanitized_query);
+    let system_prompt = prompts.system_prompt(relevant_tools.as_deref(), &gathered.context);
 
-    // Combine with agentic context
-    let full_context = if !agentic_context.is_empty() {
-        format!("{}\n{}", system_prompt, agentic_context)
-    } else {
-        system_prompt
-    };
+    (sanitized_query, system_prompt, gathered.skipped)
+}
 
-    // Step 4: Generate command from LLM
-    let response = client.generate_and_collect(&sanitized_query, &full_context).await?;
+fn report_skipped(skipped: &[SkippedProvider]) {
+    for provider in skipped {
+        eprintln!("context: skipped {} ({})", provider.name, provider.reason);
+    }
+}
 
-    // Step 5: Parse response
-    let (description, command) = match serde_json::from_str::<CommandResponse>(&response) {
-        Ok(cmd_response) => (cmd_response.description, cmd_response.command),
-        Err(_) => {
-            // Fallback: try to extract JSON from response
-            if let Some(json_start) = response.find('{') {
-                if let Some(json_end) = response.rfind('}') {
-                    let json_str = &response[json_start..=json_end];
-                    if let Ok(cmd_response) = serde_json::from_str::<CommandResponse>(json_str) {
-                        (cmd_response.description, cmd_response.command)
-                    } else {
-                        // Final fallback: just print the raw response
-                        println!("{}", response);
-                        return Err(anyhow!("Failed to parse JSON response"));
-                    }
-                } else {
-                    println!("{}", response);
-                    return Err(anyhow!("Failed to parse JSON response"));
+/// `ez context --show`: print exactly what would be sent for a query
+async fn show_context(
+    query: &str,
+    client: &LLMClient,
+    prompts: &Prompts,
+    agent: &ContextAgent,
+    tools: &ToolDetection,
+    privacy: PrivacyLevel,
+) {
+    let (sanitized_query, full_context, skipped) = build_prompt(query, prompts, agent, tools).await;
+
+    eprintln!("Privacy level: {}", privacy.as_str());
+    eprintln!("Context providers: {}", agent.provider_names().join(", "));
+    if let Some(instructions) = &prompts.instructions {
+        eprintln!("Project instructions: {}", instructions.path.display());
+    }
+    report_skipped(&skipped);
+
+    let detected = redact::find_secrets(&format!("{}\n{}", full_context, sanitized_query));
+    if !detected.is_empty() {
+        eprintln!("Redacted before sending: {}\n", detected.join(", "));
+    }
+
+    println!("{}", client.render_request(&sanitized_query, &full_context));
+}
+
+/// `ez eval`: run each case through the same prompt as a real query and check the command
+async fn run_eval(
+    eval_args: &EvalArgs,
+    client: &LLMClient,
+    prompts: &Prompts,
+    agent: &ContextAgent,
+    tools: &ToolDetection,
+    mut report: Report,
+) -> Result<()> {
+    let mut corpus = Corpus::load(&eval_args.corpus)?;
+    if let Some(filter) = &eval_args.filter {
+        corpus.filter(filter);
+    }
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let validator = command_validator::CommandValidator::new();
+
+    for case in &corpus.cases {
+        let (query, system_prompt, _) = build_prompt(&case.query, prompts, agent, tools).await;
+
+        // Latency covers generation only, not context gathering
+        let start = std::time::Instant::now();
+        let response = client.generate_and_collect(&query, &system_prompt).await;
+        let latency = start.elapsed();
+
+        let generated = match response {
+            Ok(response) => parse_command_response(&response)
+                .ok_or_else(|| "no {\"command\", \"description\"} JSON in the response".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let (command, checks) = match generated {
+            Ok(generated) => {
+                let mut checks = vec![Check::new("response", None)];
+                // A validator error fails this case, not the whole run
+                let risk = validator.validate(&generated.command);
+                match &risk {
+                    Ok(risk) => checks.extend(case.expect.check(&generated.command, risk)),
+                    Err(e) => checks.push(Check::new("validator", Some(e.to_string()))),
                 }
-            } else {
-                println!("{}", response);
-                return Err(anyhow!("Failed to parse JSON response"));
+                // With --no-sandbox the check is left out rather than failed
+                if case.expect.sandbox && !eval_args.no_sandbox {
+                    let trial = match risk {
+                        Ok(command_validator::RiskLevel::Critical) => Err(anyhow!("critical commands are not trial-run")),
+                        _ => sandbox::run_in_sandbox(&generated.command, &cwd),
+                    };
+                    let failure = match trial {
+                        Ok(trial) if trial.succeeded() => None,
+                        Ok(trial) => Some(match trial.exit_code {
+                            Some(code) => format!("exited with {}", code),
+                            None => "timed out".to_string(),
+                        }),
+                        Err(e) => Some(e.to_string()),
+                    };
+                    checks.push(Check::new("sandbox", failure));
+                }
+                (Some(generated.command), checks)
             }
+            Err(failure) => (None, vec![Check::new("response", Some(failure))]),
+        };
+
+        let result = CaseResult { id: case.id.clone(), command, latency, checks };
+        if !eval_args.json {
+            println!("{}", result.format());
         }
+        report.results.push(result);
+    }
+
+    if eval_args.json {
+        println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+    } else {
+        print!("\n{}", report.summary());
+    }
+
+    if let Some(min) = eval_args.min_pass_rate {
+        if report.pass_rate() < min {
+            return Err(anyhow!("Pass rate {:.1}% is below --min-pass-rate {}", report.pass_rate(), min));
+        }
+    }
+    Ok(())
+}
+
+/// The `{"command", "description"}` object in a model response, even with prose around it
+fn parse_command_response(response: &str) -> Option<CommandResponse> {
+    if let Ok(parsed) = serde_json::from_str::<CommandResponse>(response) {
+        return Some(parsed);
+    }
+    let json_start = response.find('{')?;
+    let json_end = response.rfind('}')?;
+    serde_json::from_str(response.get(json_start..=json_end)?).ok()
+}
+
+/// Something a post-generation check found wrong with a command
+struct CommandProblem {
+    /// The check's configured reaction
+    mode: CheckMode,
+    /// Told to the model when regenerating
+    summary: String,
+    /// Printed above the command
+    warning: String,
+}
+
+/// Run the enabled post-generation checks: missing programs, then undocumented flags
+fn check_command(command: &str, options: QueryOptions, binaries: &BinaryCheck) -> Vec<CommandProblem> {
+    let mut problems = Vec::new();
+
+    if options.binary_check != CheckMode::Off {
+        problems.extend(binaries.missing(command).into_iter().map(|missing| CommandProblem {
+            mode: options.binary_check,
+            summary: format!("{} is not installed", missing.binary),
+            warning: missing.to_string(),
+        }));
+    }
+
+    if options.flag_check != CheckMode::Off {
+        problems.extend(flag_check::check(command).into_iter().map(|flag| CommandProblem {
+            mode: options.flag_check,
+            summary: flag.to_string(),
+            warning: format!("{} (not in its --help or man page)", flag),
+        }));
+    }
+
+    problems
+}
+
+async fn process_query(
+    query: &str,
+    client: &LLMClient,
+    prompts: &Prompts,
+    agent: &ContextAgent,
+    tools: &ToolDetection,
+    binaries: &BinaryCheck,
+    options: QueryOptions,
+) -> Result<()> {
+    let (sanitized_query, full_context, skipped) = build_prompt(query, prompts, agent, tools).await;
+    if options.verbose {
+        report_skipped(&skipped);
+    }
+
+    // Step 4: Generate command from LLM (secrets are redacted by the client before sending)
+    let response = client.generate_and_collect(&sanitized_query, &full_context).await?;
+
+    // Step 5: Parse response
+    let Some(mut generated) = parse_command_response(&response) else {
+        println!("{}", response);
+        return Err(anyhow!("Failed to parse JSON response"));
     };
 
-    // Step 6: Validate command for security risks
+    // Step 6: Check that every program exists and every flag is documented
+    let mut problems = check_command(&generated.command, options, binaries);
+    if problems.iter().any(|p| p.mode == CheckMode::Regenerate) {
+        let summaries: Vec<&str> = problems.iter().map(|p| p.summary.as_str()).collect();
+        let retry_query = prompts.retry_prompt(&sanitized_query, &generated.command, &summaries);
+        let response = client.generate_and_collect(&retry_query, &full_context).await?;
+        if let Some(retried) = parse_command_response(&response) {
+            problems = check_command(&retried.command, options, binaries);
+            generated = retried;
+        }
+    }
+    let CommandResponse { description, command } = generated;
+
+    // Step 7: Validate command for security risks
     let validator = command_validator::CommandValidator::new();
 
-    match validator.validate(&command) {
+    let risk = validator.validate(&command);
+    let critical = matches!(risk, Ok(command_validator::RiskLevel::Critical));
+    match risk {
         Ok(command_validator::RiskLevel::Safe) => {
             // Safe command - print normally
             println!("💡 {}\n", description);
-            println!("{}", command);
         }
         Ok(command_validator::RiskLevel::Medium) => {
             // Risky command - print warning but still output command
             let warning = validator.get_warning_message(&command);
             println!("💡 {}\n", description);
             println!("warning: {}", warning);
-            println!("{}", command);
         }
         Ok(command_validator::RiskLevel::Critical) => {
             // Critical command - print strong warning AND command
@@ -110,7 +322,6 @@ async fn process_query(
             let critical_msg = validator.get_critical_message(&command);
             println!("💡 {}\n", description);
             println!("critical: {}", critical_msg);
-            println!("{}", command);
         }
         Err(e) => {
             // Validation error (shouldn't happen with current implementation)
@@ -119,6 +330,320 @@ async fn process_query(
         }
     }
 
+    for problem in &problems {
+        println!("warning: {}", problem.warning);
+    }
+
+    // Step 8: Show a read-only preview for destructive commands
+    // (printed before the command, which must stay the last line for the shell wrappers)
+    for command_preview in preview::build_previews(&command_validator::CommandValidator::parse(&command)) {
+        println!("preview: {} ({})", command_preview.command, command_preview.summary);
+        if options.preview {
+            match preview::run_preview(&command_preview) {
+                Ok(output) => println!("{}", output),
+                Err(e) => println!("error: {}", e),
+            }
+        }
+    }
+
+    // Step 9: Optionally observe the command in a throwaway sandbox
+    // (never for critical commands: the sandbox is not the only thing between them and the disk)
+    if options.try_run && critical {
+        println!("error: --try does not run critical commands, even in the sandbox");
+    } else if options.try_run {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        match sandbox::run_in_sandbox(&command, &cwd) {
+            Ok(report) => println!("{}", report.format()),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+
+    println!("{}", command);
+
+    Ok(())
+}
+
+/// Every config layer, with `--backend` and `--model` on top
+fn load_config(args: &Cli) -> Result<LayeredConfig> {
+    let mut layers = load_layers(args)?;
+    layers.apply_profile(&std::env::current_dir()?)?;
+    Ok(layers)
+}
+
+/// The config files, `EZ_*` variables and CLI flags, before a profile is applied
+fn load_layers(args: &Cli) -> Result<LayeredConfig> {
+    let mut layers = LayeredConfig::load()?;
+    let flags = [("backend", &args.backend), ("model", &args.model), ("profile", &args.profile)];
+    for (key, value) in flags {
+        if let Some(value) = value {
+            layers.set(key, value.clone().into(), ConfigSource::Cli(format!("--{}", key)));
+        }
+    }
+    Ok(layers)
+}
+
+/// `ez config ...`
+///
+/// Runs before the layers are loaded, but only `edit`, `path` and `validate` work
+/// when a config file doesn't parse; the other actions report the parse error.
+fn run_config(action: &ConfigAction, args: &Cli) -> Result<()> {
+    match action {
+        ConfigAction::Show { origin } => print!("{}", load_config(args)?.show(*origin)?),
+        ConfigAction::Get { key } => {
+            config_schema::schema()
+                .lookup(key)
+                .ok_or_else(|| anyhow!("Unknown config key '{}' (see `ez config list`)", key))?;
+            match load_config(args)?.get(key) {
+                Some(_) if key.ends_with("api_key") => println!("<redacted>"),
+                Some(toml::Value::String(value)) => println!("{}", value),
+                Some(value) => println!("{}", value),
+                None => return Err(anyhow!("{} is not set", key)),
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            set_user_config(key, value)?;
+            println!("{} set to {}", key, value);
+        }
+        ConfigAction::Unset { key } => {
+            let mut table = Config::load_user_table()?;
+            if config_schema::remove_key(&mut table, key) {
+                Config::save_user_table(table)?;
+                println!("{} unset", key);
+            } else {
+                println!("{} is not set in {}", key, Config::config_path()?.display());
+            }
+        }
+        ConfigAction::List => {
+            let layers = load_config(args)?;
+            for (key, kind) in config_schema::keys() {
+                let value = match layers.get(&key) {
+                    Some(_) if key.ends_with("api_key") => " = \"<redacted>\"".to_string(),
+                    Some(value) => format!(" = {}", value),
+                    None => String::new(),
+                };
+                let line = format!("{:<28} {:<32}{}", key, kind, value);
+                println!("{}", line.trim_end());
+            }
+        }
+        ConfigAction::Edit => edit_config()?,
+        ConfigAction::Validate => validate_config()?,
+        ConfigAction::Path => println!("{}", Config::config_path()?.display()),
+    }
+    Ok(())
+}
+
+/// `ez profile ...`
+fn run_profile(action: &ProfileAction, args: &Cli) -> Result<()> {
+    match action {
+        ProfileAction::List => {
+            let mut layers = load_layers(args)?;
+            let profiles = layers.config()?.profiles;
+            if profiles.is_empty() {
+                println!("No profiles configured (add one with `ez profile create`)");
+                return Ok(());
+            }
+            // Still list the profiles when the selected one doesn't exist
+            if let Err(e) = layers.apply_profile(&std::env::current_dir()?) {
+                eprintln!("⚠️  {}", e);
+            }
+            for (name, profile) in &profiles {
+                let active = layers.active_profile().filter(|(active, _)| active == name);
+                let line = format!(
+                    "{} {:<12} {:<8} {:<24} {}",
+                    if active.is_some() { "*" } else { " " },
+                    name,
+                    profile.backend.as_deref().unwrap_or("-"),
+                    profile.model.as_deref().unwrap_or("-"),
+                    active.map(|(_, why)| format!("({})", why)).unwrap_or_default()
+                );
+                println!("{}", line.trim_end());
+            }
+        }
+        ProfileAction::Use { name } => {
+            if !load_layers(args)?.config()?.profiles.contains_key(name) {
+                return Err(anyhow!("Unknown profile '{}' (see `ez profile list`)", name));
+            }
+            set_user_config("profile", name)?;
+            println!("Default profile set to: {}", name);
+        }
+        ProfileAction::Create {
+            name,
+            backend,
+            model,
+            ollama_url,
+            openai_url,
+            privacy,
+            flag_check,
+            binary_check,
+            paths,
+        } => {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(anyhow!("Profile names may only contain letters, digits, '-' and '_'"));
+            }
+            let mut table = Config::load_user_table()?;
+            let prefix = format!("profiles.{}", name);
+            if table.get("profiles").and_then(|profiles| profiles.get(name)).is_some() {
+                return Err(anyhow!("Profile '{}' already exists in {}", name, Config::config_path()?.display()));
+            }
+
+            let settings = [
+                ("backend", backend),
+                ("model", model),
+                ("ollama_url", ollama_url),
+                ("openai_url", openai_url),
+                ("privacy", privacy),
+                ("flag_check", flag_check),
+                ("binary_check", binary_check),
+            ];
+            let mut profile = toml::Table::new();
+            for (key, raw) in settings {
+                if let Some(raw) = raw {
+                    let value = config_schema::parse_value(&format!("{}.{}", prefix, key), raw)?;
+                    profile.insert(key.to_string(), value);
+                }
+            }
+            if !paths.is_empty() {
+                let paths = paths.iter().map(|path| toml::Value::String(path.clone())).collect();
+                profile.insert("paths".to_string(), toml::Value::Array(paths));
+            }
+
+            config_schema::set_key(&mut table, &prefix, toml::Value::Table(profile));
+            Config::save_user_table(table)?;
+            println!("Created profile {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// Set one key in the user's config file, refusing values that don't fit the schema
+fn set_user_config(key: &str, raw: &str) -> Result<()> {
+    let value = config_schema::parse_value(key, raw)?;
+    let mut table = Config::load_user_table()?;
+    config_schema::set_key(&mut table, key, value);
+    Config::save_user_table(table)
+}
+
+/// Edit a copy of the user's config file and only put it in place once it validates
+fn edit_config() -> Result<()> {
+    let path = Config::config_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create config directory")?;
+    }
+    let draft = path.with_file_name("config.edit.toml");
+    std::fs::write(&draft, std::fs::read_to_string(&path).unwrap_or_default())?;
+
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|e| !e.trim().is_empty()))
+        .unwrap_or_else(|| "vi".to_string());
+
+    loop {
+        // Through the shell so editors with arguments (`code --wait`) work
+        let status = std::process::Command::new("sh")
+            .args(["-c", &format!("{} \"$1\"", editor), "sh"])
+            .arg(&draft)
+            .status()
+            .with_context(|| format!("Failed to run {}", editor))?;
+        if !status.success() {
+            let _ = std::fs::remove_file(&draft);
+            return Err(anyhow!("{} exited with {}; config not saved", editor, status));
+        }
+
+        let contents = std::fs::read_to_string(&draft)?;
+        let errors = match toml::from_str::<toml::Table>(&contents) {
+            Ok(table) => config_schema::validate(&table),
+            Err(e) => vec![e.message().to_string()],
+        };
+        if errors.is_empty() {
+            std::fs::rename(&draft, &path).context("Failed to save config")?;
+            println!("Saved {}", path.display());
+            return Ok(());
+        }
+
+        eprintln!("Config has errors:");
+        for error in &errors {
+            eprintln!("  - {}", error);
+        }
+        let again = atty::is(atty::Stream::Stdin)
+            && Confirm::new().with_prompt("Edit again?").default(true).interact()?;
+        if !again {
+            let _ = std::fs::remove_file(&draft);
+            return Err(anyhow!("Config not saved"));
+        }
+    }
+}
+
+/// Check every config file that exists, reporting each file's problems
+fn validate_config() -> Result<()> {
+    let mut sources = vec![
+        ConfigSource::System(PathBuf::from(config::SYSTEM_CONFIG_PATH)),
+        ConfigSource::User(Config::config_path()?),
+    ];
+    if let Some(project) = std::env::current_dir().ok().and_then(|cwd| config::find_project_config(&cwd)) {
+        sources.push(ConfigSource::Project(project));
+    }
+
+    let mut checked = 0;
+    let mut invalid = 0;
+    for source in sources {
+        let (ConfigSource::System(path) | ConfigSource::User(path) | ConfigSource::Project(path)) = &source else {
+            continue;
+        };
+        let Ok(contents) = std::fs::read_to_string(path) else { continue };
+        checked += 1;
+
+        let errors = match toml::from_str::<toml::Table>(&contents) {
+            Ok(table) => config_schema::validate(&table),
+            Err(e) => vec![e.message().to_string()],
+        };
+        if errors.is_empty() {
+            println!("✓ {}", source);
+        } else {
+            invalid += 1;
+            println!("✗ {}", source);
+            for error in errors {
+                println!("    - {}", error);
+            }
+        }
+    }
+
+    match (checked, invalid) {
+        (0, _) => println!("No config files found"),
+        (_, 0) => {}
+        _ => return Err(anyhow!("{} of {} config files have errors", invalid, checked)),
+    }
+    Ok(())
+}
+
+/// `ez index build`: index the flags of installed tools for retrieval at query time
+async fn build_doc_index(config: &Config, embeddings: Option<&str>) -> Result<()> {
+    let path = DocIndex::default_path()?;
+
+    let mut tools = ToolDetection::detect(&config.tools).installed_paths();
+    for (tool, binary) in doc_index::core_tool_paths() {
+        if !tools.iter().any(|(name, _)| *name == tool) {
+            tools.push((tool, binary));
+        }
+    }
+
+    println!("Reading man pages and --help output of {} tools...", tools.len());
+    let mut index = DocIndex::build(&tools);
+    if index.is_empty() {
+        return Err(anyhow!("No man pages or --help output found for the installed tools"));
+    }
+
+    if let Some(model) = embeddings {
+        println!("Embedding {} entries with {}...", index.len(), model);
+        index.embed(&config.get_ollama_url(), model).await?;
+    }
+
+    index.save(&path)?;
+    println!(
+        "Indexed {} flag descriptions from {} tools into {}",
+        index.len(),
+        index.tool_count(),
+        path.display()
+    );
     Ok(())
 }
 
@@ -137,9 +662,16 @@ async fn main() -> Result<()> {
         return wizard.run().await;
     }
 
+    if let Some(Commands::Config { action }) = &args.command {
+        return run_config(action, &args);
+    }
+    if let Some(Commands::Profile { action }) = &args.command {
+        return run_profile(action, &args);
+    }
+
     // Check for first-run (no config exists) and trigger setup wizard
     let config_path = Config::config_path()?;
-    if !config_path.exists() && !args.init {
+    if !config_path.exists() && !args.init && args.command.is_none() {
         println!("Welcome to ez-term! It looks like this is your first time running the tool.");
         println!("Let's set up your configuration.\n");
 
@@ -150,7 +682,13 @@ async fn main() -> Result<()> {
     }
 
     // Load configuration
-    let mut config = Config::load()?;
+    let mut layers = load_config(&args)?;
+    let mut config = layers.config()?;
+    if args.verbose {
+        if let Some((name, selection)) = layers.active_profile() {
+            eprintln!("Using profile {} ({})", name, selection);
+        }
+    }
 
     // Check for migration (skip if running init or update commands)
     if !args.init && !matches!(args.command, Some(Commands::Init)) && !args.update {
@@ -161,7 +699,8 @@ async fn main() -> Result<()> {
                 if args.query.is_none() && atty::is(atty::Stream::Stdin) {
                     let _ = migration::check_and_migrate();
                     // Reload config after potential migration
-                    config = Config::load()?;
+                    layers = load_config(&args)?;
+                    config = layers.config()?;
                 }
             }
         }
@@ -178,25 +717,24 @@ async fn main() -> Result<()> {
 
     // Handle set backend
     if let Some(backend) = args.set_backend {
-        config.backend = Some(backend.clone());
-        config.save()?;
+        set_user_config("backend", &backend)?;
         println!("Default backend set to: {}", backend);
         return Ok(());
     }
 
     // Handle set model
     if let Some(model) = args.set_model {
-        config.model = Some(model.clone());
-        config.save()?;
+        set_user_config("model", &model)?;
         println!("Default model set to: {}", model);
         return Ok(());
     }
 
+    if let Some(Commands::Index { action: IndexAction::Build { embeddings } }) = &args.command {
+        return build_doc_index(&config, embeddings.as_deref()).await;
+    }
+
     // Determine backend
-    let backend_str = args
-        .backend
-        .or(config.backend.clone())
-        .unwrap_or_else(|| "groq".to_string());
+    let backend_str = config.get_backend();
 
     let backend = Backend::from_str(&backend_str)?;
 
@@ -210,10 +748,11 @@ async fn main() -> Result<()> {
     // Create client
     let client = LLMClient::new(
         backend.clone(),
-        args.model.or(config.model.clone()),
+        config.model.clone(),
         api_key,
         config.get_ollama_url(),
-    );
+    )
+    .with_openai_url(config.get_openai_url());
 
     // Handle list models
     if args.list_models {
@@ -238,72 +777,72 @@ async fn main() -> Result<()> {
     }
 
     // Detect system information and tools once
+    // Privacy level decides how much local context may be collected for this backend
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let privacy = config.privacy_level(backend.as_str(), &cwd);
+
     let system_info = SystemInfo::detect();
-    let tool_detection = ToolDetection::detect();
-    let context_agent = ContextAgent::new()?;
-
-    // Build context
-    let system_context = format!(
-        "System: {}\n{}",
-        system_info.format_context(),
-        tool_detection.format_context()
-    );
+    let tool_detection = ToolDetection::detect_with_privacy(privacy, &config.tools);
+    let binary_check = BinaryCheck::new(paths::home_dir().as_deref(), &system_info);
+    let mut context_agent = ContextAgent::new()?
+        .with_privacy(privacy)
+        .with_external_providers(&config.context_providers);
+    if let Some(index) = DocIndex::default_path().ok().and_then(|path| DocIndex::load(&path)) {
+        context_agent = context_agent.with_doc_index(index, config.get_ollama_url());
+    }
 
-    let context = format!(
-        "You are an expert CLI command generator. Your job is to generate REAL, EXECUTABLE shell commands that accomplish the user's goal.\n\n\
-        CRITICAL RULES:\n\
-        1. ALWAYS generate an actual command - NEVER use echo as a placeholder\n\
-        2. The command MUST be directly executable and accomplish the requested task\n\
-        3. Use real tools available on the system (shown below)\n\
-        4. Generate commands that actually DO something, not commands that just print text\n\
-        5. IMPORTANT: Generate commands specific to the user's OS (see system info below)\n\n\
-        Output format:\n\
-        - Output valid JSON only, on one line\n\
-        - JSON schema: {{\"command\": \"actual executable command\", \"description\": \"what this command does\"}}\n\
-        - NO prose before/after JSON, NO code fences, NO comments, NO trailing spaces\n\
-        - Description: 1-2 sentences explaining the command's purpose and key flags\n\n\
-        Command generation guidelines:\n\
-        - Use the ACTUAL tools installed on the user's system (see below)\n\
-        - Generate COMPLETE commands with all necessary flags and arguments\n\
-        - **MATCH the user's OS**: Use OS-specific commands and flag syntax (Linux/macOS/Windows)\n\
-        - **Linux**: Use GNU-style commands (apt/dnf, systemctl, --long-flags, /proc)\n\
-        - **macOS**: Use BSD-style commands (brew, launchctl, single-dash flags, different flag syntax)\n\
-        - **Windows**: Use PowerShell commands or cmd.exe built-ins\n\
-        - When user intent is ambiguous, prefer safe read-only operations (use -print, --dry-run, list-only modes)\n\
-        - When user explicitly requests destructive operations, generate the actual command they're asking for\n\
-        - Quote paths/globs properly (use quotes around paths with spaces)\n\
-        - For file operations: prefer find, ls, stat, du, grep (read-only)\n\
-        - For git: use status, log, diff, show (read-only by default)\n\
-        - For archives: use tar -tzf (list), zip -sf (show), unzip -l (list)\n\
-        - For system info: use df, du, free, top, ps, uname (safe read-only)\n\
-        - Combine steps with && only when ALL steps are safe/non-destructive\n\n\
-        Safety rules:\n\
-        - Generate the ACTUAL command the user is asking for\n\
-        - Be truthful and accurate - don't refuse or substitute commands\n\
-        - Command validation and safety checks happen after generation\n\
-        - Never expose/print credentials, API keys, passwords, tokens in commands\n\
-        - Explain clearly what the command does in the description field\n\n\
-        Example good responses:\n\
-        - User: \"find large files\" → {{\"command\": \"find . -type f -size +100M -exec ls -lh {{}} \\\\;\", \"description\": \"Finds files larger than 100MB in current directory and shows their size in human-readable format.\"}}\n\
-        - User: \"compress folder\" → {{\"command\": \"tar -czf archive.tar.gz .\", \"description\": \"Creates a gzip-compressed tar archive of the current directory.\"}}\n\
-        - User: \"show git changes\" → {{\"command\": \"git diff --stat\", \"description\": \"Shows a summary of changed files and the number of additions/deletions.\"}}\n\
-        - User: \"list processes\" (Linux) → {{\"command\": \"ps aux --sort=-%mem | head -20\", \"description\": \"Lists top 20 processes sorted by memory usage.\"}}\n\
-        - User: \"list processes\" (macOS) → {{\"command\": \"ps aux -m | head -20\", \"description\": \"Lists top 20 processes sorted by memory usage.\"}}\n\n\
-        Example bad responses (NEVER DO THIS):\n\
-        - {{\"command\": \"echo 'list files'\", ...}} ← WRONG: this just echoes text, doesn't list files\n\
-        - {{\"command\": \"echo 'You can use ls'\", ...}} ← WRONG: this is advice, not a command\n\
-        - {{\"command\": \"# ls -la\", ...}} ← WRONG: this is a comment, not executable\n\n\
-        User's system information:\n{}\n\n\
-        IMPORTANT: The user is on {}. Generate commands that work specifically on this OS!\n\n\
-        Now generate a REAL, EXECUTABLE command as JSON for this request:",
-        system_context,
-        system_info.os.to_uppercase()
-    );
+    // The parts of the prompt that stay the same for every query
+    let prompts = Prompts {
+        templates: config.prompts.clone(),
+        os: match privacy {
+            PrivacyLevel::None => "an unspecified OS".to_string(),
+            _ => system_info.os.to_uppercase(),
+        },
+        system: match privacy {
+            PrivacyLevel::Full => format!("System: {}", system_info.format_context()),
+            PrivacyLevel::Minimal => format!("System: {}", system_info.format_minimal_context()),
+            PrivacyLevel::None => "System information withheld (privacy mode)".to_string(),
+        },
+        tools: tool_detection.format_context(),
+        // Project instructions are project context, so they are only sent at privacy level "full"
+        instructions: match privacy {
+            PrivacyLevel::Full => Instructions::find(&cwd),
+            _ => None,
+        },
+    };
+
+    let options = QueryOptions {
+        preview: args.preview,
+        try_run: args.try_run,
+        verbose: args.verbose,
+        // Looking flags up runs `man` and `--help` for each program, so it is opt-in
+        flag_check: config.flag_check.unwrap_or(CheckMode::Off),
+        binary_check: config.binary_check.unwrap_or_default(),
+    };
+
+    if let Some(Commands::Eval(eval_args)) = &args.command {
+        let report = Report {
+            backend: backend_str.clone(),
+            model: config.model.clone().unwrap_or_else(|| backend.default_model().to_string()),
+            results: Vec::new(),
+        };
+        return run_eval(eval_args, &client, &prompts, &context_agent, &tool_detection, report).await;
+    }
+
+    // Handle context inspection
+    if let Some(Commands::Context { show, query }) = &args.command {
+        if *show {
+            show_context(query.as_deref().unwrap_or(""), &client, &prompts, &context_agent, &tool_detection, privacy).await;
+        } else {
+            println!("Use 'ez context --show [QUERY]' to print exactly what would be sent to the LLM");
+        }
+        return Ok(());
+    }
 
     // If query provided as argument, process it and exit (non-interactive mode)
     if let Some(query) = args.query {
         println!("Gathering system context...\n");
-        return process_query(&query, &client, &context, &context_agent).await;
+        return process_query(&query, &client, &prompts, &context_agent, &tool_detection, &binary_check, options).await;
     }
 
     // Check if stdin has data (piped input)
@@ -313,7 +852,7 @@ async fn main() -> Result<()> {
         std::io::stdin().read_to_string(&mut buffer)?;
         if !buffer.trim().is_empty() {
             println!("Gathering system context...\n");
-            return process_query(buffer.trim(), &client, &context, &context_agent).await;
+            return process_query(buffer.trim(), &client, &prompts, &context_agent, &tool_detection, &binary_check, options).await;
         }
     }
 
@@ -362,7 +901,7 @@ async fn main() -> Result<()> {
                 println!();
 
                 // Process query
-                let _ = process_query(query, &client, &context, &context_agent).await;
+                let _ = process_query(query, &client, &prompts, &context_agent, &tool_detection, &binary_check, options).await;
 
                 println!();
             }
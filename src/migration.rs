@@ -5,7 +5,7 @@ use crate::credentials::{store_credential, is_keyring_available};
 
 /// Check if migration is needed and prompt user
 pub fn check_and_migrate() -> Result<()> {
-    let config = Config::load()?;
+    let config = Config::load_user()?;
 
     // Already migrated or declined
     if config.migration_completed.unwrap_or(false) || config.migration_declined.unwrap_or(false) {
@@ -29,7 +29,7 @@ pub fn check_and_migrate() -> Result<()> {
 
     if !migrate {
         // User declined, remember choice
-        let mut config = Config::load()?;
+        let mut config = Config::load_user()?;
         config.migration_declined = Some(true);
         config.save()?;
 
@@ -51,7 +51,7 @@ pub fn check_and_migrate() -> Result<()> {
         eprintln!("   2. On Linux, install gnome-keyring or kwallet");
         eprintln!();
 
-        let mut config = Config::load()?;
+        let mut config = Config::load_user()?;
         config.migration_declined = Some(true);
         config.save()?;
         return Ok(());
@@ -64,7 +64,7 @@ pub fn check_and_migrate() -> Result<()> {
 }
 
 fn migrate_keys_to_keyring() -> Result<()> {
-    let config = Config::load()?;
+    let config = Config::load_user()?;
     let mut migrated_keys = Vec::new();
 
     // Migrate Groq key
@@ -83,7 +83,7 @@ fn migrate_keys_to_keyring() -> Result<()> {
 
     // Verify storage and update config
     if !migrated_keys.is_empty() {
-        let mut config = Config::load()?;
+        let mut config = Config::load_user()?;
 
         // Remove keys from config
         config.groq_api_key = None;
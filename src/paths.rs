@@ -0,0 +1,127 @@
+//! Where ez keeps its files, following the XDG base directory spec
+//!
+//! | Kind   | Contents                                       | Default                  |
+//! |--------|------------------------------------------------|--------------------------|
+//! | config | `config.toml`                                  | `~/.config/ez-term`      |
+//! | cache  | tool versions, the `ez index build` flag index | `~/.cache/ez-term`       |
+//! | state  | interactive history, logs                      | `~/.local/state/ez-term` |
+//!
+//! `EZ_CONFIG_DIR` moves everything under one directory (config at its top,
+//! the rest in `cache/` and `state/`) for containers and CI where
+//! `$HOME` is unset or read-only. Without it, `$HOME` or the matching `XDG_*`
+//! variable is required: there is no fallback to a shared directory like `/tmp`.
+
+use anyhow::{anyhow, Result};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Overrides every directory below
+pub const CONFIG_DIR_ENV: &str = "EZ_CONFIG_DIR";
+
+const APP_DIR: &str = "ez-term";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirKind {
+    Config,
+    Cache,
+    State,
+}
+
+impl DirKind {
+    /// `(XDG variable, default relative to $HOME, subdirectory of EZ_CONFIG_DIR)`
+    fn locations(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            DirKind::Config => ("XDG_CONFIG_HOME", ".config", ""),
+            DirKind::Cache => ("XDG_CACHE_HOME", ".cache", "cache"),
+            DirKind::State => ("XDG_STATE_HOME", ".local/state", "state"),
+        }
+    }
+}
+
+/// `$HOME`, if set to something
+pub fn home_dir() -> Option<PathBuf> {
+    non_empty(std::env::var_os("HOME")).map(PathBuf::from)
+}
+
+/// ez's directory of the given kind
+pub fn dir(kind: DirKind) -> Result<PathBuf> {
+    resolve(kind, |var| std::env::var_os(var)).ok_or_else(|| {
+        anyhow!(
+            "Cannot find a {} directory for ez-term: set HOME, {} or {}",
+            format!("{:?}", kind).to_lowercase(),
+            kind.locations().0,
+            CONFIG_DIR_ENV
+        )
+    })
+}
+
+pub fn config_dir() -> Result<PathBuf> {
+    dir(DirKind::Config)
+}
+
+pub fn cache_dir() -> Result<PathBuf> {
+    dir(DirKind::Cache)
+}
+
+pub fn state_dir() -> Result<PathBuf> {
+    dir(DirKind::State)
+}
+
+fn resolve(kind: DirKind, env: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let (xdg_var, home_default, override_subdir) = kind.locations();
+
+    if let Some(dir) = non_empty(env(CONFIG_DIR_ENV)) {
+        return Some(PathBuf::from(dir).join(override_subdir));
+    }
+
+    // The spec says relative XDG paths are invalid and should be ignored
+    let base = non_empty(env(xdg_var))
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| non_empty(env("HOME")).map(|home| PathBuf::from(home).join(home_default)))?;
+    Some(base.join(APP_DIR))
+}
+
+fn non_empty(value: Option<OsString>) -> Option<OsString> {
+    value.filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        move |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| OsString::from(v))
+    }
+
+    #[test]
+    fn test_home_defaults() {
+        let vars = [("HOME", "/home/ana")];
+        assert_eq!(resolve(DirKind::Config, env(&vars)), Some(PathBuf::from("/home/ana/.config/ez-term")));
+        assert_eq!(resolve(DirKind::Cache, env(&vars)), Some(PathBuf::from("/home/ana/.cache/ez-term")));
+        assert_eq!(resolve(DirKind::State, env(&vars)), Some(PathBuf::from("/home/ana/.local/state/ez-term")));
+    }
+
+    #[test]
+    fn test_xdg_variables() {
+        let vars = [
+            ("HOME", "/home/ana"),
+            ("XDG_CONFIG_HOME", "/cfg"),
+            ("XDG_STATE_HOME", "relative/state"),
+            ("XDG_CACHE_HOME", ""),
+        ];
+        assert_eq!(resolve(DirKind::Config, env(&vars)), Some(PathBuf::from("/cfg/ez-term")));
+        assert_eq!(resolve(DirKind::State, env(&vars)), Some(PathBuf::from("/home/ana/.local/state/ez-term")));
+        assert_eq!(resolve(DirKind::Cache, env(&vars)), Some(PathBuf::from("/home/ana/.cache/ez-term")));
+    }
+
+    #[test]
+    fn test_config_dir_override_without_home() {
+        let vars = [("EZ_CONFIG_DIR", "/ci/ez"), ("XDG_CACHE_HOME", "/cache")];
+        assert_eq!(resolve(DirKind::Config, env(&vars)), Some(PathBuf::from("/ci/ez")));
+        assert_eq!(resolve(DirKind::Cache, env(&vars)), Some(PathBuf::from("/ci/ez/cache")));
+        assert_eq!(resolve(DirKind::State, env(&vars)), Some(PathBuf::from("/ci/ez/state")));
+
+        assert_eq!(resolve(DirKind::Config, env(&[])), None);
+    }
+}
@@ -0,0 +1,836 @@
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::Chars;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::command_validator::ParsedCommand;
+use crate::process_util::output_with_limits;
+
+/// Maximum number of output lines shown when a preview is run
+const MAX_PREVIEW_LINES: usize = 50;
+
+/// Output kept from a preview process; anything past this is discarded
+const MAX_PREVIEW_BYTES: usize = 1024 * 1024;
+
+/// How long a preview may run before it is killed
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read-only stand-in for a destructive command
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preview {
+    /// Shell command that shows what the original would touch without changing anything
+    pub command: String,
+    /// Short description of what the preview output lists
+    pub summary: String,
+    /// What `run_preview` actually executes
+    run: Run,
+}
+
+/// How a preview is executed; programs get their arguments as argv, never through a shell
+#[derive(Debug, Clone, PartialEq)]
+enum Run {
+    /// Run `program args...` and show its output
+    List(Vec<Word>),
+    /// Diff each file against what `sed <script> file` prints
+    SedDiff { script: Vec<Word>, files: Vec<Word> },
+}
+
+/// A command-line word: its unquoted value and the text as written
+#[derive(Debug, Clone, PartialEq)]
+struct Word {
+    value: String,
+    raw: String,
+}
+
+impl Word {
+    /// A word the preview adds itself
+    fn literal(value: &str) -> Self {
+        Word {
+            value: value.to_string(),
+            raw: value.to_string(),
+        }
+    }
+
+    /// Expand the word the way the shell would: `~/` and `*`, `?`, `[...]` apply only when unquoted
+    fn expand(&self) -> Vec<String> {
+        if self.raw != self.value {
+            return vec![self.value.clone()];
+        }
+
+        let value = match (self.value.strip_prefix('~'), crate::paths::home_dir()) {
+            (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+                format!("{}{}", home.display(), rest)
+            }
+            _ => self.value.clone(),
+        };
+
+        glob(&value)
+    }
+}
+
+/// Build read-only previews for the destructive parts of a parsed command line
+///
+/// Supports `find ... -delete` / `-exec rm`, `rm`, `mv` and `sed -i`. Segments
+/// that are not destructive (or that cannot be previewed) are ignored.
+pub fn build_previews(commands: &[ParsedCommand]) -> Vec<Preview> {
+    commands.iter().filter_map(build_preview).collect()
+}
+
+/// Build a preview for a single simple command
+///
+/// Commands with `$(...)` or backticks in their arguments are not previewed,
+/// since the substitution would have to run to know what they touch.
+pub fn build_preview(command: &ParsedCommand) -> Option<Preview> {
+    if command.raw_args.iter().any(|a| a.contains("$(") || a.contains('`')) {
+        return None;
+    }
+
+    match command.program.as_str() {
+        "sudo" | "doas" => build_preview(&strip_privilege_wrapper(command)?),
+        "find" => preview_find(command),
+        "rm" => preview_rm(command),
+        "mv" => preview_mv(command),
+        "sed" => preview_sed(command),
+        _ => None,
+    }
+}
+
+/// Run a preview command and render its (truncated) output
+pub fn run_preview(preview: &Preview) -> Result<String> {
+    let text = match &preview.run {
+        Run::List(words) => {
+            let argv = expand_all(words);
+            let (stdout, stderr) = run_capped(&argv)?;
+            stdout + &stderr
+        }
+        Run::SedDiff { script, files } => {
+            let script = expand_all(script);
+            let mut text = String::new();
+            for file in expand_all(files) {
+                text.push_str(&sed_diff(&script, &file)?);
+            }
+            text
+        }
+    };
+
+    Ok(render_output(&text))
+}
+
+fn expand_all(words: &[Word]) -> Vec<String> {
+    words.iter().flat_map(Word::expand).collect()
+}
+
+/// Run `argv` with the preview timeout and output cap, returning stdout and stderr
+fn run_capped(argv: &[String]) -> Result<(String, String)> {
+    let (program, args) = argv.split_first().context("Empty preview command")?;
+    let output = output_with_limits(Command::new(program).args(args), PREVIEW_TIMEOUT, MAX_PREVIEW_BYTES)
+        .context("Failed to run preview command")?;
+
+    let Some(output) = output else {
+        bail!("Preview timed out after {}s", PREVIEW_TIMEOUT.as_secs());
+    };
+
+    Ok((
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    ))
+}
+
+/// Unified diff between `file` and the output of `sed <script> file`
+fn sed_diff(script: &[String], file: &str) -> Result<String> {
+    let mut argv = vec!["sed".to_string()];
+    argv.extend(script.iter().cloned());
+    argv.push(file.to_string());
+    let (edited, errors) = run_capped(&argv)?;
+    if !errors.is_empty() {
+        return Ok(errors);
+    }
+    if edited.len() >= MAX_PREVIEW_BYTES {
+        return Ok(format!("{}: too large to preview\n", file));
+    }
+
+    let (edited_path, mut edited_file) = create_temp_file()?;
+    let written = edited_file.write_all(edited.as_bytes());
+    drop(edited_file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&edited_path);
+        return Err(e).context("Failed to write preview file");
+    }
+    let label = format!("{} (after sed)", file);
+    let argv: Vec<String> = ["diff", "-u", "-L", file, "-L", &label, file, &edited_path.to_string_lossy()]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    let result = run_capped(&argv);
+    let _ = fs::remove_file(&edited_path);
+
+    let (diff, errors) = result?;
+    Ok(diff + &errors)
+}
+
+/// A new, private file in the temp directory
+///
+/// The name is unpredictable and the file is created with `O_EXCL`, so a symlink
+/// planted by another user can't redirect the write.
+fn create_temp_file() -> Result<(PathBuf, File)> {
+    for attempt in 0..100u32 {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let path = std::env::temp_dir().join(format!("ez-preview-{}-{}-{}.txt", std::process::id(), nanos, attempt));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).context("Failed to create preview file"),
+        }
+    }
+    bail!("Failed to create preview file: too many name collisions")
+}
+
+/// Pathname expansion for `pattern`; like the shell, a pattern with no matches stays as written
+fn glob(pattern: &str) -> Vec<String> {
+    if !pattern.contains(['*', '?', '[']) {
+        return vec![pattern.to_string()];
+    }
+
+    let (mut matches, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (vec!["/".to_string()], rest),
+        None => (vec![String::new()], pattern),
+    };
+
+    for component in rest.split('/') {
+        matches = matches
+            .iter()
+            .flat_map(|prefix| glob_component(prefix, component))
+            .collect();
+    }
+
+    let mut matches: Vec<String> = matches.into_iter().filter(|m| Path::new(m).exists()).collect();
+    if matches.is_empty() {
+        return vec![pattern.to_string()];
+    }
+    matches.sort();
+    matches
+}
+
+/// Entries of `prefix` matching one path component of a glob
+fn glob_component(prefix: &str, component: &str) -> Vec<String> {
+    let join = |name: &str| match prefix {
+        "" => name.to_string(),
+        p if p.ends_with('/') => format!("{}{}", p, name),
+        p => format!("{}/{}", p, name),
+    };
+
+    let Some(regex) = glob_regex(component) else {
+        return vec![if component.is_empty() { format!("{}/", prefix) } else { join(component) }];
+    };
+
+    let dir = if prefix.is_empty() { "." } else { prefix };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| !name.starts_with('.') || component.starts_with('.'))
+        .filter(|name| regex.is_match(name))
+        .map(|name| join(&name))
+        .collect()
+}
+
+/// Regex for a glob path component, or `None` when it has no wildcards
+fn glob_regex(component: &str) -> Option<regex::Regex> {
+    if !component.contains(['*', '?', '[']) {
+        return None;
+    }
+
+    let mut pattern = String::from("^");
+    let mut chars = component.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            '[' => {
+                let mut class = String::new();
+                let mut closed = false;
+                while let Some(&next) = chars.peek() {
+                    chars.next();
+                    if next == ']' && !class.is_empty() {
+                        closed = true;
+                        break;
+                    }
+                    class.push(next);
+                }
+                if !closed {
+                    pattern.push_str(&regex::escape(&format!("[{}", class)));
+                    continue;
+                }
+                pattern.push('[');
+                let class = match class.strip_prefix(['!', '^']) {
+                    Some(rest) => {
+                        pattern.push('^');
+                        rest.to_string()
+                    }
+                    None => class,
+                };
+                for c in class.chars() {
+                    match c {
+                        '-' => pattern.push('-'),
+                        c => pattern.push_str(&regex::escape(&c.to_string())),
+                    }
+                }
+                pattern.push(']');
+            }
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    regex::Regex::new(&pattern).ok()
+}
+
+/// Indent output and cap it at `MAX_PREVIEW_LINES` lines
+pub fn render_output(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    if lines.is_empty() {
+        return "  (nothing matched)".to_string();
+    }
+
+    let mut rendered: Vec<String> = lines
+        .iter()
+        .take(MAX_PREVIEW_LINES)
+        .map(|line| format!("  {}", line))
+        .collect();
+
+    if lines.len() > MAX_PREVIEW_LINES {
+        rendered.push(format!("  ... ({} more lines)", lines.len() - MAX_PREVIEW_LINES));
+    }
+
+    rendered.join("\n")
+}
+
+/// Drop `sudo`/`doas` and their options; previews never need elevated privileges
+fn strip_privilege_wrapper(command: &ParsedCommand) -> Option<ParsedCommand> {
+    let start = command.args.iter().position(|a| !a.starts_with('-'))?;
+
+    Some(ParsedCommand {
+        program: command.args[start].clone(),
+        args: command.args[start + 1..].to_vec(),
+        raw_args: command.raw_args[start + 1..].to_vec(),
+    })
+}
+
+/// Arguments of a parsed command as words
+fn words(command: &ParsedCommand) -> Vec<Word> {
+    command
+        .args
+        .iter()
+        .zip(&command.raw_args)
+        .map(|(value, raw)| Word {
+            value: value.clone(),
+            raw: raw.clone(),
+        })
+        .collect()
+}
+
+/// Raw text of `words`, joined for display
+fn display(words: &[Word]) -> String {
+    words.iter().map(|w| w.raw.as_str()).collect::<Vec<_>>().join(" ")
+}
+
+/// `program` followed by `words`, for `Run::List`
+fn argv(program: &str, words: &[Word]) -> Vec<Word> {
+    let mut argv = vec![Word::literal(program)];
+    argv.extend(words.iter().cloned());
+    argv
+}
+
+/// Split arguments into options and operands, honoring `--`
+fn operands(command: &ParsedCommand) -> (Vec<&str>, Vec<Word>) {
+    let mut options = Vec::new();
+    let mut operands = Vec::new();
+    let mut options_done = false;
+
+    for (value, word) in command.args.iter().zip(words(command)) {
+        if !options_done && value == "--" {
+            options_done = true;
+        } else if !options_done && value.starts_with('-') && value.len() > 1 {
+            options.push(value.as_str());
+        } else {
+            operands.push(word);
+        }
+    }
+
+    (options, operands)
+}
+
+/// `find ... -delete` becomes `find ... -print`, and `-exec rm {} \;` is replaced by `-print`
+///
+/// Once a find is previewed, every action that runs a program or writes a file
+/// (`-exec`, `-execdir`, `-ok`, `-okdir`, `-fprint` and friends) is replaced by
+/// `-print` as well, so nothing but the listing happens.
+fn preview_find(command: &ParsedCommand) -> Option<Preview> {
+    let args = words(command);
+    let is_exec = |arg: &str| matches!(arg, "-exec" | "-execdir" | "-ok" | "-okdir");
+
+    let deletes = args.iter().enumerate().any(|(i, word)| {
+        word.value == "-delete"
+            || (is_exec(&word.value)
+                && args
+                    .get(i + 1)
+                    .map(|p| matches!(p.value.as_str(), "rm" | "unlink" | "rmdir" | "shred"))
+                    .unwrap_or(false))
+    });
+    if !deletes {
+        return None;
+    }
+
+    let mut kept = Vec::new();
+    let mut i = 0;
+
+    while i < args.len() {
+        let arg = args[i].value.as_str();
+
+        if arg == "-delete" {
+            kept.push(Word::literal("-print"));
+        } else if is_exec(arg) {
+            // Skip the whole -exec clause up to its terminator
+            while i < args.len() && !matches!(args[i].value.as_str(), ";" | "+") {
+                i += 1;
+            }
+            kept.push(Word::literal("-print"));
+        } else if matches!(arg, "-fprint" | "-fprint0" | "-fls" | "-fprintf") {
+            // Skip the output file (and the format for -fprintf)
+            i += if arg == "-fprintf" { 2 } else { 1 };
+            kept.push(Word::literal("-print"));
+        } else {
+            kept.push(args[i].clone());
+        }
+        i += 1;
+    }
+
+    Some(Preview {
+        command: format!("find {}", display(&kept)),
+        summary: "files that would be deleted".to_string(),
+        run: Run::List(argv("find", &kept)),
+    })
+}
+
+/// `rm` lists what its arguments expand to (recursively for `-r`)
+fn preview_rm(command: &ParsedCommand) -> Option<Preview> {
+    let (options, targets) = operands(command);
+    if targets.is_empty() {
+        return None;
+    }
+
+    let recursive = options.iter().any(|o| {
+        *o == "--recursive" || (!o.starts_with("--") && (o.contains('r') || o.contains('R')))
+    });
+
+    let (preview_command, run) = if recursive {
+        // find has no `--`: `./` keeps a target like `-name` from being read as an option
+        let targets: Vec<Word> = targets
+            .into_iter()
+            .map(|target| {
+                if target.value.starts_with(['/', '~', '$']) {
+                    target
+                } else {
+                    Word { value: format!("./{}", target.value), raw: format!("./{}", target.raw) }
+                }
+            })
+            .collect();
+        let mut run = argv("find", &targets);
+        run.push(Word::literal("-print"));
+        (format!("find {} -print", display(&targets)), run)
+    } else {
+        let mut run = vec![Word::literal("ls"), Word::literal("-1d"), Word::literal("--")];
+        run.extend(targets.iter().cloned());
+        (format!("ls -1d -- {}", display(&targets)), run)
+    };
+
+    Some(Preview {
+        command: preview_command,
+        summary: "files that would be deleted".to_string(),
+        run: Run::List(run),
+    })
+}
+
+/// `mv` lists the sources that would be moved
+fn preview_mv(command: &ParsedCommand) -> Option<Preview> {
+    let (_, targets) = operands(command);
+    let (destination, sources) = targets.split_last()?;
+    if sources.is_empty() {
+        return None;
+    }
+
+    let mut run = vec![Word::literal("ls"), Word::literal("-1d"), Word::literal("--")];
+    run.extend(sources.iter().cloned());
+
+    Some(Preview {
+        command: format!("ls -1d -- {}", display(sources)),
+        summary: format!("files that would be moved to {}", destination.raw),
+        run: Run::List(run),
+    })
+}
+
+/// `sed -i` shows a unified diff of the edits it would make to each file
+fn preview_sed(command: &ParsedCommand) -> Option<Preview> {
+    let args = words(command);
+    let mut kept = Vec::new();
+    let mut operands = Vec::new();
+    let mut scripts = Vec::new();
+    let mut in_place = false;
+    let mut has_script_option = false;
+    let mut i = 0;
+
+    while i < args.len() {
+        let arg = args[i].value.as_str();
+
+        if arg == "--file" || arg.starts_with("--file=") {
+            // A script file can't be checked for commands that write or execute
+            return None;
+        } else if arg.starts_with("--in-place") {
+            in_place = true;
+        } else if arg == "--expression" {
+            has_script_option = true;
+            kept.push(args[i].clone());
+            if let Some(next) = args.get(i + 1) {
+                scripts.push(next.value.clone());
+                kept.push(next.clone());
+                i += 1;
+            }
+        } else if let Some(script) = arg.strip_prefix("--expression=") {
+            has_script_option = true;
+            scripts.push(script.to_string());
+            kept.push(args[i].clone());
+        } else if arg.starts_with("--") {
+            kept.push(args[i].clone());
+        } else if arg.starts_with('-') && arg.len() > 1 {
+            // A cluster of short options: `-i`, `-Ei`, `-ni.bak`, `-ne 's/a/b/'`
+            let mut flags = String::new();
+            let mut script = None;
+            for (pos, flag) in arg.char_indices().skip(1) {
+                match flag {
+                    // The rest of the cluster is the backup suffix
+                    'i' => {
+                        in_place = true;
+                        // BSD sed takes the suffix as a separate (often empty) argument
+                        let bsd_suffix = args.get(i + 1).map(|w| w.raw == "''" || w.raw == "\"\"").unwrap_or(false);
+                        if pos + 1 == arg.len() && bsd_suffix {
+                            i += 1;
+                        }
+                        break;
+                    }
+                    'e' => {
+                        script = Some(&arg[pos + 1..]);
+                        break;
+                    }
+                    'f' => return None,
+                    flag => flags.push(flag),
+                }
+            }
+
+            if !flags.is_empty() {
+                kept.push(Word::literal(&format!("-{}", flags)));
+            }
+            match script {
+                Some("") => {
+                    has_script_option = true;
+                    if let Some(next) = args.get(i + 1) {
+                        scripts.push(next.value.clone());
+                        kept.extend([Word::literal("-e"), next.clone()]);
+                        i += 1;
+                    }
+                }
+                Some(attached) => {
+                    has_script_option = true;
+                    scripts.push(attached.to_string());
+                    kept.extend([
+                        Word::literal("-e"),
+                        Word { value: attached.to_string(), raw: format!("'{}'", attached) },
+                    ]);
+                }
+                None => {}
+            }
+        } else {
+            operands.push(args[i].clone());
+        }
+        i += 1;
+    }
+
+    if !in_place {
+        return None;
+    }
+
+    // Without -e the first operand is the script itself
+    if !has_script_option && !operands.is_empty() {
+        let script = operands.remove(0);
+        scripts.push(script.value.clone());
+        kept.push(script);
+    }
+
+    if operands.is_empty() || !scripts.iter().all(|s| sed_script_is_read_only(s)) {
+        return None;
+    }
+
+    Some(Preview {
+        command: format!(
+            "for f in {}; do sed {} \"$f\" | diff -u \"$f\" -; done",
+            display(&operands),
+            display(&kept)
+        ),
+        summary: "changes sed -i would make".to_string(),
+        run: Run::SedDiff {
+            script: kept,
+            files: operands,
+        },
+    })
+}
+
+/// Whether a sed script only prints, i.e. has no `e`, `r`, `R`, `w` or `W` commands
+/// and no `s///e` or `s///w` flags
+///
+/// Anything this scanner doesn't recognize counts as not read-only.
+fn sed_script_is_read_only(script: &str) -> bool {
+    let mut chars = script.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // Separators, addresses and address modifiers
+            ' ' | '\t' | '\n' | ';' | '{' | '}' | '!' | ',' | '$' | '~' | '+' | '0'..='9' => {}
+            '/' => {
+                if !skip_delimited(&mut chars, '/') {
+                    return false;
+                }
+            }
+            '\\' => match chars.next() {
+                Some(delimiter) if skip_delimited(&mut chars, delimiter) => {}
+                _ => return false,
+            },
+            // Address flags (`/re/I`, `/re/M`)
+            'I' | 'M' => {}
+            's' | 'y' => {
+                let Some(delimiter) = chars.next() else {
+                    return false;
+                };
+                if !skip_delimited(&mut chars, delimiter) || !skip_delimited(&mut chars, delimiter) {
+                    return false;
+                }
+                if c == 's' {
+                    while let Some(&flag) = chars.peek() {
+                        match flag {
+                            'e' | 'w' => return false,
+                            'g' | 'p' | 'i' | 'I' | 'm' | 'M' | '0'..='9' => {
+                                chars.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                }
+            }
+            // Text and label arguments run to the end of the line
+            'a' | 'i' | 'c' | ':' | 'b' | 't' | 'T' => {
+                for next in chars.by_ref() {
+                    if next == '\n' || (next == ';' && matches!(c, 'b' | 't' | 'T')) {
+                        break;
+                    }
+                }
+            }
+            'p' | 'P' | 'd' | 'D' | 'n' | 'N' | 'g' | 'G' | 'h' | 'H' | 'x' | 'l' | 'L' | 'q' | 'Q' | 'z' | 'F'
+            | '=' => {}
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Skip past the next unescaped `delimiter`; false if there is none
+fn skip_delimited(chars: &mut Peekable<Chars>, delimiter: char) -> bool {
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == delimiter {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command_validator::CommandValidator;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn preview_for(command: &str) -> Option<Preview> {
+        build_previews(&CommandValidator::parse(command)).into_iter().next()
+    }
+
+    #[test]
+    fn test_find_delete_becomes_print() {
+        let preview = preview_for("find . -name '*.tmp' -delete").unwrap();
+        assert_eq!(preview.command, "find . -name '*.tmp' -print");
+
+        let preview = preview_for("find /var/log -mtime +7 -exec rm -f {} \\;").unwrap();
+        assert_eq!(preview.command, "find /var/log -mtime +7 -print");
+    }
+
+    #[test]
+    fn test_find_without_delete_has_no_preview() {
+        assert!(preview_for("find . -name '*.rs' -exec wc -l {} +").is_none());
+    }
+
+    #[test]
+    fn test_find_preview_drops_every_action() {
+        let preview = preview_for("find . -name '*.tmp' -exec touch {} \\; -execdir sh -c 'x' \\; -delete").unwrap();
+        assert_eq!(preview.command, "find . -name '*.tmp' -print -print -print");
+
+        let preview = preview_for("find . -fprint out.txt -ok rm {} + -fprintf log.txt '%p'").unwrap();
+        assert_eq!(preview.command, "find . -print -print -print");
+    }
+
+    #[test]
+    fn test_rm_lists_targets() {
+        let preview = preview_for("rm -f *.log 'my file'").unwrap();
+        assert_eq!(preview.command, "ls -1d -- *.log 'my file'");
+
+        let preview = preview_for("sudo rm -rf build/").unwrap();
+        assert_eq!(preview.command, "find ./build/ -print");
+
+        let preview = preview_for("rm -r -- -delete /srv/old").unwrap();
+        assert_eq!(preview.command, "find ./-delete /srv/old -print");
+    }
+
+    #[test]
+    fn test_mv_lists_sources() {
+        let preview = preview_for("mv a.txt b.txt dest/").unwrap();
+        assert_eq!(preview.command, "ls -1d -- a.txt b.txt");
+        assert!(preview.summary.contains("dest/"));
+    }
+
+    #[test]
+    fn test_sed_in_place_becomes_diff() {
+        let preview = preview_for("sed -i 's/foo/bar/g' config.txt").unwrap();
+        assert_eq!(
+            preview.command,
+            "for f in config.txt; do sed 's/foo/bar/g' \"$f\" | diff -u \"$f\" -; done"
+        );
+
+        let preview = preview_for("sed -i '' -e 's/a/b/' x.txt").unwrap();
+        assert!(preview.command.starts_with("for f in x.txt; do sed -e 's/a/b/'"));
+
+        assert!(preview_for("sed 's/foo/bar/' config.txt").is_none());
+
+        // In-place editing bundled with other short options
+        let preview = preview_for("sed -Ei 's/a+/b/' x.txt").unwrap();
+        assert!(preview.command.starts_with("for f in x.txt; do sed -E 's/a+/b/'"));
+        let preview = preview_for("sed -ni.bak '1p' x.txt").unwrap();
+        assert!(preview.command.starts_with("for f in x.txt; do sed -n '1p'"));
+        let preview = preview_for("sed -Eie 's/a/b/' x.txt").unwrap();
+        assert!(preview.command.starts_with("for f in x.txt; do sed -E 's/a/b/'"));
+        assert!(preview_for("sed -nf script.sed -i x.txt").is_none());
+    }
+
+    #[test]
+    fn test_sed_scripts_that_write_or_execute_have_no_preview() {
+        for script in ["1e id", "w out.txt", "s/a/b/w out.txt", "s/a/b/ge", "/x/W log", "$r other.txt", "1R other"] {
+            assert!(preview_for(&format!("sed -i '{}' f.txt", script)).is_none(), "{}", script);
+        }
+        assert!(preview_for("sed -i -e 's/a/b/' -e 'w x' f.txt").is_none());
+        assert!(preview_for("sed -i -f script.sed f.txt").is_none());
+
+        for script in ["s/a/b/g", "s|/usr|/opt|2", "/^#/d", "1,3{s/x/y/;p}", "/start/,/end/ s/e/w/", "$a end"] {
+            assert!(preview_for(&format!("sed -i '{}' f.txt", script)).is_some(), "{}", script);
+        }
+    }
+
+    #[test]
+    fn test_safe_commands_have_no_preview() {
+        assert!(build_previews(&CommandValidator::parse("ls -la && du -sh .")).is_empty());
+    }
+
+    #[test]
+    fn test_run_preview_shows_glob_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.log"), "").unwrap();
+        fs::write(temp_dir.path().join("b.log"), "").unwrap();
+
+        let command = format!("rm {}/*.log", temp_dir.path().display());
+        let output = run_preview(&preview_for(&command).unwrap()).unwrap();
+
+        assert!(output.contains("a.log"));
+        assert!(output.contains("b.log"));
+        assert!(temp_dir.path().join("a.log").exists());
+    }
+
+    #[test]
+    fn test_command_substitution_has_no_preview() {
+        assert!(preview_for("rm -rf $(cat dirs.txt)").is_none());
+        assert!(preview_for("rm `ls`").is_none());
+        assert!(preview_for("find . -name \"$(touch x)\" -delete").is_none());
+    }
+
+    #[test]
+    fn test_run_preview_does_not_use_a_shell() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("a.txt"), "").unwrap();
+        fs::write(temp_dir.path().join(".hidden"), "").unwrap();
+
+        let dir = temp_dir.path().display();
+        let output = run_preview(&preview_for(&format!("rm {}/* '{}/;touch pwned'", dir, dir)).unwrap()).unwrap();
+        assert!(output.contains("a.txt"));
+        assert!(!output.contains(".hidden"));
+        assert!(!std::path::Path::new("pwned").exists());
+
+        // Quoted globs are taken literally, and patterns without matches stay as written
+        let output = run_preview(&preview_for(&format!("rm '{}/*' {}/*.none", dir, dir)).unwrap()).unwrap();
+        assert!(!output.contains("a.txt"));
+        assert!(output.contains("*.none"));
+    }
+
+    #[test]
+    fn test_run_preview_diffs_sed_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("config.txt");
+        fs::write(&file, "foo\n").unwrap();
+
+        let output = run_preview(&preview_for(&format!("sed -i 's/foo/bar/' {}", file.display())).unwrap()).unwrap();
+        assert!(output.contains("-foo"));
+        assert!(output.contains("+bar"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "foo\n");
+    }
+
+    #[test]
+    fn test_temp_files_are_new_and_unique() {
+        let (path, _file) = create_temp_file().unwrap();
+        let (other, _other_file) = create_temp_file().unwrap();
+        assert_ne!(path, other);
+        assert!(OpenOptions::new().write(true).create_new(true).open(&path).is_err());
+        fs::remove_file(path).unwrap();
+        fs::remove_file(other).unwrap();
+    }
+
+    #[test]
+    fn test_render_output_truncates() {
+        let output: String = (0..60).map(|i| format!("line{}\n", i)).collect();
+        let rendered = render_output(&output);
+        assert!(rendered.contains("line49"));
+        assert!(!rendered.contains("line50"));
+        assert!(rendered.contains("10 more lines"));
+        assert_eq!(render_output(""), "  (nothing matched)");
+    }
+}
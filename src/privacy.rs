@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+/// How much local context may be collected and sent to a backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PrivacyLevel {
+    /// Nothing about the machine is collected
+    None,
+    /// Only the OS and shell
+    Minimal,
+    /// Everything: tools, history and query-specific project context
+    #[default]
+    Full,
+}
+
+impl PrivacyLevel {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::None => "none",
+            Self::Minimal => "minimal",
+            Self::Full => "full",
+        }
+    }
+
+    /// Whether tools, history and project context may be collected
+    pub fn allows_full_context(&self) -> bool {
+        *self == Self::Full
+    }
+}
+
+/// Privacy settings from the `[privacy]` table of the config file
+///
+/// ```toml
+/// [privacy]
+/// groq = "minimal"
+/// openai = "minimal"
+///
+/// [[privacy.overrides]]
+/// path = "~/work/client-x"
+/// level = "none"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ollama: Option<PrivacyLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groq: Option<PrivacyLevel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openai: Option<PrivacyLevel>,
+    /// Directory-scoped levels; the longest matching path wins over the backend level
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<PrivacyOverride>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyOverride {
+    /// Directory prefix; `~` expands to the home directory
+    pub path: String,
+    pub level: PrivacyLevel,
+    /// Backends the override applies to (all backends when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backends: Vec<String>,
+}
+
+impl PrivacyConfig {
+    /// Privacy level for `backend` when running from `cwd`
+    ///
+    /// Both `cwd` and the override paths are canonicalized when they exist, so
+    /// symlinks and `..` can't step around an override.
+    pub fn resolve(&self, backend: &str, cwd: &Path) -> PrivacyLevel {
+        let cwd = canonical(cwd);
+        let directory_level = self
+            .overrides
+            .iter()
+            .filter(|o| o.backends.is_empty() || o.backends.iter().any(|b| b.eq_ignore_ascii_case(backend)))
+            .filter_map(|o| {
+                let prefix = canonical(&expand_home(&o.path));
+                cwd.starts_with(&prefix).then(|| (prefix.components().count(), o.level))
+            })
+            .max_by_key(|(depth, _)| *depth)
+            .map(|(_, level)| level);
+
+        directory_level
+            .or_else(|| self.backend_level(backend))
+            .unwrap_or_default()
+    }
+
+    fn backend_level(&self, backend: &str) -> Option<PrivacyLevel> {
+        match backend.to_lowercase().as_str() {
+            "ollama" => self.ollama,
+            "groq" => self.groq,
+            "openai" => self.openai,
+            _ => None,
+        }
+    }
+}
+
+/// `path` with symlinks and `..` resolved, or as given if it can't be resolved
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Expand a leading `~` to `$HOME`
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), paths::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> PrivacyConfig {
+        PrivacyConfig {
+            groq: Some(PrivacyLevel::Minimal),
+            overrides: vec![
+                PrivacyOverride {
+                    path: "/home/dev/work".to_string(),
+                    level: PrivacyLevel::Minimal,
+                    backends: vec![],
+                },
+                PrivacyOverride {
+                    path: "/home/dev/work/client-x".to_string(),
+                    level: PrivacyLevel::None,
+                    backends: vec!["groq".to_string(), "openai".to_string()],
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backend_levels() {
+        let config = config();
+        assert_eq!(config.resolve("groq", Path::new("/srv")), PrivacyLevel::Minimal);
+        assert_eq!(config.resolve("openai", Path::new("/srv")), PrivacyLevel::Full);
+        assert_eq!(PrivacyConfig::default().resolve("groq", Path::new("/srv")), PrivacyLevel::Full);
+    }
+
+    #[test]
+    fn test_longest_directory_override_wins() {
+        let config = config();
+        let client_dir = Path::new("/home/dev/work/client-x/api");
+
+        assert_eq!(config.resolve("groq", client_dir), PrivacyLevel::None);
+        assert_eq!(config.resolve("openai", client_dir), PrivacyLevel::None);
+        // The client-x override is scoped to cloud backends
+        assert_eq!(config.resolve("ollama", client_dir), PrivacyLevel::Minimal);
+        assert_eq!(config.resolve("openai", Path::new("/home/dev/work/other")), PrivacyLevel::Minimal);
+        // Prefix matching is by path component, not by string
+        assert_eq!(config.resolve("openai", Path::new("/home/dev/workshop")), PrivacyLevel::Full);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_overrides_match_through_symlinks() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let real = dir.path().join("client-x");
+        let link = dir.path().join("shortcut");
+        std::fs::create_dir_all(real.join("api")).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let config = |path: &Path| PrivacyConfig {
+            overrides: vec![PrivacyOverride {
+                path: path.display().to_string(),
+                level: PrivacyLevel::None,
+                backends: vec![],
+            }],
+            ..Default::default()
+        };
+
+        // Entering through the symlink still hits an override on the real path
+        assert_eq!(config(&real).resolve("groq", &link.join("api")), PrivacyLevel::None);
+        // And an override written with the symlink covers the real path
+        assert_eq!(config(&link).resolve("groq", &real.join("api")), PrivacyLevel::None);
+        assert_eq!(config(&real).resolve("groq", &real.join("../client-x/api")), PrivacyLevel::None);
+        assert_eq!(config(&real).resolve("groq", &real.join("api/../..")), PrivacyLevel::Full);
+    }
+
+    #[test]
+    fn test_parse_from_toml() {
+        let config: PrivacyConfig = toml::from_str(
+            r#"
+            groq = "none"
+
+            [[overrides]]
+            path = "/tmp/nda"
+            level = "minimal"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.groq, Some(PrivacyLevel::None));
+        assert_eq!(config.overrides[0].level, PrivacyLevel::Minimal);
+        assert!(toml::from_str::<PrivacyConfig>("groq = \"secret\"").is_err());
+    }
+
+    #[test]
+    fn test_level_ordering() {
+        assert!(PrivacyLevel::None < PrivacyLevel::Minimal);
+        assert!(PrivacyLevel::Minimal < PrivacyLevel::Full);
+        assert!(PrivacyLevel::Full.allows_full_context());
+        assert!(!PrivacyLevel::Minimal.allows_full_context());
+    }
+}
@@ -0,0 +1,220 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running child is polled for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long to wait for the pipes to close once the process group has been killed
+const DRAIN_GRACE: Duration = Duration::from_millis(200);
+
+/// Whether `binary` is a file in one of the `PATH` directories
+pub fn find_in_path(binary: &str) -> bool {
+    which(binary).is_some()
+}
+
+/// The first `PATH` entry containing `binary`
+pub fn which(binary: &str) -> Option<PathBuf> {
+    if !is_bare_name(binary) {
+        return None;
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(binary))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether `name` is a plain file name that can't point outside a `PATH` directory
+pub fn is_bare_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains('/')
+        && !name.contains(std::path::MAIN_SEPARATOR)
+        && !Path::new(name).is_absolute()
+}
+
+/// Run a command to completion, killing it if it outlives `timeout`
+///
+/// Returns `Ok(None)` when the command timed out. Stdout and stderr are
+/// drained on background threads so a chatty child cannot deadlock on a full pipe.
+/// On Unix the child gets its own process group so that grandchildren
+/// (e.g. the program behind `sh -c`) are killed along with it.
+pub fn output_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Option<Output>> {
+    output_with_limits(command, timeout, usize::MAX)
+}
+
+/// `output_with_timeout` that keeps at most `max_bytes` of stdout and of stderr
+///
+/// Output past the cap is read and discarded, so the child never blocks on a full pipe.
+/// A grandchild left running in the background (`sleep 100 &`) keeps the pipes
+/// open after the child exits; the pipes are only waited on until `timeout`,
+/// then the process group is killed.
+pub fn output_with_limits(command: &mut Command, timeout: Duration, max_bytes: usize) -> io::Result<Option<Output>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().map(|r| drain(r, max_bytes));
+    let stderr = child.stderr.take().map(|r| drain(r, max_bytes));
+
+    let deadline = Instant::now() + timeout;
+    let status: Option<ExitStatus> = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            kill_tree(&mut child);
+            let _ = child.wait();
+            break None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let mut collect = |pipe: Option<Receiver<Vec<u8>>>| {
+        let Some(pipe) = pipe else {
+            return Vec::new();
+        };
+        pipe.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .or_else(|_| {
+                kill_tree(&mut child);
+                pipe.recv_timeout(DRAIN_GRACE)
+            })
+            .unwrap_or_default()
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+
+    Ok(status.map(|status| Output { status, stdout, stderr }))
+}
+
+/// Run `command` and return its trimmed stdout if it succeeded in time
+pub fn stdout_with_timeout(command: &mut Command, timeout: Duration) -> Option<String> {
+    let output = output_with_timeout(command, timeout).ok()??;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok().map(|s| s.trim().to_string())
+}
+
+/// Kill the child's whole process group, falling back to the child alone
+fn kill_tree(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        let group = format!("-{}", child.id());
+        let killed = Command::new("kill")
+            .args(["-KILL", "--", &group])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false);
+        if killed {
+            return;
+        }
+    }
+
+    let _ = child.kill();
+}
+
+fn drain<R: Read + Send + 'static>(mut reader: R, max_bytes: usize) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 8192];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let keep = n.min(max_bytes.saturating_sub(buffer.len()));
+                    buffer.extend_from_slice(&chunk[..keep]);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+        let _ = sender.send(buffer);
+    });
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_which_rejects_paths() {
+        assert!(which("sh").is_some());
+        for name in ["/bin/sh", "../sh", "./sh", ".hidden", "bin/sh", ""] {
+            assert!(!is_bare_name(name), "{}", name);
+            assert_eq!(which(name), None);
+        }
+    }
+
+    #[test]
+    fn test_output_with_timeout_completes() {
+        let output = output_with_timeout(Command::new("sh").args(["-c", "echo hello"]), Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+    }
+
+    #[test]
+    fn test_output_with_timeout_kills_slow_command() {
+        let start = Instant::now();
+        let output =
+            output_with_timeout(Command::new("sh").args(["-c", "sleep 5"]), Duration::from_millis(100)).unwrap();
+        assert!(output.is_none());
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_output_with_timeout_does_not_wait_for_background_grandchild() {
+        let start = Instant::now();
+        let output = output_with_timeout(
+            Command::new("sh").args(["-c", "sleep 30 & echo started"]),
+            Duration::from_millis(300),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "started");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_output_with_limits_caps_output() {
+        let output = output_with_limits(
+            Command::new("sh").args(["-c", "yes | head -c 100000"]),
+            Duration::from_secs(5),
+            1000,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout.len(), 1000);
+    }
+
+    #[test]
+    fn test_stdout_with_timeout() {
+        assert_eq!(
+            stdout_with_timeout(Command::new("sh").args(["-c", "echo '  trimmed  '"]), Duration::from_secs(5)),
+            Some("trimmed".to_string())
+        );
+        assert_eq!(stdout_with_timeout(Command::new("sh").args(["-c", "exit 1"]), Duration::from_secs(5)), None);
+        assert_eq!(stdout_with_timeout(&mut Command::new("ez-definitely-missing-binary"), Duration::from_secs(5)), None);
+    }
+}
@@ -0,0 +1,185 @@
+//! Named backend/model setups (`[profiles.<name>]`)
+//!
+//! A profile bundles the settings people switch between together: backend,
+//! model, endpoint URLs, privacy level and check policy. The active one is
+//! picked by `--profile`, then `EZ_PROFILE`, then the longest `paths` prefix
+//! containing the working directory, then the `profile` key. Its settings sit
+//! above the config files but below `EZ_*` variables and CLI flags.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use toml::Value;
+
+use crate::flag_check::CheckMode;
+use crate::privacy::{expand_home, PrivacyLevel};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ollama_url: Option<String>,
+    /// OpenAI-compatible endpoint for the `openai` backend (vLLM, LiteLLM, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub openai_url: Option<String>,
+    /// Privacy level for the profile's backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<PrivacyLevel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_check: Option<CheckMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binary_check: Option<CheckMode>,
+    /// Directory prefixes that select the profile; `~` expands to the home directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+}
+
+impl Profile {
+    /// The top-level config keys this profile sets; `privacy` is handled by
+    /// the caller since it depends on the backend in effect
+    pub fn settings(&self) -> Vec<(&'static str, Value)> {
+        let strings = [
+            ("backend", &self.backend),
+            ("model", &self.model),
+            ("ollama_url", &self.ollama_url),
+            ("openai_url", &self.openai_url),
+        ];
+        let checks = [("flag_check", self.flag_check), ("binary_check", self.binary_check)];
+
+        strings
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, Value::String(value.clone()?))))
+            .chain(checks.into_iter().filter_map(|(key, mode)| {
+                Some((key, Value::try_from(mode?).ok()?))
+            }))
+            .collect()
+    }
+
+    /// The deepest `paths` entry containing `cwd`, with its depth
+    fn best_path(&self, cwd: &Path) -> Option<(usize, &str)> {
+        self.paths
+            .iter()
+            .filter_map(|path| {
+                let prefix = expand_home(path);
+                cwd.starts_with(&prefix).then(|| (prefix.components().count(), path.as_str()))
+            })
+            .max_by_key(|(depth, _)| *depth)
+    }
+}
+
+/// Why a profile is active
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// `--profile` or `EZ_PROFILE`
+    Explicit(String),
+    /// A `paths` prefix of the working directory
+    Path(String),
+    /// The `profile` key
+    Default,
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selection::Explicit(how) => write!(f, "{}", how),
+            Selection::Path(path) => write!(f, "path {}", path),
+            Selection::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// The profile to use: `explicit` (flag or variable, with how it was given),
+/// else the best path match for `cwd`, else `default`
+pub fn select(
+    profiles: &BTreeMap<String, Profile>,
+    explicit: Option<(String, String)>,
+    default: Option<&str>,
+    cwd: &Path,
+) -> Option<(String, Selection)> {
+    if let Some((name, how)) = explicit {
+        return Some((name, Selection::Explicit(how)));
+    }
+
+    let by_path = profiles
+        .iter()
+        .filter_map(|(name, profile)| Some((name, profile.best_path(cwd)?)))
+        .max_by_key(|(_, (depth, _))| *depth);
+    if let Some((name, (_, path))) = by_path {
+        return Some((name.clone(), Selection::Path(path.to_string())));
+    }
+
+    default.map(|name| (name.to_string(), Selection::Default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles() -> BTreeMap<String, Profile> {
+        toml::from_str(
+            r#"
+            [work]
+            backend = "openai"
+            openai_url = "http://vllm.internal:8000/v1"
+            privacy = "minimal"
+            flag_check = "regenerate"
+            paths = ["/srv/work", "/srv/work/legacy"]
+
+            [client]
+            backend = "ollama"
+            paths = ["/srv/work/client-x"]
+
+            [personal]
+            backend = "groq"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_profile_settings() {
+        let work = &profiles()["work"];
+        assert_eq!(
+            work.settings(),
+            vec![
+                ("backend", Value::String("openai".to_string())),
+                ("openai_url", Value::String("http://vllm.internal:8000/v1".to_string())),
+                ("flag_check", Value::String("regenerate".to_string())),
+            ]
+        );
+        assert_eq!(work.privacy, Some(PrivacyLevel::Minimal));
+    }
+
+    #[test]
+    fn test_select_profile() {
+        let profiles = profiles();
+        let select = |explicit: Option<(&str, &str)>, default, cwd: &str| {
+            let explicit = explicit.map(|(name, how)| (name.to_string(), how.to_string()));
+            select(&profiles, explicit, default, Path::new(cwd))
+        };
+
+        assert_eq!(
+            select(Some(("personal", "--profile")), Some("work"), "/srv/work"),
+            Some(("personal".to_string(), Selection::Explicit("--profile".to_string())))
+        );
+        assert_eq!(
+            select(None, Some("personal"), "/srv/work/api"),
+            Some(("work".to_string(), Selection::Path("/srv/work".to_string())))
+        );
+        // The deepest prefix wins
+        assert_eq!(
+            select(None, None, "/srv/work/client-x/app").map(|(name, _)| name),
+            Some("client".to_string())
+        );
+        assert_eq!(
+            select(None, None, "/srv/work/legacy/app"),
+            Some(("work".to_string(), Selection::Path("/srv/work/legacy".to_string())))
+        );
+        assert_eq!(select(None, Some("personal"), "/home"), Some(("personal".to_string(), Selection::Default)));
+        assert_eq!(select(None, None, "/srv/workshop"), None);
+    }
+}
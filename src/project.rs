@@ -0,0 +1,476 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+/// Ecosystems whose project files are recognised when walking up from the cwd
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectKind {
+    Cargo,
+    Node,
+    Python,
+    Go,
+}
+
+impl ProjectKind {
+    pub const ALL: [ProjectKind; 4] = [Self::Cargo, Self::Node, Self::Python, Self::Go];
+
+    /// File that marks a project directory
+    pub fn marker(&self) -> &'static str {
+        match self {
+            Self::Cargo => "Cargo.toml",
+            Self::Node => "package.json",
+            Self::Python => "pyproject.toml",
+            Self::Go => "go.mod",
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Node => "node",
+            Self::Python => "python",
+            Self::Go => "go",
+        }
+    }
+}
+
+/// A directory containing a project marker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub kind: ProjectKind,
+    pub dir: PathBuf,
+    pub name: Option<String>,
+    /// Package manager or build tool (`pnpm`, `uv`, `poetry`, ...)
+    pub tool: String,
+    /// Scripts or tasks declared by the project file (npm scripts, poetry scripts)
+    pub scripts: Vec<String>,
+}
+
+/// A monorepo root and its member globs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    pub kind: ProjectKind,
+    pub root: PathBuf,
+    pub members: Vec<String>,
+}
+
+/// Project structure discovered by walking up from the working directory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectLayout {
+    pub cwd: PathBuf,
+    /// Directory containing `.git`
+    pub vcs_root: Option<PathBuf>,
+    /// Project directories, nearest first
+    pub packages: Vec<Package>,
+    /// Workspace roots, nearest first
+    pub workspaces: Vec<Workspace>,
+}
+
+impl ProjectLayout {
+    /// Walk up from `cwd` to the repository root
+    ///
+    /// Without a `.git` directory the walk stops below `$HOME` and `/`, so a
+    /// stray `~/package.json` is not mistaken for the project.
+    pub fn discover(cwd: &Path) -> Self {
+        let home = paths::home_dir();
+        let mut layout = Self {
+            cwd: cwd.to_path_buf(),
+            vcs_root: cwd.ancestors().find(|dir| dir.join(".git").exists()).map(Path::to_path_buf),
+            ..Default::default()
+        };
+
+        for dir in cwd.ancestors() {
+            let is_vcs_root = layout.vcs_root.as_deref() == Some(dir);
+            if !is_vcs_root && (Some(dir) == home.as_deref() || dir.parent().is_none()) {
+                break;
+            }
+
+            for kind in ProjectKind::ALL {
+                if dir.join(kind.marker()).is_file() {
+                    layout.add_project(kind, dir);
+                }
+            }
+
+            if is_vcs_root {
+                break;
+            }
+        }
+
+        layout
+    }
+
+    fn add_project(&mut self, kind: ProjectKind, dir: &Path) {
+        let manifest = dir.join(kind.marker());
+        let Ok(content) = fs::read_to_string(&manifest) else {
+            return;
+        };
+
+        let (package, workspace_members) = match kind {
+            ProjectKind::Cargo => parse_cargo(&content),
+            ProjectKind::Node => parse_package_json(&content, dir),
+            ProjectKind::Python => parse_pyproject(&content),
+            ProjectKind::Go => parse_go(&content, dir),
+        };
+
+        if let Some(members) = workspace_members {
+            self.workspaces.push(Workspace {
+                kind,
+                root: dir.to_path_buf(),
+                members,
+            });
+        }
+
+        if let Some((name, scripts)) = package {
+            self.packages.push(Package {
+                kind,
+                dir: dir.to_path_buf(),
+                name,
+                tool: detect_tool(kind, dir, self.vcs_root.as_deref()),
+                scripts,
+            });
+        }
+    }
+
+    /// The project root: the outermost workspace, else the repository, else the outermost package
+    pub fn root(&self) -> Option<&Path> {
+        self.workspaces
+            .last()
+            .map(|w| w.root.as_path())
+            .or(self.vcs_root.as_deref())
+            .or_else(|| self.packages.last().map(|p| p.dir.as_path()))
+    }
+
+    /// Nearest project of the given kind
+    pub fn nearest(&self, kind: ProjectKind) -> Option<&Package> {
+        self.packages.iter().find(|p| p.kind == kind)
+    }
+
+    /// Workspace containing the nearest package of the given kind
+    pub fn workspace_for(&self, kind: ProjectKind) -> Option<&Workspace> {
+        self.workspaces.iter().find(|w| w.kind == kind)
+    }
+
+    /// First of `names` found in the cwd or a parent, up to the project root
+    pub fn find_file(&self, names: &[&str]) -> Option<PathBuf> {
+        let root = self.root().unwrap_or(&self.cwd);
+        self.cwd
+            .ancestors()
+            .take_while(|dir| dir.starts_with(root))
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .find(|path| path.is_file())
+    }
+
+    /// `path` relative to the project root, for compact display
+    pub fn display_path(&self, path: &Path) -> String {
+        match self.root().and_then(|root| path.strip_prefix(root).ok()) {
+            Some(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+            Some(rel) => rel.display().to_string(),
+            None => path.display().to_string(),
+        }
+    }
+
+    /// Command that runs the tests of the nearest package
+    pub fn test_command(&self) -> Option<String> {
+        let package = self.packages.first()?;
+        let in_workspace = self.workspace_for(package.kind).is_some();
+
+        let command = match package.kind {
+            ProjectKind::Cargo => match (&package.name, in_workspace) {
+                (Some(name), true) => format!("cargo test -p {}", name),
+                _ => "cargo test".to_string(),
+            },
+            ProjectKind::Node => {
+                if !package.scripts.iter().any(|s| s == "test") {
+                    return None;
+                }
+                match package.tool.as_str() {
+                    "npm" => "npm test".to_string(),
+                    tool => format!("{} run test", tool),
+                }
+            }
+            ProjectKind::Python => match package.tool.as_str() {
+                "hatch" => "hatch test".to_string(),
+                "uv" | "poetry" | "pdm" | "pipenv" => format!("{} run pytest", package.tool),
+                _ => "pytest".to_string(),
+            },
+            ProjectKind::Go => {
+                if package.dir == self.cwd {
+                    "go test ./...".to_string()
+                } else {
+                    "go test .".to_string()
+                }
+            }
+        };
+
+        Some(command)
+    }
+}
+
+type ParsedPackage = (Option<(Option<String>, Vec<String>)>, Option<Vec<String>>);
+
+fn string_list(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().filter_map(|i| i.as_str().map(String::from)).collect())
+        .unwrap_or_default()
+}
+
+fn parse_cargo(content: &str) -> ParsedPackage {
+    let Ok(toml) = content.parse::<toml::Value>() else {
+        return (None, None);
+    };
+
+    let package = toml.get("package").map(|p| {
+        let name = p.get("name").and_then(|n| n.as_str()).map(String::from);
+        (name, Vec::new())
+    });
+    let members = toml.get("workspace").map(|w| string_list(w.get("members")));
+
+    (package, members)
+}
+
+fn parse_package_json(content: &str, dir: &Path) -> ParsedPackage {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(content) else {
+        return (None, None);
+    };
+
+    let name = json.get("name").and_then(|v| v.as_str()).map(String::from);
+    let scripts = json
+        .get("scripts")
+        .and_then(|v| v.as_object())
+        .map(|s| s.keys().cloned().collect())
+        .unwrap_or_default();
+
+    // "workspaces": ["packages/*"] or {"packages": [...]}, or pnpm-workspace.yaml
+    let workspaces = json.get("workspaces").map(|w| w.get("packages").unwrap_or(w));
+    let members = match workspaces.and_then(|w| w.as_array()) {
+        Some(items) => Some(items.iter().filter_map(|i| i.as_str().map(String::from)).collect()),
+        None => fs::read_to_string(dir.join("pnpm-workspace.yaml")).ok().map(|yaml| parse_pnpm_workspace(&yaml)),
+    };
+
+    (Some((name, scripts)), members)
+}
+
+/// The `packages:` list of a pnpm-workspace.yaml
+fn parse_pnpm_workspace(yaml: &str) -> Vec<String> {
+    yaml.lines()
+        .skip_while(|line| !line.starts_with("packages:"))
+        .skip(1)
+        .take_while(|line| line.starts_with(' ') || line.starts_with('-') || line.trim().is_empty())
+        .filter_map(|line| line.trim().strip_prefix('-'))
+        .map(|item| item.trim().trim_matches(['\'', '"']).to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_pyproject(content: &str) -> ParsedPackage {
+    let Ok(toml) = content.parse::<toml::Value>() else {
+        return (None, None);
+    };
+
+    let tool = toml.get("tool");
+    let name = toml
+        .get("project")
+        .and_then(|p| p.get("name"))
+        .or_else(|| tool.and_then(|t| t.get("poetry")).and_then(|p| p.get("name")))
+        .and_then(|n| n.as_str())
+        .map(String::from);
+
+    let scripts = toml
+        .get("project")
+        .and_then(|p| p.get("scripts"))
+        .or_else(|| tool.and_then(|t| t.get("poetry")).and_then(|p| p.get("scripts")))
+        .and_then(|s| s.as_table())
+        .map(|s| s.keys().cloned().collect())
+        .unwrap_or_default();
+
+    let members = tool
+        .and_then(|t| t.get("uv"))
+        .and_then(|uv| uv.get("workspace"))
+        .map(|w| string_list(w.get("members")));
+
+    (Some((name, scripts)), members)
+}
+
+fn parse_go(content: &str, dir: &Path) -> ParsedPackage {
+    let module = content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("module "))
+        .map(|m| m.trim().to_string());
+
+    let members = fs::read_to_string(dir.join("go.work")).ok().map(|work| parse_go_work(&work));
+
+    (Some((module, Vec::new())), members)
+}
+
+/// Module directories from `use` directives in a go.work file
+fn parse_go_work(content: &str) -> Vec<String> {
+    let mut members = Vec::new();
+    let mut in_block = false;
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if in_block {
+            if line == ")" {
+                in_block = false;
+            } else if !line.is_empty() {
+                members.push(line.to_string());
+            }
+        } else if line == "use (" {
+            in_block = true;
+        } else if let Some(dir) = line.strip_prefix("use ") {
+            members.push(dir.trim().to_string());
+        }
+    }
+
+    members
+}
+
+/// Package manager or build tool, judged by lock files between the project and the repository root
+fn detect_tool(kind: ProjectKind, dir: &Path, vcs_root: Option<&Path>) -> String {
+    let has = |file: &str| match vcs_root {
+        Some(root) if dir.starts_with(root) => dir
+            .ancestors()
+            .take_while(|d| d.starts_with(root))
+            .any(|d| d.join(file).exists()),
+        _ => dir.join(file).exists(),
+    };
+
+    let tool = match kind {
+        ProjectKind::Cargo => "cargo",
+        ProjectKind::Go => "go",
+        ProjectKind::Node => {
+            if has("pnpm-lock.yaml") || has("pnpm-workspace.yaml") {
+                "pnpm"
+            } else if has("yarn.lock") {
+                "yarn"
+            } else if has("bun.lockb") || has("bun.lock") {
+                "bun"
+            } else {
+                "npm"
+            }
+        }
+        ProjectKind::Python => {
+            let pyproject = fs::read_to_string(dir.join("pyproject.toml")).unwrap_or_default();
+            if has("uv.lock") || pyproject.contains("[tool.uv") {
+                "uv"
+            } else if has("poetry.lock") || pyproject.contains("[tool.poetry") {
+                "poetry"
+            } else if has("pdm.lock") || pyproject.contains("[tool.pdm") {
+                "pdm"
+            } else if pyproject.contains("[tool.hatch") {
+                "hatch"
+            } else if has("Pipfile") {
+                "pipenv"
+            } else {
+                "pip"
+            }
+        }
+    };
+
+    tool.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_cargo_workspace_member() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        write(root, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(root, "crates/core/Cargo.toml", "[package]\nname = \"acme-core\"\n");
+        fs::create_dir_all(root.join("crates/core/src")).unwrap();
+
+        let layout = ProjectLayout::discover(&root.join("crates/core/src"));
+
+        assert_eq!(layout.vcs_root.as_deref(), Some(root));
+        assert_eq!(layout.root(), Some(root));
+        assert_eq!(layout.workspaces[0].members, vec!["crates/*"]);
+        let package = layout.nearest(ProjectKind::Cargo).unwrap();
+        assert_eq!(package.name.as_deref(), Some("acme-core"));
+        assert_eq!(layout.display_path(&package.dir), "crates/core");
+        assert_eq!(layout.test_command().as_deref(), Some("cargo test -p acme-core"));
+    }
+
+    #[test]
+    fn test_node_workspaces_and_package_manager() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        write(root, "package.json", r#"{"name": "mono", "private": true, "workspaces": ["packages/*"]}"#);
+        write(root, "pnpm-lock.yaml", "");
+        write(root, "packages/web/package.json", r#"{"name": "web", "scripts": {"test": "vitest", "dev": "vite"}}"#);
+
+        let layout = ProjectLayout::discover(&root.join("packages/web"));
+
+        assert_eq!(layout.packages.len(), 2);
+        let web = layout.nearest(ProjectKind::Node).unwrap();
+        assert_eq!(web.name.as_deref(), Some("web"));
+        assert_eq!(web.tool, "pnpm");
+        assert_eq!(web.scripts, vec!["dev", "test"]);
+        assert_eq!(layout.workspace_for(ProjectKind::Node).unwrap().members, vec!["packages/*"]);
+        assert_eq!(layout.test_command().as_deref(), Some("pnpm run test"));
+    }
+
+    #[test]
+    fn test_python_and_go_projects() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        write(root, "pyproject.toml", "[project]\nname = \"acme\"\n\n[tool.uv]\ndev-dependencies = []\n");
+
+        let layout = ProjectLayout::discover(root);
+        let package = layout.nearest(ProjectKind::Python).unwrap();
+        assert_eq!(package.tool, "uv");
+        assert_eq!(layout.test_command().as_deref(), Some("uv run pytest"));
+
+        write(root, "svc/go.mod", "module example.com/acme/svc\n\ngo 1.22\n");
+        write(root, "go.work", "go 1.22\n\nuse (\n\t./svc\n\t./tools // comment\n)\n");
+        write(root, "go.mod", "module example.com/acme\n");
+        fs::create_dir_all(root.join("svc/internal/api")).unwrap();
+
+        let layout = ProjectLayout::discover(&root.join("svc/internal/api"));
+        assert_eq!(layout.nearest(ProjectKind::Go).unwrap().name.as_deref(), Some("example.com/acme/svc"));
+        assert_eq!(layout.workspace_for(ProjectKind::Go).unwrap().members, vec!["./svc", "./tools"]);
+        assert_eq!(layout.test_command().as_deref(), Some("go test ."));
+    }
+
+    #[test]
+    fn test_find_file_stops_at_root() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("repo");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("svc/api")).unwrap();
+        write(&root, "Makefile", "all:\n");
+        write(dir.path(), "justfile", "outside:\n");
+
+        let layout = ProjectLayout::discover(&root.join("svc/api"));
+        assert_eq!(layout.find_file(&["GNUmakefile", "Makefile"]), Some(root.join("Makefile")));
+        assert_eq!(layout.find_file(&["justfile"]), None);
+    }
+
+    #[test]
+    fn test_pnpm_workspace_yaml() {
+        let members = parse_pnpm_workspace("packages:\n  - 'apps/*'\n  - \"libs/*\"\n\ncatalog:\n  react: ^18\n");
+        assert_eq!(members, vec!["apps/*", "libs/*"]);
+    }
+
+    #[test]
+    fn test_no_project() {
+        let dir = TempDir::new().unwrap();
+        let layout = ProjectLayout::discover(dir.path());
+        assert!(layout.packages.is_empty());
+        assert_eq!(layout.test_command(), None);
+    }
+}
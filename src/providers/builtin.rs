@@ -0,0 +1,592 @@
+//! Built-in context providers
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use super::{ContextProvider, ProviderContext, DEFAULT_PROVIDER_TIMEOUT};
+use crate::process_util::stdout_with_timeout;
+use crate::project::{Package, ProjectKind};
+
+/// Run a command in the working directory and return its trimmed stdout on success
+///
+/// Commands are killed after the provider timeout so a hung daemon (`docker ps`,
+/// `kubectl` against an unreachable cluster) cannot leave processes behind.
+pub(super) fn command_stdout(ctx: &ProviderContext, program: &str, args: &[&str]) -> Option<String> {
+    stdout_with_timeout(
+        Command::new(program).args(args).current_dir(&ctx.cwd),
+        DEFAULT_PROVIDER_TIMEOUT,
+    )
+}
+
+pub(super) fn non_empty(context: String) -> Option<String> {
+    if context.is_empty() {
+        None
+    } else {
+        Some(context)
+    }
+}
+
+/// "Found: X in current directory", or where above it the file was found
+pub(super) fn found_line(ctx: &ProviderContext, package: &Package) -> String {
+    if package.dir == ctx.cwd {
+        format!("\nFound: {} in current directory", package.kind.marker())
+    } else {
+        format!("\nFound: {} in {}", package.kind.marker(), package.dir.display())
+    }
+}
+
+pub struct ProjectProvider;
+
+impl ContextProvider for ProjectProvider {
+    fn name(&self) -> &str {
+        "project"
+    }
+
+    fn title(&self) -> &str {
+        "Project Layout"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec![
+            "project", "test", "build", "lint", "format", "package", "workspace", "monorepo", "module", "script",
+            "target", "task",
+        ]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        !ctx.project.packages.is_empty()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let project = &ctx.project;
+        let mut context = String::new();
+
+        if let Some(root) = project.root() {
+            context.push_str(&format!("Project root: {}", root.display()));
+            if ctx.cwd != root {
+                context.push_str(&format!(" (current directory: {})", project.display_path(&ctx.cwd)));
+            }
+        }
+
+        for workspace in &project.workspaces {
+            context.push_str(&format!(
+                "\nMonorepo: {} workspace at {} with members {}",
+                workspace.kind.as_str(),
+                project.display_path(&workspace.root),
+                workspace.members.join(", ")
+            ));
+        }
+
+        for package in &project.packages {
+            context.push_str(&format!(
+                "\n{} project{} at {} (tool: {})",
+                package.kind.as_str(),
+                package.name.as_deref().map(|n| format!(" {}", n)).unwrap_or_default(),
+                project.display_path(&package.dir),
+                package.tool
+            ));
+            if !package.scripts.is_empty() {
+                context.push_str(&format!("\n  scripts: {}", package.scripts.join(" ")));
+            }
+        }
+
+        if let Some(test) = project.test_command() {
+            context.push_str(&format!("\nTests for the current package: {}", test));
+        }
+
+        non_empty(context)
+    }
+}
+
+pub struct GitProvider;
+
+impl ContextProvider for GitProvider {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn title(&self) -> &str {
+        "Git Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["git", "repository", "repo", "commit", "branch"]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        command_stdout(ctx, "git", &["rev-parse", "--is-inside-work-tree"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(branch) = command_stdout(ctx, "git", &["branch", "--show-current"]) {
+            context.push_str(&format!("Current branch: {}", branch));
+        }
+
+        if let Some(name) = command_stdout(ctx, "git", &["config", "--get", "user.name"]) {
+            context.push_str(&format!("\nGit user: {}", name));
+        }
+
+        if let Some(remotes) = command_stdout(ctx, "git", &["remote", "-v"]) {
+            let remote_lines: Vec<&str> = remotes.lines().take(2).collect();
+            if !remote_lines.is_empty() {
+                context.push_str("\nRemotes:\n");
+                for line in remote_lines {
+                    context.push_str(&format!("  {}\n", line));
+                }
+            }
+        }
+
+        if let Some(status) = command_stdout(ctx, "git", &["status", "--short"]) {
+            let file_count = status.lines().count();
+            if file_count > 0 {
+                context.push_str(&format!("\nUncommitted changes: {} files", file_count));
+            }
+        }
+
+        non_empty(context)
+    }
+}
+
+pub struct DockerProvider;
+
+impl ContextProvider for DockerProvider {
+    fn name(&self) -> &str {
+        "docker"
+    }
+
+    fn title(&self) -> &str {
+        "Docker Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["docker", "container", "image"]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        command_stdout(ctx, "docker", &["--version"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(version) = command_stdout(ctx, "docker", &["--version"]) {
+            context.push_str(&format!("Docker version: {}", version));
+        }
+
+        if let Some(containers) = command_stdout(ctx, "docker", &["ps", "-q"]) {
+            context.push_str(&format!("\nRunning containers: {}", containers.lines().count()));
+        }
+
+        if ctx.cwd.join("docker-compose.yml").exists() {
+            context.push_str("\nFound: docker-compose.yml in current directory");
+        } else if ctx.cwd.join("compose.yml").exists() {
+            context.push_str("\nFound: compose.yml in current directory");
+        }
+
+        non_empty(context)
+    }
+}
+
+pub struct NpmProvider;
+
+impl ContextProvider for NpmProvider {
+    fn name(&self) -> &str {
+        "npm"
+    }
+
+    fn title(&self) -> &str {
+        "NPM/Node Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["npm", "node", "package.json", "javascript"]
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(version) = command_stdout(ctx, "node", &["--version"]) {
+            context.push_str(&format!("Node version: {}", version));
+        }
+
+        if let Some(version) = command_stdout(ctx, "npm", &["--version"]) {
+            context.push_str(&format!("\nNPM version: {}", version));
+        }
+
+        if let Some(package) = ctx.project.nearest(ProjectKind::Node) {
+            context.push_str(&found_line(ctx, package));
+
+            if let Some(name) = &package.name {
+                context.push_str(&format!("\nPackage name: {}", name));
+            }
+
+            if !package.scripts.is_empty() {
+                context.push_str("\nAvailable scripts:");
+                for script in package.scripts.iter().take(5) {
+                    context.push_str(&format!(" {}", script));
+                }
+                context.push_str(&format!(" (run with {})", package.tool));
+            }
+        }
+
+        non_empty(context)
+    }
+}
+
+pub struct PythonProvider;
+
+impl ContextProvider for PythonProvider {
+    fn name(&self) -> &str {
+        "python"
+    }
+
+    fn title(&self) -> &str {
+        "Python Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["python", "pip", "virtualenv", "venv", "conda"]
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(version) = command_stdout(ctx, "python3", &["--version"])
+            .or_else(|| command_stdout(ctx, "python", &["--version"]))
+        {
+            context.push_str(&format!("Python version: {}", version));
+        }
+
+        if std::env::var("VIRTUAL_ENV").is_ok() {
+            context.push_str("\nVirtual environment: ACTIVE");
+        }
+
+        let package = ctx.project.nearest(ProjectKind::Python);
+        let project_dir = package.map(|p| p.dir.as_path()).unwrap_or(&ctx.cwd);
+
+        if project_dir.join("requirements.txt").exists() {
+            context.push_str("\nFound: requirements.txt");
+        }
+
+        if project_dir.join("Pipfile").exists() {
+            context.push_str("\nFound: Pipfile (using pipenv)");
+        }
+
+        if let Some(package) = package {
+            context.push_str(&found_line(ctx, package));
+            if let Some(name) = &package.name {
+                context.push_str(&format!("\nProject name: {}", name));
+            }
+            context.push_str(&format!("\nManaged with: {}", package.tool));
+        }
+
+        let source_layout = if project_dir.join("src").is_dir() { "src/ layout" } else { "flat layout" };
+        let tests_dir = ["tests", "test"].into_iter().find(|d| project_dir.join(d).is_dir());
+        if package.is_some() || tests_dir.is_some() {
+            context.push_str(&format!("\nLayout: {}", source_layout));
+            if let Some(tests) = tests_dir {
+                context.push_str(&format!(", tests in {}/", tests));
+            }
+        }
+
+        non_empty(context)
+    }
+}
+
+/// Startup files read for a shell, relative to the home directory
+pub fn rc_files(shell: Option<&str>) -> Vec<&'static str> {
+    match shell {
+        Some("zsh") => vec![".zshrc", ".zshenv"],
+        Some("bash") => vec![".bashrc", ".bash_profile"],
+        Some("fish") => vec![".config/fish/config.fish"],
+        _ => vec![".bashrc", ".zshrc"],
+    }
+}
+
+/// `alias name=...` lines of a startup file
+pub fn alias_lines(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().filter(|line| line.trim_start().starts_with("alias "))
+}
+
+/// Lines that start a function definition (`name() {`, `function name`)
+pub fn function_lines(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().filter(|line| {
+        let trimmed = line.trim();
+        trimmed.ends_with("() {") || trimmed.contains("function ")
+    })
+}
+
+/// Names of the aliases and functions defined in the shell's startup files
+pub fn shell_definitions(home_dir: &Path, shell: Option<&str>) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for rc_file in rc_files(shell) {
+        let Ok(content) = fs::read_to_string(home_dir.join(rc_file)) else {
+            continue;
+        };
+
+        for line in alias_lines(&content) {
+            // `alias ll='ls -la'`, zsh's `alias -g G='| grep'`, fish's `alias ll 'ls -la'`
+            let name = line
+                .trim_start()
+                .trim_start_matches("alias")
+                .split_whitespace()
+                .find(|word| !word.starts_with('-'))
+                .and_then(|word| word.split('=').next());
+            names.extend(name.filter(|n| !n.is_empty()).map(str::to_string));
+        }
+
+        for line in function_lines(&content) {
+            let trimmed = line.trim();
+            let name = match trimmed.find("function ") {
+                Some(start) => trimmed[start + "function ".len()..].split_whitespace().next(),
+                None => trimmed.strip_suffix("() {"),
+            };
+            names.extend(
+                name.map(|n| n.trim_end_matches("()").trim())
+                    .filter(|n| !n.is_empty() && !n.contains(char::is_whitespace))
+                    .map(str::to_string),
+            );
+        }
+    }
+    names
+}
+
+pub struct ShellRcProvider;
+
+impl ContextProvider for ShellRcProvider {
+    fn name(&self) -> &str {
+        "shell"
+    }
+
+    fn title(&self) -> &str {
+        "Shell Configuration"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["alias", "function", "shell", "bashrc", "zshrc"]
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let home_dir = ctx.home_dir.as_ref()?;
+        let mut context = String::new();
+
+        for rc_file in rc_files(ctx.shell.as_deref()) {
+            let Ok(content) = fs::read_to_string(home_dir.join(rc_file)) else {
+                continue;
+            };
+
+            let aliases: Vec<&str> = alias_lines(&content).take(10).collect();
+
+            if !aliases.is_empty() {
+                context.push_str(&format!("\nAliases from {}:\n", rc_file));
+                for alias in aliases {
+                    context.push_str(&format!("  {}\n", alias.trim()));
+                }
+            }
+
+            let functions: Vec<&str> = function_lines(&content).take(5).collect();
+
+            if !functions.is_empty() {
+                context.push_str(&format!("\nFunctions from {}:\n", rc_file));
+                for func in functions {
+                    context.push_str(&format!("  {}\n", func.trim()));
+                }
+            }
+
+            let exports: Vec<&str> = content
+                .lines()
+                .filter(|line| line.trim_start().starts_with("export "))
+                .take(10)
+                .collect();
+
+            if !exports.is_empty() {
+                context.push_str(&format!("\nExports from {}:\n", rc_file));
+                for export in exports {
+                    context.push_str(&format!("  {}\n", export.trim()));
+                }
+            }
+        }
+
+        non_empty(context)
+    }
+}
+
+pub struct CargoProvider;
+
+impl ContextProvider for CargoProvider {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn title(&self) -> &str {
+        "Rust/Cargo Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["rust", "cargo", "crate"]
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(version) = command_stdout(ctx, "rustc", &["--version"]) {
+            context.push_str(&format!("Rust version: {}", version));
+        }
+
+        if let Some(version) = command_stdout(ctx, "cargo", &["--version"]) {
+            context.push_str(&format!("\nCargo version: {}", version));
+        }
+
+        if let Some(package) = ctx.project.nearest(ProjectKind::Cargo) {
+            context.push_str(&found_line(ctx, package));
+
+            if let Some(name) = &package.name {
+                context.push_str(&format!("\nPackage name: {}", name));
+            }
+        }
+
+        if let Some(workspace) = ctx.project.workspace_for(ProjectKind::Cargo) {
+            context.push_str(&format!(
+                "\nCargo workspace at {} (members: {})",
+                workspace.root.display(),
+                workspace.members.join(", ")
+            ));
+        }
+
+        non_empty(context)
+    }
+}
+
+pub struct KubernetesProvider;
+
+impl ContextProvider for KubernetesProvider {
+    fn name(&self) -> &str {
+        "kubernetes"
+    }
+
+    fn title(&self) -> &str {
+        "Kubernetes Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["k8s", "kubernetes", "kubectl", "pod", "deployment"]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        command_stdout(ctx, "kubectl", &["version", "--client"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(version) = command_stdout(ctx, "kubectl", &["version", "--client", "--short"]) {
+            context.push_str(&format!("Kubectl version: {}", version));
+        }
+
+        if let Some(current) = command_stdout(ctx, "kubectl", &["config", "current-context"]) {
+            context.push_str(&format!("\nCurrent context: {}", current));
+        }
+
+        if let Some(ns) = command_stdout(
+            ctx,
+            "kubectl",
+            &["config", "view", "--minify", "--output", "jsonpath={..namespace}"],
+        ) {
+            if !ns.is_empty() {
+                context.push_str(&format!("\nCurrent namespace: {}", ns));
+            }
+        }
+
+        non_empty(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn context_in(dir: &TempDir) -> ProviderContext {
+        ProviderContext::new("", dir.path().to_path_buf(), Some(dir.path().to_path_buf()), Some("zsh".to_string()))
+    }
+
+    #[test]
+    fn test_npm_provider_reads_package_json() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{"name": "demo-app", "scripts": {"build": "tsc", "test": "jest"}}"#,
+        )
+        .unwrap();
+
+        let context = NpmProvider.gather(&context_in(&dir)).unwrap();
+        assert!(context.contains("Package name: demo-app"));
+        assert!(context.contains("Available scripts: build test"));
+    }
+
+    #[test]
+    fn test_project_provider_reports_workspace_member() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        fs::create_dir_all(root.join("crates/api/src")).unwrap();
+        fs::write(root.join("crates/api/Cargo.toml"), "[package]\nname = \"api\"\n").unwrap();
+
+        let cwd = root.join("crates/api/src");
+        let ctx = ProviderContext::new("run the tests for this package", cwd, Some(root.to_path_buf()), None);
+
+        assert!(ProjectProvider.relevance(&ctx.query) > 0.0);
+        assert!(ProjectProvider.detect(&ctx));
+        let context = ProjectProvider.gather(&ctx).unwrap();
+        assert!(context.contains("(current directory: crates/api/src)"));
+        assert!(context.contains("Monorepo: cargo workspace at . with members crates/*"));
+        assert!(context.contains("cargo project api at crates/api"));
+        assert!(context.contains("Tests for the current package: cargo test -p api"));
+
+        let cargo = CargoProvider.gather(&ctx).unwrap();
+        assert!(cargo.contains(&format!("Found: Cargo.toml in {}", root.join("crates/api").display())));
+    }
+
+    #[test]
+    fn test_shell_rc_provider_extracts_aliases() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(".zshrc"),
+            "alias ll='ls -la'\nexport EDITOR=vim\nmkcd() {\n  mkdir -p \"$1\"\n}\n",
+        )
+        .unwrap();
+
+        let context = ShellRcProvider.gather(&context_in(&dir)).unwrap();
+        assert!(context.contains("alias ll='ls -la'"));
+        assert!(context.contains("export EDITOR=vim"));
+        assert!(context.contains("mkcd() {"));
+    }
+
+    #[test]
+    fn test_shell_definitions() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(".zshrc"),
+            "alias ll='ls -la'\nalias -g G='| grep'\nmkcd() {\n  mkdir -p \"$1\"\n}\nfunction gco {\n}\n",
+        )
+        .unwrap();
+
+        let names = shell_definitions(dir.path(), Some("zsh"));
+        let mut names: Vec<&str> = names.iter().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, vec!["G", "gco", "ll", "mkcd"]);
+        assert!(shell_definitions(dir.path(), Some("fish")).is_empty());
+    }
+
+    #[test]
+    fn test_relevance_uses_keywords() {
+        assert!(GitProvider.relevance("switch to branch main") > 0.0);
+        assert_eq!(GitProvider.relevance("list large files"), 0.0);
+        assert!(KubernetesProvider.relevance("restart the pod") > 0.0);
+    }
+}
@@ -0,0 +1,455 @@
+//! AWS, Google Cloud and Azure CLI providers
+//!
+//! These read only the CLIs' plain configuration (profiles, active project,
+//! default subscription). Credential stores such as `~/.aws/credentials`,
+//! gcloud's `credentials.db` or Azure's token cache are never opened, and only
+//! an allowlist of keys is taken from the files that are read.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use super::builtin::non_empty;
+use super::{word_relevance, ContextProvider, ProviderContext};
+
+/// Words that make every cloud provider relevant
+const CLOUD_WORDS: [&str; 6] = ["cloud", "region", "bucket", "vm", "instance", "subscription"];
+
+/// At most this many profiles or configurations are listed
+const MAX_PROFILES: usize = 20;
+
+/// A section of an INI-style file and its `key = value` pairs
+#[derive(Debug, Clone, PartialEq)]
+pub struct IniSection {
+    pub name: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl IniSection {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parse the INI dialect the cloud CLIs write, keeping only `allowed` keys
+///
+/// Filtering happens while parsing so values of other keys (secrets included)
+/// never leave this function.
+pub fn parse_ini(content: &str, allowed: &[&str]) -> Vec<IniSection> {
+    let mut sections: Vec<IniSection> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push(IniSection {
+                name: name.trim().to_string(),
+                entries: Vec::new(),
+            });
+        } else if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim();
+            if let (Some(section), true) = (sections.last_mut(), allowed.contains(&key)) {
+                section.entries.push((key.to_string(), value.trim().to_string()));
+            }
+        }
+    }
+
+    sections
+}
+
+/// First non-empty environment variable out of `names`, with its name
+fn env_var(names: &[&'static str]) -> Option<(String, &'static str)> {
+    names
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|v| !v.is_empty()).map(|v| (v, *name)))
+}
+
+fn cloud_relevance(query: &str, words: &[&str]) -> f32 {
+    word_relevance(query, words).max(word_relevance(query, &CLOUD_WORDS))
+}
+
+/// Keys of `~/.aws/config` that say which account, role and region a profile uses
+const AWS_KEYS: [&str; 8] = [
+    "region",
+    "output",
+    "role_arn",
+    "source_profile",
+    "sso_session",
+    "sso_account_id",
+    "sso_role_name",
+    "sso_region",
+];
+
+/// Summarise `~/.aws/config` for the given active profile and environment region
+pub fn describe_aws_config(config: &[IniSection], profile: &str, env_region: Option<&str>) -> String {
+    let profiles: Vec<(&str, &IniSection)> = config
+        .iter()
+        .filter_map(|section| match section.name.as_str() {
+            "default" => Some(("default", section)),
+            name => name.strip_prefix("profile ").map(|n| (n.trim(), section)),
+        })
+        .collect();
+
+    let mut context = String::new();
+    let active = profiles.iter().find(|(name, _)| *name == profile).map(|(_, s)| *s);
+
+    match (env_region, active.and_then(|s| s.get("region"))) {
+        (Some(region), _) => context.push_str(&format!("\nRegion: {} (from environment)", region)),
+        (None, Some(region)) => context.push_str(&format!("\nRegion: {} (from profile)", region)),
+        (None, None) => context.push_str("\nRegion: not set (pass --region)"),
+    }
+
+    if let Some(section) = active {
+        if let Some(role) = section.get("role_arn") {
+            context.push_str(&format!("\nAssumes role: {}", role));
+        }
+        if let Some(account) = section.get("sso_account_id") {
+            let role = section.get("sso_role_name").unwrap_or("?");
+            context.push_str(&format!("\nSSO account: {} (role {}); log in with aws sso login", account, role));
+        } else if section.get("sso_session").is_some() {
+            context.push_str("\nSSO profile; log in with aws sso login");
+        }
+    } else if profile != "default" {
+        context.push_str(&format!("\nProfile {} is not defined in the config file", profile));
+    }
+
+    if !profiles.is_empty() {
+        let names: Vec<String> = profiles
+            .iter()
+            .take(MAX_PROFILES)
+            .map(|(name, section)| match section.get("region") {
+                Some(region) => format!("{} ({})", name, region),
+                None => name.to_string(),
+            })
+            .collect();
+        context.push_str(&format!("\nProfiles (use --profile <name>): {}", names.join(", ")));
+        if profiles.len() > MAX_PROFILES {
+            context.push_str(&format!(", ... and {} more", profiles.len() - MAX_PROFILES));
+        }
+    }
+
+    context
+}
+
+pub struct AwsProvider;
+
+impl AwsProvider {
+    fn config_path(ctx: &ProviderContext) -> Option<PathBuf> {
+        env::var_os("AWS_CONFIG_FILE")
+            .map(PathBuf::from)
+            .or_else(|| Some(ctx.home_dir.as_ref()?.join(".aws/config")))
+    }
+}
+
+impl ContextProvider for AwsProvider {
+    fn name(&self) -> &str {
+        "aws"
+    }
+
+    fn title(&self) -> &str {
+        "AWS Context"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        cloud_relevance(
+            query,
+            &["aws", "s3", "ec2", "ecs", "eks", "ecr", "lambda", "iam", "cloudformation", "route53", "dynamodb", "rds", "sqs", "sns"],
+        )
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        Self::config_path(ctx).is_some_and(|path| path.is_file()) || env_var(&["AWS_PROFILE", "AWS_REGION"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let (profile, source) = env_var(&["AWS_PROFILE", "AWS_DEFAULT_PROFILE"])
+            .map(|(profile, name)| (profile, format!("from {}", name)))
+            .unwrap_or_else(|| ("default".to_string(), "default".to_string()));
+        let env_region = env_var(&["AWS_REGION", "AWS_DEFAULT_REGION"]).map(|(region, _)| region);
+
+        let config = Self::config_path(ctx)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+        let sections = parse_ini(&config, &AWS_KEYS);
+
+        let mut context = format!("Active profile: {} ({})", profile, source);
+        context.push_str(&describe_aws_config(&sections, &profile, env_region.as_deref()));
+        Some(context)
+    }
+}
+
+/// Keys of a gcloud configuration worth showing, by section
+const GCLOUD_KEYS: [&str; 4] = ["account", "project", "region", "zone"];
+
+pub struct GcloudProvider;
+
+impl GcloudProvider {
+    fn config_dir(ctx: &ProviderContext) -> Option<PathBuf> {
+        env::var_os("CLOUDSDK_CONFIG")
+            .map(PathBuf::from)
+            .or_else(|| Some(ctx.home_dir.as_ref()?.join(".config/gcloud")))
+    }
+}
+
+/// Summarise a gcloud configuration file (`configurations/config_<name>`)
+pub fn describe_gcloud_config(sections: &[IniSection]) -> String {
+    let mut context = String::new();
+    let value = |section: &str, key: &str| {
+        sections
+            .iter()
+            .filter(|s| s.name == section)
+            .find_map(|s| s.get(key).map(str::to_string))
+    };
+
+    if let Some(project) = value("core", "project") {
+        context.push_str(&format!("\nProject: {}", project));
+    }
+    if let Some(account) = value("core", "account") {
+        context.push_str(&format!("\nAccount: {}", account));
+    }
+    if let Some(region) = value("compute", "region") {
+        context.push_str(&format!("\nDefault region: {}", region));
+    }
+    if let Some(zone) = value("compute", "zone") {
+        context.push_str(&format!("\nDefault zone: {}", zone));
+    }
+
+    context
+}
+
+impl ContextProvider for GcloudProvider {
+    fn name(&self) -> &str {
+        "gcloud"
+    }
+
+    fn title(&self) -> &str {
+        "Google Cloud Context"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        cloud_relevance(
+            query,
+            &["gcloud", "gcp", "gsutil", "gke", "bigquery", "bq", "gcs", "google", "firebase", "cloudrun"],
+        )
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        Self::config_dir(ctx).is_some_and(|dir| dir.join("configurations").is_dir())
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let dir = Self::config_dir(ctx)?;
+        let active = env_var(&["CLOUDSDK_ACTIVE_CONFIG_NAME"])
+            .map(|(name, _)| name)
+            .or_else(|| fs::read_to_string(dir.join("active_config")).ok())
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(|| "default".to_string());
+
+        let mut context = format!("Active configuration: {}", active);
+
+        let config = fs::read_to_string(dir.join("configurations").join(format!("config_{}", active)))
+            .unwrap_or_default();
+        context.push_str(&describe_gcloud_config(&parse_ini(&config, &GCLOUD_KEYS)));
+
+        if let Some((project, name)) = env_var(&["CLOUDSDK_CORE_PROJECT"]) {
+            context.push_str(&format!("\nProject override: {} (from {})", project, name));
+        }
+
+        let mut others: Vec<String> = fs::read_dir(dir.join("configurations"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().strip_prefix("config_")?.to_string();
+                (name != active).then_some(name)
+            })
+            .collect();
+        others.sort();
+        others.truncate(MAX_PROFILES);
+        if !others.is_empty() {
+            context.push_str(&format!(
+                "\nOther configurations (use --configuration <name>): {}",
+                others.join(", ")
+            ));
+        }
+
+        Some(context)
+    }
+}
+
+/// The default subscription and the others available, from `azureProfile.json`
+///
+/// Only names, ids and the signed-in user's name are read; the file holds no
+/// tokens, but nothing else is taken from it either.
+pub fn describe_azure_profile(profile: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(profile.trim_start_matches('\u{feff}')).ok()?;
+    let subscriptions = json.get("subscriptions")?.as_array()?;
+
+    let name = |sub: &serde_json::Value| sub.get("name").and_then(|n| n.as_str()).unwrap_or("?").to_string();
+    let mut context = String::new();
+
+    if let Some(default) = subscriptions
+        .iter()
+        .find(|sub| sub.get("isDefault").and_then(|d| d.as_bool()).unwrap_or(false))
+    {
+        context.push_str(&format!(
+            "Default subscription: {} ({})",
+            name(default),
+            default.get("id").and_then(|i| i.as_str()).unwrap_or("?")
+        ));
+        if let Some(user) = default.pointer("/user/name").and_then(|u| u.as_str()) {
+            context.push_str(&format!("\nSigned in as: {}", user));
+        }
+    }
+
+    let others: Vec<String> = subscriptions
+        .iter()
+        .filter(|sub| !sub.get("isDefault").and_then(|d| d.as_bool()).unwrap_or(false))
+        .take(MAX_PROFILES)
+        .map(name)
+        .collect();
+    if !others.is_empty() {
+        context.push_str(&format!("\nOther subscriptions (use --subscription <name>): {}", others.join(", ")));
+    }
+
+    non_empty(context.trim_start().to_string())
+}
+
+pub struct AzureProvider;
+
+impl AzureProvider {
+    fn config_dir(ctx: &ProviderContext) -> Option<PathBuf> {
+        env::var_os("AZURE_CONFIG_DIR")
+            .map(PathBuf::from)
+            .or_else(|| Some(ctx.home_dir.as_ref()?.join(".azure")))
+    }
+}
+
+impl ContextProvider for AzureProvider {
+    fn name(&self) -> &str {
+        "azure"
+    }
+
+    fn title(&self) -> &str {
+        "Azure Context"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        cloud_relevance(query, &["az", "azure", "aks", "acr", "entra", "bicep"])
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        Self::config_dir(ctx).is_some_and(|dir| dir.join("azureProfile.json").is_file())
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let dir = Self::config_dir(ctx)?;
+        let mut context = fs::read_to_string(dir.join("azureProfile.json"))
+            .ok()
+            .and_then(|profile| describe_azure_profile(&profile))
+            .unwrap_or_else(|| "Not logged in (run az login)".to_string());
+
+        // `az config set defaults.group=...` writes these
+        let config = fs::read_to_string(dir.join("config")).unwrap_or_default();
+        for section in parse_ini(&config, &["group", "location"]) {
+            if section.name != "defaults" {
+                continue;
+            }
+            if let Some(group) = section.get("group") {
+                context.push_str(&format!("\nDefault resource group: {}", group));
+            }
+            if let Some(location) = section.get("location") {
+                context.push_str(&format!("\nDefault location: {}", location));
+            }
+        }
+
+        Some(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_ini_drops_unlisted_keys() {
+        let sections = parse_ini(
+            "[default]\n\
+             region = eu-west-1\n\
+             aws_secret_access_key = hunter2\n\
+             # comment\n\
+             [profile prod]\n\
+             role_arn = arn:aws:iam::123456789012:role/admin\n",
+            &AWS_KEYS,
+        );
+
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].get("region"), Some("eu-west-1"));
+        assert_eq!(sections[0].get("aws_secret_access_key"), None);
+        assert_eq!(sections[1].name, "profile prod");
+    }
+
+    #[test]
+    fn test_describe_aws_config() {
+        let sections = parse_ini(
+            "[default]\nregion = us-east-1\n\n\
+             [profile staging]\nsso_session = corp\nsso_account_id = 111122223333\nsso_role_name = Dev\nregion = eu-central-1\n\n\
+             [sso-session corp]\nsso_region = us-east-1\n",
+            &AWS_KEYS,
+        );
+
+        let context = describe_aws_config(&sections, "staging", None);
+        assert!(context.contains("Region: eu-central-1 (from profile)"));
+        assert!(context.contains("SSO account: 111122223333 (role Dev)"));
+        assert!(context.contains("Profiles (use --profile <name>): default (us-east-1), staging (eu-central-1)"));
+
+        let context = describe_aws_config(&sections, "missing", Some("ap-south-1"));
+        assert!(context.contains("Region: ap-south-1 (from environment)"));
+        assert!(context.contains("Profile missing is not defined"));
+    }
+
+    #[test]
+    fn test_gcloud_and_azure() {
+        let sections = parse_ini(
+            "[core]\naccount = dev@example.com\nproject = shop-prod\n\n[compute]\nregion = europe-west1\n",
+            &GCLOUD_KEYS,
+        );
+        let context = describe_gcloud_config(&sections);
+        assert!(context.contains("Project: shop-prod"));
+        assert!(context.contains("Default region: europe-west1"));
+
+        let context = describe_azure_profile(
+            "\u{feff}{\"installationId\": \"x\", \"subscriptions\": [\
+             {\"id\": \"sub-1\", \"name\": \"Dev\", \"isDefault\": false, \"user\": {\"name\": \"dev@example.com\"}},\
+             {\"id\": \"sub-2\", \"name\": \"Prod\", \"isDefault\": true, \"user\": {\"name\": \"dev@example.com\"}}]}",
+        )
+        .unwrap();
+        assert!(context.starts_with("Default subscription: Prod (sub-2)"));
+        assert!(context.contains("Other subscriptions (use --subscription <name>): Dev"));
+    }
+
+    #[test]
+    fn test_cloud_relevance_and_no_credentials() {
+        assert_eq!(AwsProvider.relevance("copy this to s3"), 1.0);
+        assert_eq!(AwsProvider.relevance("list the laws"), 0.0);
+        assert_eq!(AzureProvider.relevance("lazy loading"), 0.0);
+        assert_eq!(GcloudProvider.relevance("which region is the bucket in"), 1.0);
+
+        let home = TempDir::new().unwrap();
+        fs::create_dir_all(home.path().join(".aws")).unwrap();
+        fs::write(home.path().join(".aws/config"), "[default]\nregion = us-west-2\n").unwrap();
+        fs::write(
+            home.path().join(".aws/credentials"),
+            "[default]\naws_access_key_id = AKIAEXAMPLE\n",
+        )
+        .unwrap();
+
+        let ctx = ProviderContext::new("s3 ls", home.path().to_path_buf(), Some(home.path().to_path_buf()), None);
+        let context = AwsProvider.gather(&ctx).unwrap();
+        assert!(!context.contains("AKIAEXAMPLE"));
+    }
+}
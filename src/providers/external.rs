@@ -0,0 +1,218 @@
+//! User-defined providers backed by shell commands
+//!
+//! ```toml
+//! [[context_providers]]
+//! name = "terraform"
+//! keywords = ["terraform", "tf", "workspace"]
+//! detect_files = ["main.tf"]
+//! command = "terraform workspace show"
+//! ```
+//!
+//! The command runs through `sh -c` in the current directory with the query in
+//! `$EZ_QUERY`. Its stdout is used as the context section; with
+//! `format = "json"` an object is rendered as `key: value` lines. Commands that
+//! run longer than `timeout_secs` (default 2) are killed and the provider skipped.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::Duration;
+
+use super::{keyword_relevance, ContextProvider, ProviderContext, DEFAULT_PROVIDER_TIMEOUT};
+use crate::process_util::output_with_timeout;
+
+/// At most this many lines of provider output make it into the prompt
+const MAX_OUTPUT_LINES: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// A `[[context_providers]]` entry from the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalProviderConfig {
+    pub name: String,
+    /// Section heading; defaults to the name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Words in a query that trigger the provider; empty means always
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Files of which at least one must exist in the current directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detect_files: Vec<String>,
+    /// Shell command whose stdout becomes the context
+    pub command: String,
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Seconds before the command is killed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<f64>,
+}
+
+pub struct ExternalProvider {
+    config: ExternalProviderConfig,
+    title: String,
+}
+
+impl ExternalProvider {
+    pub fn new(config: ExternalProviderConfig) -> Self {
+        let title = config.title.clone().unwrap_or_else(|| config.name.clone());
+        Self { config, title }
+    }
+}
+
+impl ContextProvider for ExternalProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        self.config.keywords.iter().map(String::as_str).collect()
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        if self.config.keywords.is_empty() {
+            1.0
+        } else {
+            keyword_relevance(query, &self.config.keywords)
+        }
+    }
+
+    fn timeout(&self) -> Duration {
+        self.config
+            .timeout_secs
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .unwrap_or(DEFAULT_PROVIDER_TIMEOUT)
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        self.config.detect_files.is_empty() || self.config.detect_files.iter().any(|f| ctx.cwd.join(f).exists())
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", &self.config.command])
+            .current_dir(&ctx.cwd)
+            .env("EZ_QUERY", &ctx.query);
+
+        let output = output_with_timeout(&mut command, self.timeout()).ok()??;
+        if !output.status.success() {
+            return None;
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let text = match self.config.format {
+            OutputFormat::Text => stdout.trim().to_string(),
+            OutputFormat::Json => render_json(&serde_json::from_str(&stdout).ok()?),
+        };
+
+        let text: Vec<&str> = text.lines().take(MAX_OUTPUT_LINES).collect();
+        if text.is_empty() {
+            None
+        } else {
+            Some(text.join("\n"))
+        }
+    }
+}
+
+/// Render provider JSON as plain lines: objects as `key: value`, arrays one item per line
+fn render_json(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    fn scalar(value: &Value) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| format!("{}: {}", key, scalar(value)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Array(items) => items.iter().map(scalar).collect::<Vec<_>>().join("\n"),
+        Value::Null => String::new(),
+        other => scalar(other).trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn provider(toml_src: &str) -> ExternalProvider {
+        ExternalProvider::new(toml::from_str(toml_src).unwrap())
+    }
+
+    fn context_in(dir: &TempDir, query: &str) -> ProviderContext {
+        ProviderContext::new(query, dir.path().to_path_buf(), Some(dir.path().to_path_buf()), None)
+    }
+
+    #[test]
+    fn test_text_provider() {
+        let dir = TempDir::new().unwrap();
+        let provider = provider(
+            r#"
+            name = "echo"
+            keywords = ["deploy"]
+            command = "echo \"query was: $EZ_QUERY\""
+            "#,
+        );
+
+        assert_eq!(provider.title(), "echo");
+        assert_eq!(provider.relevance("deploy the app"), 1.0);
+        assert_eq!(provider.relevance("list files"), 0.0);
+        assert_eq!(
+            provider.gather(&context_in(&dir, "deploy the app")).as_deref(),
+            Some("query was: deploy the app")
+        );
+    }
+
+    #[test]
+    fn test_json_provider() {
+        let dir = TempDir::new().unwrap();
+        let provider = provider(
+            r#"
+            name = "terraform"
+            title = "Terraform"
+            format = "json"
+            command = "echo '{\"workspace\": \"staging\", \"modules\": 3}'"
+            "#,
+        );
+
+        assert_eq!(provider.relevance("anything"), 1.0);
+        assert_eq!(
+            provider.gather(&context_in(&dir, "")).as_deref(),
+            Some("modules: 3\nworkspace: staging")
+        );
+    }
+
+    #[test]
+    fn test_detect_files_and_failures() {
+        let dir = TempDir::new().unwrap();
+        let provider = provider(
+            r#"
+            name = "tf"
+            detect_files = ["main.tf"]
+            command = "exit 1"
+            "#,
+        );
+
+        assert!(!provider.detect(&context_in(&dir, "")));
+        std::fs::write(dir.path().join("main.tf"), "").unwrap();
+        assert!(provider.detect(&context_in(&dir, "")));
+        assert_eq!(provider.gather(&context_in(&dir, "")), None);
+    }
+}
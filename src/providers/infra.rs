@@ -0,0 +1,366 @@
+//! Terraform and docker compose providers
+
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::{ContextProvider, ProviderContext};
+
+const COMPOSE_FILES: [&str; 4] = ["compose.yaml", "compose.yml", "docker-compose.yaml", "docker-compose.yml"];
+
+/// Settings worth showing from a backend or cloud block
+const BACKEND_SETTINGS: [&str; 8] = [
+    "bucket",
+    "key",
+    "prefix",
+    "region",
+    "workspace_key_prefix",
+    "resource_group_name",
+    "storage_account_name",
+    "organization",
+];
+
+/// Terraform backend configuration found in a module's `.tf` files
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerraformBackend {
+    /// `s3`, `gcs`, `local`, ... or `cloud` for a `cloud {}` block
+    pub kind: String,
+    pub settings: Vec<(String, String)>,
+}
+
+/// Find the `backend "x" {}` or `cloud {}` block in Terraform source
+pub fn parse_terraform_backend(source: &str) -> Option<TerraformBackend> {
+    static BLOCK: OnceLock<Regex> = OnceLock::new();
+    static SETTING: OnceLock<Regex> = OnceLock::new();
+    let block = BLOCK.get_or_init(|| Regex::new(r#"(?m)^\s*(?:backend\s+"([^"]+)"|(cloud))\s*\{"#).unwrap());
+    let setting = SETTING.get_or_init(|| Regex::new(r#"(?m)^\s*([a-z_]+)\s*=\s*"([^"]*)""#).unwrap());
+
+    let caps = block.captures(source)?;
+    let kind = caps.get(1).or(caps.get(2))?.as_str().to_string();
+
+    // The block body runs to the matching closing brace
+    let body_start = caps.get(0)?.end();
+    let mut depth = 1;
+    let body_len = source[body_start..].find(|c| {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        depth == 0
+    })?;
+    let body = &source[body_start..body_start + body_len];
+
+    let settings = setting
+        .captures_iter(body)
+        .filter(|c| BACKEND_SETTINGS.contains(&&c[1]) || &c[1] == "name")
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect();
+
+    Some(TerraformBackend { kind, settings })
+}
+
+fn file_names(dir: &Path, matches: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|name| matches(name))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+pub struct TerraformProvider;
+
+impl ContextProvider for TerraformProvider {
+    fn name(&self) -> &str {
+        "terraform"
+    }
+
+    fn title(&self) -> &str {
+        "Terraform Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec![
+            "terraform", "tf", "plan", "apply", "workspace", "infra", "state", "backend", "tfvars", "provision",
+            "destroy", "module",
+        ]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.cwd.join(".terraform").is_dir() || !file_names(&ctx.cwd, |n| n.ends_with(".tf")).is_empty()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        let current = std::env::var("TF_WORKSPACE")
+            .ok()
+            .or_else(|| fs::read_to_string(ctx.cwd.join(".terraform/environment")).ok())
+            .map(|w| w.trim().to_string())
+            .filter(|w| !w.is_empty())
+            .unwrap_or_else(|| "default".to_string());
+        context.push_str(&format!("Current workspace: {}", current));
+
+        let mut workspaces = vec!["default".to_string()];
+        workspaces.extend(file_names(&ctx.cwd.join("terraform.tfstate.d"), |_| true));
+        if workspaces.len() > 1 {
+            context.push_str(&format!("\nLocal workspaces: {}", workspaces.join(", ")));
+        }
+
+        let source: String = file_names(&ctx.cwd, |n| n.ends_with(".tf"))
+            .iter()
+            .filter_map(|name| fs::read_to_string(ctx.cwd.join(name)).ok())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        match parse_terraform_backend(&source) {
+            Some(backend) => {
+                context.push_str(&format!("\nBackend: {}", backend.kind));
+                for (key, value) in &backend.settings {
+                    context.push_str(&format!(" {}={}", key, value));
+                }
+            }
+            None => context.push_str("\nBackend: local (no backend block)"),
+        }
+
+        let mut var_files = file_names(&ctx.cwd, |n| n.ends_with(".tfvars") || n.ends_with(".tfvars.json"));
+        for dir in ["env", "envs", "environments", "vars"] {
+            var_files.extend(
+                file_names(&ctx.cwd.join(dir), |n| n.ends_with(".tfvars"))
+                    .into_iter()
+                    .map(|n| format!("{}/{}", dir, n)),
+            );
+        }
+        if !var_files.is_empty() {
+            context.push_str(&format!("\nVariable files: {}", var_files.join(", ")));
+        }
+
+        if !ctx.cwd.join(".terraform").is_dir() {
+            context.push_str("\nNot initialized (run terraform init first)");
+        }
+
+        Some(context)
+    }
+}
+
+/// A service from a compose file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComposeService {
+    pub name: String,
+    /// Image, or `build <context>`
+    pub source: Option<String>,
+    pub ports: Vec<String>,
+    pub profiles: Vec<String>,
+    pub depends_on: Vec<String>,
+}
+
+pub fn parse_compose(content: &str) -> Vec<ComposeService> {
+    use serde_yaml::Value;
+
+    fn strings(value: Option<&Value>) -> Vec<String> {
+        match value {
+            Some(Value::Sequence(items)) => items
+                .iter()
+                .filter_map(|i| match i {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Number(n) => Some(n.to_string()),
+                    // Long port syntax
+                    Value::Mapping(_) => i.get("published").map(|p| match p {
+                        Value::String(s) => s.clone(),
+                        other => serde_yaml::to_string(other).unwrap_or_default().trim().to_string(),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+            // depends_on in long form is a mapping of service names
+            Some(Value::Mapping(map)) => map.keys().filter_map(|k| k.as_str().map(String::from)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    let Ok(yaml) = serde_yaml::from_str::<Value>(content) else {
+        return Vec::new();
+    };
+    let Some(services) = yaml.get("services").and_then(|s| s.as_mapping()) else {
+        return Vec::new();
+    };
+
+    services
+        .iter()
+        .filter_map(|(name, service)| {
+            let build = match service.get("build") {
+                Some(Value::String(context)) => Some(format!("build {}", context)),
+                Some(build) => Some(format!(
+                    "build {}",
+                    build.get("context").and_then(|c| c.as_str()).unwrap_or(".")
+                )),
+                None => None,
+            };
+
+            Some(ComposeService {
+                name: name.as_str()?.to_string(),
+                source: service.get("image").and_then(|i| i.as_str()).map(String::from).or(build),
+                ports: strings(service.get("ports")),
+                profiles: strings(service.get("profiles")),
+                depends_on: strings(service.get("depends_on")),
+            })
+        })
+        .collect()
+}
+
+pub struct ComposeProvider;
+
+impl ContextProvider for ComposeProvider {
+    fn name(&self) -> &str {
+        "compose"
+    }
+
+    fn title(&self) -> &str {
+        "Docker Compose Services"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["compose", "service", "stack", "container", "docker", "restart", "logs"]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.project.find_file(&COMPOSE_FILES).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let path = ctx.project.find_file(&COMPOSE_FILES)?;
+        let services = parse_compose(&fs::read_to_string(&path).ok()?);
+        if services.is_empty() {
+            return None;
+        }
+
+        let command = if path.parent() == Some(ctx.cwd.as_path()) {
+            "docker compose".to_string()
+        } else {
+            format!("docker compose -f {}", path.display())
+        };
+        let mut context = format!("{} (run with {}):", path.display(), command);
+
+        for service in &services {
+            context.push_str(&format!("\n  {}", service.name));
+            if let Some(source) = &service.source {
+                context.push_str(&format!(" ({})", source));
+            }
+            if !service.ports.is_empty() {
+                context.push_str(&format!(" ports {}", service.ports.join(", ")));
+            }
+            if !service.depends_on.is_empty() {
+                context.push_str(&format!(" depends on {}", service.depends_on.join(", ")));
+            }
+            if !service.profiles.is_empty() {
+                context.push_str(&format!(" [profiles: {}]", service.profiles.join(", ")));
+            }
+        }
+
+        Some(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_terraform_backend() {
+        let backend = parse_terraform_backend(
+            r#"
+            terraform {
+              required_version = ">= 1.5"
+              backend "s3" {
+                bucket = "acme-tf-state"
+                key    = "network/terraform.tfstate"
+                region = "eu-west-1"
+                assume_role {
+                  role_arn = "arn:aws:iam::123:role/tf"
+                }
+              }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(backend.kind, "s3");
+        assert_eq!(
+            backend.settings,
+            vec![
+                ("bucket".to_string(), "acme-tf-state".to_string()),
+                ("key".to_string(), "network/terraform.tfstate".to_string()),
+                ("region".to_string(), "eu-west-1".to_string()),
+            ]
+        );
+
+        let cloud = parse_terraform_backend(
+            "terraform {\n  cloud {\n    organization = \"acme\"\n    workspaces {\n      name = \"app-staging\"\n    }\n  }\n}\n",
+        )
+        .unwrap();
+        assert_eq!(cloud.kind, "cloud");
+        assert!(cloud.settings.contains(&("name".to_string(), "app-staging".to_string())));
+
+        assert_eq!(parse_terraform_backend("resource \"null_resource\" \"x\" {}"), None);
+    }
+
+    #[test]
+    fn test_terraform_provider_workspaces() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("main.tf"), "terraform {\n  backend \"gcs\" {\n    bucket = \"state\"\n  }\n}\n").unwrap();
+        fs::create_dir_all(root.join(".terraform")).unwrap();
+        fs::write(root.join(".terraform/environment"), "staging").unwrap();
+        fs::create_dir_all(root.join("terraform.tfstate.d/staging")).unwrap();
+        fs::create_dir_all(root.join("terraform.tfstate.d/prod")).unwrap();
+        fs::create_dir_all(root.join("envs")).unwrap();
+        fs::write(root.join("envs/staging.tfvars"), "").unwrap();
+
+        let ctx = ProviderContext::new("plan the staging workspace", root.to_path_buf(), Some(root.to_path_buf()), None);
+        assert!(TerraformProvider.relevance(&ctx.query) > 0.0);
+        assert!(TerraformProvider.detect(&ctx));
+
+        let context = TerraformProvider.gather(&ctx).unwrap();
+        assert!(context.contains("Current workspace: staging"));
+        assert!(context.contains("Local workspaces: default, prod, staging"));
+        assert!(context.contains("Backend: gcs bucket=state"));
+        assert!(context.contains("Variable files: envs/staging.tfvars"));
+        assert!(!context.contains("Not initialized"));
+    }
+
+    #[test]
+    fn test_parse_compose() {
+        let services = parse_compose(
+            "services:\n\
+             \x20 web:\n\
+             \x20   build: ./web\n\
+             \x20   ports: [\"3000:3000\"]\n\
+             \x20   depends_on:\n\
+             \x20     db:\n\
+             \x20       condition: service_healthy\n\
+             \x20 db:\n\
+             \x20   image: postgres:16\n\
+             \x20   ports:\n\
+             \x20     - target: 5432\n\
+             \x20       published: 5433\n\
+             \x20 mailhog:\n\
+             \x20   image: mailhog/mailhog\n\
+             \x20   profiles: [debug]\n",
+        );
+
+        assert_eq!(services.len(), 3);
+        assert_eq!(services[0].source.as_deref(), Some("build ./web"));
+        assert_eq!(services[0].depends_on, vec!["db"]);
+        assert_eq!(services[1].ports, vec!["5433"]);
+        assert_eq!(services[2].profiles, vec!["debug"]);
+        assert!(parse_compose("version: '3'").is_empty());
+    }
+}
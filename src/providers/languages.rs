@@ -0,0 +1,589 @@
+//! Go, Gradle, Maven and Ruby providers
+
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::builtin::{command_stdout, found_line, non_empty};
+use super::{keyword_relevance, word_relevance, ContextProvider, ProviderContext};
+use crate::project::ProjectKind;
+
+/// Facts from a go.mod file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoModule {
+    pub module: Option<String>,
+    pub go_version: Option<String>,
+    pub toolchain: Option<String>,
+    /// `tool` directives (Go 1.24+)
+    pub tools: Vec<String>,
+    pub requires: usize,
+    pub replaces: usize,
+}
+
+pub fn parse_go_mod(content: &str) -> GoModule {
+    let mut module = GoModule::default();
+    let mut block: Option<String> = None;
+
+    for line in content.lines() {
+        let line = line.split("//").next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(directive) = &block {
+            if line == ")" {
+                block = None;
+            } else {
+                module.add_directive(&directive.clone(), line);
+            }
+            continue;
+        }
+
+        let (directive, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        if rest == "(" {
+            block = Some(directive.to_string());
+        } else {
+            module.add_directive(directive, rest);
+        }
+    }
+
+    module
+}
+
+impl GoModule {
+    fn add_directive(&mut self, directive: &str, value: &str) {
+        match directive {
+            "module" => self.module = Some(value.to_string()),
+            "go" => self.go_version = Some(value.to_string()),
+            "toolchain" => self.toolchain = Some(value.to_string()),
+            "tool" => self.tools.push(value.to_string()),
+            "require" => self.requires += 1,
+            "replace" => self.replaces += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Imports of a `tools.go` file (the pre-1.24 way of pinning tools)
+fn tools_go_imports(content: &str) -> Vec<String> {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    let import = IMPORT.get_or_init(|| Regex::new(r#"(?m)^\s*_\s+"([^"]+)""#).unwrap());
+    import.captures_iter(content).map(|c| c[1].to_string()).collect()
+}
+
+pub struct GoProvider;
+
+impl ContextProvider for GoProvider {
+    fn name(&self) -> &str {
+        "go"
+    }
+
+    fn title(&self) -> &str {
+        "Go Context"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        word_relevance(query, &["go", "golang", "go.mod", "go.sum", "gofmt", "goroutine", "gopls"])
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(version) = command_stdout(ctx, "go", &["version"]) {
+            context.push_str(&format!("Go version: {}", version));
+        }
+
+        if let Some(package) = ctx.project.nearest(ProjectKind::Go) {
+            context.push_str(&found_line(ctx, package));
+
+            let go_mod = parse_go_mod(&fs::read_to_string(package.dir.join("go.mod")).unwrap_or_default());
+            if let Some(module) = &go_mod.module {
+                context.push_str(&format!("\nModule: {}", module));
+            }
+            if let Some(version) = &go_mod.go_version {
+                context.push_str(&format!("\nGo directive: {}", version));
+            }
+            if let Some(toolchain) = &go_mod.toolchain {
+                context.push_str(&format!("\nToolchain: {}", toolchain));
+            }
+            context.push_str(&format!(
+                "\nDependencies: {} required, {} replaced",
+                go_mod.requires, go_mod.replaces
+            ));
+
+            let mut tools = go_mod.tools.clone();
+            if let Ok(tools_go) = fs::read_to_string(package.dir.join("tools.go")) {
+                tools.extend(tools_go_imports(&tools_go));
+            }
+            if !tools.is_empty() {
+                context.push_str(&format!("\nTools (run with go tool / go run): {}", tools.join(", ")));
+            }
+
+            if package.dir.join("vendor").is_dir() {
+                context.push_str("\nVendored dependencies: vendor/");
+            }
+        }
+
+        if let Some(workspace) = ctx.project.workspace_for(ProjectKind::Go) {
+            context.push_str(&format!(
+                "\nGo workspace at {} (use: {})",
+                workspace.root.display(),
+                workspace.members.join(", ")
+            ));
+        }
+
+        non_empty(context)
+    }
+}
+
+/// Quoted strings following `include` in a settings.gradle(.kts)
+pub fn parse_gradle_modules(settings: &str) -> Vec<String> {
+    static QUOTED: OnceLock<Regex> = OnceLock::new();
+    let quoted = QUOTED.get_or_init(|| Regex::new(r#"["']([^"']+)["']"#).unwrap());
+
+    settings
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("include"))
+        .flat_map(|line| quoted.captures_iter(line).map(|c| c[1].to_string()).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Custom tasks and applied plugins from a build.gradle(.kts)
+pub fn parse_gradle_build(build: &str) -> (Vec<String>, Vec<String>) {
+    static TASK: OnceLock<Regex> = OnceLock::new();
+    static PLUGIN: OnceLock<Regex> = OnceLock::new();
+    let task = TASK.get_or_init(|| {
+        Regex::new(r#"(?m)(?:tasks\.register(?:<[^>]+>)?\(\s*["']([A-Za-z0-9_-]+)["']|^\s*task\s+\(?["']?([A-Za-z0-9_-]+))"#)
+            .unwrap()
+    });
+    let plugin = PLUGIN.get_or_init(|| {
+        Regex::new(r#"(?m)^\s*(?:id\s*\(?\s*["']([^"']+)["']|kotlin\(\s*["']([^"']+)["']\s*\)|(java|application|`java-library`|`maven-publish`)\s*$)"#)
+            .unwrap()
+    });
+
+    let tasks = task
+        .captures_iter(build)
+        .filter_map(|c| c.get(1).or(c.get(2)).map(|m| m.as_str().to_string()))
+        .collect();
+    let plugins = plugin
+        .captures_iter(build)
+        .filter_map(|c| {
+            c.get(1)
+                .map(|m| m.as_str().to_string())
+                .or_else(|| c.get(2).map(|m| format!("kotlin-{}", m.as_str())))
+                .or_else(|| c.get(3).map(|m| m.as_str().trim_matches('`').to_string()))
+        })
+        .collect();
+
+    (tasks, plugins)
+}
+
+/// Facts from a pom.xml
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MavenProject {
+    pub artifact: Option<String>,
+    pub packaging: Option<String>,
+    pub java_version: Option<String>,
+    pub modules: Vec<String>,
+    pub profiles: Vec<String>,
+}
+
+/// Extract the interesting parts of a pom.xml without a full XML parser
+pub fn parse_pom(pom: &str) -> MavenProject {
+    static NESTED: OnceLock<Regex> = OnceLock::new();
+    static MODULE: OnceLock<Regex> = OnceLock::new();
+    static PROFILE: OnceLock<Regex> = OnceLock::new();
+    let nested = NESTED.get_or_init(|| {
+        Regex::new(r"(?s)<(parent|dependencies|dependencyManagement|build|profiles|plugins|reporting)>.*?</(parent|dependencies|dependencyManagement|build|profiles|plugins|reporting)>")
+            .unwrap()
+    });
+    let module = MODULE.get_or_init(|| Regex::new(r"<module>\s*([^<]+?)\s*</module>").unwrap());
+    let profile = PROFILE.get_or_init(|| Regex::new(r"(?s)<profile>\s*<id>\s*([^<]+?)\s*</id>").unwrap());
+
+    // Only look at the project's own top-level elements
+    let top_level = nested.replace_all(pom, "");
+    let element = |name: &str| {
+        let open = format!("<{}>", name);
+        let start = top_level.find(&open)? + open.len();
+        let end = top_level[start..].find('<')?;
+        Some(top_level[start..start + end].trim().to_string())
+    };
+
+    MavenProject {
+        artifact: element("artifactId"),
+        packaging: element("packaging"),
+        java_version: element("maven.compiler.release")
+            .or_else(|| element("java.version"))
+            .or_else(|| element("maven.compiler.source")),
+        modules: module.captures_iter(pom).map(|c| c[1].to_string()).collect(),
+        profiles: profile.captures_iter(pom).map(|c| c[1].to_string()).collect(),
+    }
+}
+
+/// Wrapper script if the project ships one, else the global tool
+fn wrapper_or(dir: &Path, wrapper: &str, tool: &str) -> String {
+    if dir.join(wrapper).is_file() {
+        format!("./{}", wrapper)
+    } else {
+        tool.to_string()
+    }
+}
+
+pub struct GradleProvider;
+
+const GRADLE_FILES: [&str; 4] = ["settings.gradle.kts", "settings.gradle", "build.gradle.kts", "build.gradle"];
+
+impl ContextProvider for GradleProvider {
+    fn name(&self) -> &str {
+        "gradle"
+    }
+
+    fn title(&self) -> &str {
+        "Gradle Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["gradle", "java", "kotlin", "jvm", "jar", "spring", "android"]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.project.find_file(&GRADLE_FILES).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let build_file = ctx.project.find_file(&["build.gradle.kts", "build.gradle"]);
+        let settings_file = ctx.project.find_file(&["settings.gradle.kts", "settings.gradle"]);
+        // The root project is where settings.gradle lives
+        let root = settings_file.as_ref().or(build_file.as_ref())?.parent()?.to_path_buf();
+
+        let mut context = format!("Gradle project at {}", root.display());
+        context.push_str(&format!("\nRun with: {}", wrapper_or(&root, "gradlew", "gradle")));
+
+        if let Some(settings) = settings_file.and_then(|f| fs::read_to_string(f).ok()) {
+            let modules = parse_gradle_modules(&settings);
+            if !modules.is_empty() {
+                context.push_str(&format!("\nModules: {}", modules.join(", ")));
+            }
+        }
+
+        if let Some(build_file) = build_file {
+            let (tasks, plugins) = parse_gradle_build(&fs::read_to_string(&build_file).unwrap_or_default());
+            if build_file.parent() != Some(root.as_path()) {
+                context.push_str(&format!("\nCurrent module build file: {}", build_file.display()));
+            }
+            if !plugins.is_empty() {
+                context.push_str(&format!("\nPlugins: {}", plugins.join(", ")));
+            }
+            if !tasks.is_empty() {
+                context.push_str(&format!("\nCustom tasks: {}", tasks.join(", ")));
+            }
+        }
+
+        Some(context)
+    }
+}
+
+pub struct MavenProvider;
+
+impl ContextProvider for MavenProvider {
+    fn name(&self) -> &str {
+        "maven"
+    }
+
+    fn title(&self) -> &str {
+        "Maven Context"
+    }
+
+    fn keywords(&self) -> Vec<&str> {
+        vec!["maven", "mvn", "pom", "java", "jvm", "jar", "spring"]
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.project.find_file(&["pom.xml"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let pom_path = ctx.project.find_file(&["pom.xml"])?;
+        let dir = pom_path.parent()?;
+        let pom = parse_pom(&fs::read_to_string(&pom_path).ok()?);
+
+        let mut context = format!("Found: {}", pom_path.display());
+        context.push_str(&format!("\nRun with: {}", wrapper_or(dir, "mvnw", "mvn")));
+
+        if let Some(artifact) = &pom.artifact {
+            context.push_str(&format!("\nArtifact: {}", artifact));
+            if let Some(packaging) = &pom.packaging {
+                context.push_str(&format!(" ({})", packaging));
+            }
+        }
+        if let Some(java) = &pom.java_version {
+            context.push_str(&format!("\nJava version: {}", java));
+        }
+        if !pom.modules.is_empty() {
+            context.push_str(&format!("\nModules (build one with -pl <module> -am): {}", pom.modules.join(", ")));
+        }
+        if !pom.profiles.is_empty() {
+            context.push_str(&format!("\nProfiles (-P <id>): {}", pom.profiles.join(", ")));
+        }
+
+        Some(context)
+    }
+}
+
+/// Rake tasks, with namespaces, from a Rakefile
+pub fn parse_rakefile(content: &str) -> Vec<String> {
+    static NAMESPACE: OnceLock<Regex> = OnceLock::new();
+    static TASK: OnceLock<Regex> = OnceLock::new();
+    let namespace = NAMESPACE.get_or_init(|| Regex::new(r#"^(\s*)namespace\s+[:"']([\w-]+)"#).unwrap());
+    let task = TASK.get_or_init(|| Regex::new(r#"^\s*(?:multi)?task\s+\(?\s*(?::([\w-]+)|["']([\w:-]+)["']|([\w-]+):)"#).unwrap());
+
+    let mut tasks = Vec::new();
+    let mut scopes: Vec<(usize, String)> = Vec::new();
+
+    for line in content.lines() {
+        let indent = line.len() - line.trim_start().len();
+
+        if line.trim() == "end" {
+            if scopes.last().is_some_and(|(depth, _)| *depth == indent) {
+                scopes.pop();
+            }
+            continue;
+        }
+
+        if let Some(caps) = namespace.captures(line) {
+            scopes.push((caps[1].len(), caps[2].to_string()));
+        } else if let Some(caps) = task.captures(line) {
+            let name = caps.get(1).or(caps.get(2)).or(caps.get(3)).map(|m| m.as_str()).unwrap_or_default();
+            let mut full: Vec<&str> = scopes.iter().map(|(_, n)| n.as_str()).collect();
+            full.push(name);
+            tasks.push(full.join(":"));
+        }
+    }
+
+    tasks
+}
+
+/// Gems worth calling out because they decide which commands make sense
+const NOTABLE_GEMS: [&str; 9] = [
+    "rails", "rspec", "rspec-rails", "minitest", "rubocop", "sinatra", "sidekiq", "jekyll", "standard",
+];
+
+pub struct RubyProvider;
+
+impl ContextProvider for RubyProvider {
+    fn name(&self) -> &str {
+        "ruby"
+    }
+
+    fn title(&self) -> &str {
+        "Ruby Context"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        keyword_relevance(query, &["ruby", "gem", "bundle", "rake", "rails", "rspec", "rubocop"])
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.project.find_file(&["Gemfile", ".ruby-version", "Rakefile", "gems.rb"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let mut context = String::new();
+
+        if let Some(version) = command_stdout(ctx, "ruby", &["--version"]) {
+            context.push_str(&format!("Ruby version: {}", version));
+        }
+
+        if let Some(path) = ctx.project.find_file(&[".ruby-version"]) {
+            if let Ok(pinned) = fs::read_to_string(&path) {
+                context.push_str(&format!("\nPinned in .ruby-version: {}", pinned.trim()));
+            }
+        }
+
+        if let Some(gemfile) = ctx.project.find_file(&["Gemfile", "gems.rb"]) {
+            let dir = gemfile.parent()?;
+            context.push_str(&format!("\nFound: {}", gemfile.display()));
+
+            let content = fs::read_to_string(&gemfile).unwrap_or_default();
+            let gems: Vec<&str> = content
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("gem "))
+                .filter_map(|rest| rest.split(['"', '\'']).nth(1))
+                .collect();
+            let notable: Vec<&str> = gems.iter().copied().filter(|g| NOTABLE_GEMS.contains(g)).collect();
+            context.push_str(&format!("\nGems: {} declared", gems.len()));
+            if !notable.is_empty() {
+                context.push_str(&format!(" (including {})", notable.join(", ")));
+            }
+
+            if let Ok(lock) = fs::read_to_string(dir.join("Gemfile.lock")) {
+                let bundler = lock
+                    .lines()
+                    .skip_while(|l| l.trim() != "BUNDLED WITH")
+                    .nth(1)
+                    .map(|v| format!(" (bundler {})", v.trim()))
+                    .unwrap_or_default();
+                context.push_str(&format!("\nGemfile.lock present{}: run tools with bundle exec", bundler));
+            }
+
+            if dir.join("bin/rails").is_file() {
+                context.push_str("\nRails app: use bin/rails for generators, db tasks and the server");
+            }
+        }
+
+        if let Some(rakefile) = ctx.project.find_file(&["Rakefile", "rakefile", "Rakefile.rb"]) {
+            let tasks = parse_rakefile(&fs::read_to_string(&rakefile).unwrap_or_default());
+            if !tasks.is_empty() {
+                context.push_str(&format!("\nRake tasks: {}", tasks.join(", ")));
+            }
+        }
+
+        non_empty(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_go_mod() {
+        let module = parse_go_mod(
+            "module github.com/acme/api\n\
+             \n\
+             go 1.24\n\
+             \n\
+             toolchain go1.24.2\n\
+             \n\
+             require (\n\
+             \tgithub.com/spf13/cobra v1.8.0\n\
+             \tgolang.org/x/sync v0.7.0 // indirect\n\
+             )\n\
+             \n\
+             require github.com/stretchr/testify v1.9.0\n\
+             \n\
+             replace github.com/acme/lib => ../lib\n\
+             \n\
+             tool (\n\
+             \tgolang.org/x/tools/cmd/stringer\n\
+             \tgithub.com/golangci/golangci-lint/cmd/golangci-lint\n\
+             )\n",
+        );
+
+        assert_eq!(module.module.as_deref(), Some("github.com/acme/api"));
+        assert_eq!(module.go_version.as_deref(), Some("1.24"));
+        assert_eq!(module.toolchain.as_deref(), Some("go1.24.2"));
+        assert_eq!(module.requires, 3);
+        assert_eq!(module.replaces, 1);
+        assert_eq!(module.tools.len(), 2);
+    }
+
+    #[test]
+    fn test_go_provider_relevance_and_tools_go() {
+        assert_eq!(GoProvider.relevance("restart mongo"), 0.0);
+        assert_eq!(GoProvider.relevance("run the go tests"), 1.0);
+
+        let imports = tools_go_imports("//go:build tools\npackage tools\n\nimport (\n\t_ \"github.com/a/b/cmd/x\"\n)\n");
+        assert_eq!(imports, vec!["github.com/a/b/cmd/x"]);
+    }
+
+    #[test]
+    fn test_parse_gradle() {
+        let modules = parse_gradle_modules(
+            "rootProject.name = \"shop\"\ninclude(\":app\", \":core\")\ninclude ':feature:cart'\n",
+        );
+        assert_eq!(modules, vec![":app", ":core", ":feature:cart"]);
+
+        let (tasks, plugins) = parse_gradle_build(
+            "plugins {\n\
+             \x20   id(\"org.springframework.boot\") version \"3.3.0\"\n\
+             \x20   kotlin(\"jvm\") version \"2.0.0\"\n\
+             \x20   java\n\
+             }\n\
+             tasks.register<Exec>(\"dockerBuild\") {\n\
+             \x20   commandLine(\"docker\", \"build\", \".\")\n\
+             }\n\
+             task integrationTest(type: Test) {}\n",
+        );
+        assert_eq!(tasks, vec!["dockerBuild", "integrationTest"]);
+        assert_eq!(plugins, vec!["org.springframework.boot", "kotlin-jvm", "java"]);
+    }
+
+    #[test]
+    fn test_parse_pom() {
+        let pom = parse_pom(
+            r#"<project>
+              <parent><artifactId>spring-boot-starter-parent</artifactId></parent>
+              <artifactId>shop</artifactId>
+              <packaging>pom</packaging>
+              <properties><java.version>21</java.version></properties>
+              <modules>
+                <module>api</module>
+                <module>worker</module>
+              </modules>
+              <dependencies>
+                <dependency><artifactId>junit</artifactId></dependency>
+              </dependencies>
+              <profiles>
+                <profile><id>integration</id></profile>
+              </profiles>
+            </project>"#,
+        );
+
+        assert_eq!(pom.artifact.as_deref(), Some("shop"));
+        assert_eq!(pom.packaging.as_deref(), Some("pom"));
+        assert_eq!(pom.java_version.as_deref(), Some("21"));
+        assert_eq!(pom.modules, vec!["api", "worker"]);
+        assert_eq!(pom.profiles, vec!["integration"]);
+    }
+
+    #[test]
+    fn test_parse_rakefile() {
+        let tasks = parse_rakefile(
+            "require 'rake'\n\
+             \n\
+             task default: :spec\n\
+             \n\
+             desc 'Run specs'\n\
+             task :spec do\n\
+             end\n\
+             \n\
+             namespace :db do\n\
+             \x20 task :migrate do\n\
+             \x20 end\n\
+             \x20 task 'seed' do\n\
+             \x20 end\n\
+             end\n\
+             \n\
+             task :lint\n",
+        );
+
+        assert_eq!(tasks, vec!["default", "spec", "db:migrate", "db:seed", "lint"]);
+    }
+
+    #[test]
+    fn test_ruby_provider() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("Gemfile"), "source 'https://rubygems.org'\ngem 'rails', '~> 7.1'\ngem \"rspec-rails\"\ngem 'puma'\n").unwrap();
+        fs::write(root.join("Gemfile.lock"), "GEM\n  specs:\n\nBUNDLED WITH\n   2.5.6\n").unwrap();
+        fs::write(root.join(".ruby-version"), "3.3.1\n").unwrap();
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::write(root.join("bin/rails"), "").unwrap();
+
+        let ctx = ProviderContext::new("run the rspec suite", root.to_path_buf(), Some(root.to_path_buf()), None);
+        assert!(RubyProvider.relevance(&ctx.query) > 0.0);
+        assert!(RubyProvider.detect(&ctx));
+
+        let context = RubyProvider.gather(&ctx).unwrap();
+        assert!(context.contains("Pinned in .ruby-version: 3.3.1"));
+        assert!(context.contains("Gems: 3 declared (including rails, rspec-rails)"));
+        assert!(context.contains("Gemfile.lock present (bundler 2.5.6): run tools with bundle exec"));
+        assert!(context.contains("Rails app"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Context providers gather query-specific information for the prompt
+//!
+//! Each provider decides how relevant it is to a query, whether it applies to
+//! the current directory, and then gathers a section of context. Built-in
+//! providers live in [`builtin`]; users can declare their own shell-command
+//! providers in the config file (see [`external`]).
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::project::ProjectLayout;
+
+pub mod builtin;
+pub mod cloud;
+pub mod external;
+pub mod infra;
+pub mod languages;
+pub mod task_runners;
+
+pub use external::{ExternalProvider, ExternalProviderConfig};
+
+/// Providers scoring below this are not gathered
+pub const RELEVANCE_THRESHOLD: f32 = 0.5;
+
+/// How long a provider may take before it is skipped
+pub const DEFAULT_PROVIDER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What a provider gets to look at when gathering context
+#[derive(Debug, Clone)]
+pub struct ProviderContext {
+    pub query: String,
+    pub cwd: PathBuf,
+    /// `None` when `$HOME` is unset; providers then skip files under it
+    pub home_dir: Option<PathBuf>,
+    pub shell: Option<String>,
+    /// Project roots found walking up from `cwd`
+    pub project: ProjectLayout,
+}
+
+impl ProviderContext {
+    pub fn new(query: &str, cwd: PathBuf, home_dir: Option<PathBuf>, shell: Option<String>) -> Self {
+        let project = ProjectLayout::discover(&cwd);
+        Self {
+            query: query.to_string(),
+            cwd,
+            home_dir,
+            shell,
+            project,
+        }
+    }
+}
+
+pub trait ContextProvider: Send + Sync {
+    /// Short identifier, e.g. `git`
+    fn name(&self) -> &str;
+
+    /// Section heading in the prompt, e.g. `Git Context`
+    fn title(&self) -> &str;
+
+    /// Words in a query that make this provider relevant
+    fn keywords(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// Score between 0 and 1; defaults to matching [`ContextProvider::keywords`]
+    fn relevance(&self, query: &str) -> f32 {
+        keyword_relevance(query, &self.keywords())
+    }
+
+    /// Whether the provider applies here (tool installed, project files present)
+    fn detect(&self, _ctx: &ProviderContext) -> bool {
+        true
+    }
+
+    /// Time budget for `detect` and `gather` together
+    fn timeout(&self) -> Duration {
+        DEFAULT_PROVIDER_TIMEOUT
+    }
+
+    /// Gather the context section, or `None` if there is nothing to say
+    ///
+    /// Providers run on blocking threads and are abandoned once their timeout
+    /// passes, so any command they spawn should be bounded as well (see
+    /// [`crate::process_util::output_with_timeout`]).
+    fn gather(&self, ctx: &ProviderContext) -> Option<String>;
+}
+
+/// 1.0 if the query mentions any keyword, 0.0 otherwise
+pub fn keyword_relevance<S: AsRef<str>>(query: &str, keywords: &[S]) -> f32 {
+    let query = query.to_lowercase();
+    if keywords.iter().any(|kw| query.contains(&kw.as_ref().to_lowercase())) {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// 1.0 if the query contains any of `words` as a whole word, 0.0 otherwise
+///
+/// For short names like `go` that [`keyword_relevance`] would find inside
+/// "mongo" or "google".
+pub fn word_relevance(query: &str, words: &[&str]) -> f32 {
+    let matches = query
+        .split_whitespace()
+        .map(|token| token.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+        .any(|token| words.contains(&token.as_str()));
+    if matches {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// The providers that ship with ez
+pub fn builtin_providers() -> Vec<Box<dyn ContextProvider>> {
+    vec![
+        Box::new(builtin::ProjectProvider),
+        Box::new(task_runners::MakeProvider),
+        Box::new(task_runners::JustProvider),
+        Box::new(task_runners::TaskfileProvider),
+        Box::new(builtin::GitProvider),
+        Box::new(builtin::DockerProvider),
+        Box::new(infra::ComposeProvider),
+        Box::new(infra::TerraformProvider),
+        Box::new(builtin::NpmProvider),
+        Box::new(builtin::PythonProvider),
+        Box::new(builtin::ShellRcProvider),
+        Box::new(builtin::CargoProvider),
+        Box::new(languages::GoProvider),
+        Box::new(languages::GradleProvider),
+        Box::new(languages::MavenProvider),
+        Box::new(languages::RubyProvider),
+        Box::new(builtin::KubernetesProvider),
+        Box::new(cloud::AwsProvider),
+        Box::new(cloud::GcloudProvider),
+        Box::new(cloud::AzureProvider),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_relevance() {
+        assert_eq!(keyword_relevance("show my Git branches", &["git"]), 1.0);
+        assert_eq!(keyword_relevance("list files", &["git", "docker"]), 0.0);
+        assert_eq!(keyword_relevance("anything", &[] as &[&str]), 0.0);
+    }
+
+    #[test]
+    fn test_word_relevance() {
+        assert_eq!(word_relevance("run the go tests", &["go"]), 1.0);
+        assert_eq!(word_relevance("which version of Go?", &["go"]), 1.0);
+        assert_eq!(word_relevance("tidy go.mod", &["go.mod"]), 1.0);
+        assert_eq!(word_relevance("restart mongo and google-chrome", &["go"]), 0.0);
+    }
+
+    #[test]
+    fn test_builtin_provider_names_are_unique() {
+        let providers = builtin_providers();
+        let mut names: Vec<&str> = providers.iter().map(|p| p.name()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), providers.len());
+    }
+}
@@ -0,0 +1,380 @@
+//! Make, just and Task providers: the targets a project is actually driven by
+
+use regex::Regex;
+use std::fs;
+use std::sync::OnceLock;
+
+use super::{word_relevance, ContextProvider, ProviderContext};
+
+/// At most this many targets are listed per task runner
+const MAX_TARGETS: usize = 30;
+
+/// Verbs that usually mean "run one of the project's tasks"
+///
+/// Matched as whole words, since "run", "test" or "dev" turn up inside
+/// "truncate", "latest" or "/dev/null".
+const TASK_KEYWORDS: [&str; 29] = [
+    "make", "target", "targets", "recipe", "recipes", "task", "tasks", "just", "build", "test", "tests", "lint",
+    "run", "running", "start", "stop", "dev", "deploy", "release", "install", "clean", "format", "generate",
+    "migrate", "serve", "stack", "bootstrap", "makefile", "justfile",
+];
+
+/// A runnable target with its description, if the file documents one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Render targets as `name - description` lines under a heading
+fn format_targets(heading: String, targets: &[Target]) -> Option<String> {
+    if targets.is_empty() {
+        return None;
+    }
+
+    let mut context = heading;
+    for target in targets.iter().take(MAX_TARGETS) {
+        match &target.description {
+            Some(description) => context.push_str(&format!("\n  {} - {}", target.name, description)),
+            None => context.push_str(&format!("\n  {}", target.name)),
+        }
+    }
+    if targets.len() > MAX_TARGETS {
+        context.push_str(&format!("\n  ... and {} more", targets.len() - MAX_TARGETS));
+    }
+
+    Some(context)
+}
+
+/// Make targets, skipping special (`.PHONY`), pattern (`%.o`) and variable (`:=`, `::=`) lines
+///
+/// A trailing `## text` on the target line (the common `make help` idiom) or a
+/// `#` comment directly above it becomes the description.
+pub fn parse_makefile(content: &str) -> Vec<Target> {
+    static RULE: OnceLock<Regex> = OnceLock::new();
+    let rule = RULE.get_or_init(|| Regex::new(r"^([A-Za-z0-9][A-Za-z0-9_./ -]*?)\s*::?([^:=]|$)").unwrap());
+
+    let mut targets: Vec<Target> = Vec::new();
+    let mut comment: Option<String> = None;
+
+    for line in content.lines() {
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.trim_start_matches('#').trim().to_string()).filter(|c| !c.is_empty());
+            continue;
+        }
+
+        if let Some(caps) = rule.captures(line) {
+            let description = line
+                .split_once("##")
+                .map(|(_, doc)| doc.trim().to_string())
+                .or_else(|| comment.take());
+
+            for name in caps[1].split_whitespace() {
+                if name.contains('%') || name.contains('$') || targets.iter().any(|t| t.name == name) {
+                    continue;
+                }
+                targets.push(Target {
+                    name: name.to_string(),
+                    description: description.clone(),
+                });
+            }
+        }
+
+        if !line.starts_with('\t') {
+            comment = None;
+        }
+    }
+
+    targets
+}
+
+/// just recipes with their parameters, skipping private (`_name`, `[private]`) recipes
+///
+/// A `#` comment directly above a recipe (or a `[doc("...")]` attribute) becomes the description.
+pub fn parse_justfile(content: &str) -> Vec<Target> {
+    static RECIPE: OnceLock<Regex> = OnceLock::new();
+    static DOC: OnceLock<Regex> = OnceLock::new();
+    let recipe = RECIPE.get_or_init(|| Regex::new(r"^@?([A-Za-z_][A-Za-z0-9_-]*)((?:\s+[^:]+?)?)\s*:([^=]|$)").unwrap());
+    let doc = DOC.get_or_init(|| Regex::new(r#"^\[doc\(\s*["'](.*)["']\s*\)\]"#).unwrap());
+
+    const KEYWORDS: [&str; 6] = ["set", "alias", "export", "import", "mod", "unexport"];
+
+    let mut targets = Vec::new();
+    let mut comment: Option<String> = None;
+    let mut private = false;
+
+    for line in content.lines() {
+        if let Some(text) = line.strip_prefix('#') {
+            if !text.starts_with('!') {
+                comment = Some(text.trim().to_string()).filter(|c| !c.is_empty());
+            }
+            continue;
+        }
+
+        if line.starts_with('[') {
+            if let Some(caps) = doc.captures(line) {
+                comment = Some(caps[1].to_string());
+            }
+            private |= !line.starts_with("[doc") && line.contains("private");
+            continue;
+        }
+
+        if let Some(caps) = recipe.captures(line) {
+            let name = &caps[1];
+            if !KEYWORDS.contains(&name) && !name.starts_with('_') && !private {
+                let params = caps[2].trim();
+                targets.push(Target {
+                    name: if params.is_empty() {
+                        name.to_string()
+                    } else {
+                        format!("{} {}", name, params)
+                    },
+                    description: comment.clone(),
+                });
+            }
+        }
+
+        if !line.starts_with([' ', '\t']) {
+            comment = None;
+            private = false;
+        }
+    }
+
+    targets
+}
+
+/// Task (taskfile.dev) tasks, skipping `internal: true` ones
+pub fn parse_taskfile(content: &str) -> Vec<Target> {
+    let Ok(yaml) = serde_yaml::from_str::<serde_yaml::Value>(content) else {
+        return Vec::new();
+    };
+    let Some(tasks) = yaml.get("tasks").and_then(|t| t.as_mapping()) else {
+        return Vec::new();
+    };
+
+    tasks
+        .iter()
+        .filter(|(_, task)| !task.get("internal").and_then(|i| i.as_bool()).unwrap_or(false))
+        .filter_map(|(name, task)| {
+            Some(Target {
+                name: name.as_str()?.to_string(),
+                description: task
+                    .get("desc")
+                    .or_else(|| task.get("summary"))
+                    .and_then(|d| d.as_str())
+                    .map(|d| d.trim().to_string()),
+            })
+        })
+        .collect()
+}
+
+pub struct MakeProvider;
+
+impl ContextProvider for MakeProvider {
+    fn name(&self) -> &str {
+        "make"
+    }
+
+    fn title(&self) -> &str {
+        "Make Targets"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        word_relevance(query, &TASK_KEYWORDS)
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.project.find_file(&["GNUmakefile", "makefile", "Makefile"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let path = ctx.project.find_file(&["GNUmakefile", "makefile", "Makefile"])?;
+        let targets = parse_makefile(&fs::read_to_string(&path).ok()?);
+        let dir = path.parent()?;
+        let heading = if dir == ctx.cwd {
+            format!("{} (run with make <target>):", path.display())
+        } else {
+            format!("{} (run with make -C {} <target>):", path.display(), dir.display())
+        };
+        format_targets(heading, &targets)
+    }
+}
+
+pub struct JustProvider;
+
+impl ContextProvider for JustProvider {
+    fn name(&self) -> &str {
+        "just"
+    }
+
+    fn title(&self) -> &str {
+        "Just Recipes"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        word_relevance(query, &TASK_KEYWORDS)
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.project.find_file(&["justfile", "Justfile", ".justfile"]).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let path = ctx.project.find_file(&["justfile", "Justfile", ".justfile"])?;
+        let recipes = parse_justfile(&fs::read_to_string(&path).ok()?);
+        format_targets(format!("{} (run with just <recipe> [args]):", path.display()), &recipes)
+    }
+}
+
+pub struct TaskfileProvider;
+
+const TASKFILE_NAMES: [&str; 6] = [
+    "Taskfile.yml",
+    "taskfile.yml",
+    "Taskfile.yaml",
+    "taskfile.yaml",
+    "Taskfile.dist.yml",
+    "taskfile.dist.yml",
+];
+
+impl ContextProvider for TaskfileProvider {
+    fn name(&self) -> &str {
+        "taskfile"
+    }
+
+    fn title(&self) -> &str {
+        "Taskfile Tasks"
+    }
+
+    fn relevance(&self, query: &str) -> f32 {
+        word_relevance(query, &TASK_KEYWORDS)
+    }
+
+    fn detect(&self, ctx: &ProviderContext) -> bool {
+        ctx.project.find_file(&TASKFILE_NAMES).is_some()
+    }
+
+    fn gather(&self, ctx: &ProviderContext) -> Option<String> {
+        let path = ctx.project.find_file(&TASKFILE_NAMES)?;
+        let tasks = parse_taskfile(&fs::read_to_string(&path).ok()?);
+        format_targets(format!("{} (run with task <name>):", path.display()), &tasks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(targets: &[Target]) -> Vec<&str> {
+        targets.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_makefile() {
+        let targets = parse_makefile(
+            "CC := gcc\n\
+             VERSION ?= 1.0\n\
+             PREFIX ::= /usr/local\n\
+             DESTDIR :::= /tmp/stage\n\
+             LDFLAGS:=-static\n\
+             .PHONY: build test\n\
+             \n\
+             # Compile everything\n\
+             build: deps\n\
+             \t$(CC) -o app main.c\n\
+             \n\
+             test: build ## Run the unit tests\n\
+             \t./app --test\n\
+             \n\
+             %.o: %.c\n\
+             \t$(CC) -c $<\n\
+             \n\
+             dev-up dev-down:\n\
+             \tdocker compose $@\n\
+             \n\
+             install:: build\n",
+        );
+
+        assert_eq!(names(&targets), vec!["build", "test", "dev-up", "dev-down", "install"]);
+        assert_eq!(targets[0].description.as_deref(), Some("Compile everything"));
+        assert_eq!(targets[1].description.as_deref(), Some("Run the unit tests"));
+        assert_eq!(targets[2].description, None);
+    }
+
+    #[test]
+    fn test_parse_justfile() {
+        let targets = parse_justfile(
+            "set dotenv-load\n\
+             alias b := build\n\
+             version := \"1.0\"\n\
+             \n\
+             # Build the release binary\n\
+             build:\n\
+             \tcargo build --release\n\
+             \n\
+             [doc('Deploy to an environment')]\n\
+             deploy env='staging': build\n\
+             \t./deploy.sh {{env}}\n\
+             \n\
+             _helper:\n\
+             \techo private\n\
+             \n\
+             [private]\n\
+             hidden:\n\
+             \techo hidden\n\
+             \n\
+             @fmt:\n\
+             \tcargo fmt\n",
+        );
+
+        assert_eq!(names(&targets), vec!["build", "deploy env='staging'", "fmt"]);
+        assert_eq!(targets[0].description.as_deref(), Some("Build the release binary"));
+        assert_eq!(targets[1].description.as_deref(), Some("Deploy to an environment"));
+    }
+
+    #[test]
+    fn test_parse_taskfile() {
+        let targets = parse_taskfile(
+            "version: '3'\n\
+             tasks:\n\
+             \x20 dev:\n\
+             \x20   desc: Start the dev stack\n\
+             \x20   cmds: [docker compose up -d]\n\
+             \x20 lint: golangci-lint run\n\
+             \x20 setup:\n\
+             \x20   internal: true\n\
+             \x20   cmds: [go mod download]\n",
+        );
+
+        assert_eq!(names(&targets), vec!["dev", "lint"]);
+        assert_eq!(targets[0].description.as_deref(), Some("Start the dev stack"));
+        assert!(parse_taskfile("not: [valid").is_empty());
+    }
+
+    #[test]
+    fn test_make_provider_finds_makefile_above_cwd() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("Makefile"), "up: ## Start the dev stack\n\tdocker compose up\n").unwrap();
+
+        let ctx = ProviderContext::new("start the dev stack", root.join("src"), Some(root.to_path_buf()), None);
+        assert!(MakeProvider.relevance(&ctx.query) > 0.0);
+        assert!(MakeProvider.detect(&ctx));
+        assert!(!JustProvider.detect(&ctx));
+
+        let context = MakeProvider.gather(&ctx).unwrap();
+        assert!(context.contains(&format!("make -C {} <target>", root.display())));
+        assert!(context.contains("  up - Start the dev stack"));
+    }
+
+    #[test]
+    fn test_task_keywords_match_whole_words() {
+        assert!(MakeProvider.relevance("run the tests") > 0.0);
+        assert!(JustProvider.relevance("which recipes are there?") > 0.0);
+        assert_eq!(MakeProvider.relevance("truncate the log file"), 0.0);
+        assert_eq!(TaskfileProvider.relevance("show the latest commit"), 0.0);
+        assert_eq!(MakeProvider.relevance("write zeros from /dev/zero"), 0.0);
+    }
+}
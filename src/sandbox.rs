@@ -0,0 +1,404 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::process_util::{find_in_path, output_with_timeout};
+
+/// Maximum time a command may run inside the sandbox
+const SANDBOX_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Isolation mechanism used to run a trial command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SandboxBackend {
+    /// bubblewrap (`bwrap`), works without privileges on most distros
+    Bubblewrap,
+    /// util-linux `unshare` with a user namespace and an overlayfs mount
+    Unshare,
+}
+
+impl SandboxBackend {
+    /// Pick the first available backend (bubblewrap preferred), Linux only
+    pub fn detect() -> Option<Self> {
+        if std::env::consts::OS != "linux" {
+            return None;
+        }
+
+        if find_in_path("bwrap") {
+            Some(Self::Bubblewrap)
+        } else if find_in_path("unshare") {
+            Some(Self::Unshare)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Bubblewrap => "bubblewrap",
+            Self::Unshare => "unshare",
+        }
+    }
+}
+
+/// Observed behavior of a command run in the sandbox
+#[derive(Debug, Clone)]
+pub struct SandboxReport {
+    pub backend: SandboxBackend,
+    /// Exit code, or `None` if the command was killed (e.g. timed out)
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// Paths relative to the working directory
+    pub created: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+}
+
+impl SandboxReport {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// Render the report for the terminal
+    pub fn format(&self) -> String {
+        let mut report = match (self.exit_code, self.timed_out) {
+            (_, true) => format!(
+                "sandbox ({}): timed out after {}s",
+                self.backend.as_str(),
+                SANDBOX_TIMEOUT.as_secs()
+            ),
+            (Some(code), _) => format!("sandbox ({}): exit code {}", self.backend.as_str(), code),
+            (None, _) => format!("sandbox ({}): killed by signal", self.backend.as_str()),
+        };
+
+        for (label, paths) in [
+            ("created", &self.created),
+            ("modified", &self.modified),
+            ("deleted", &self.deleted),
+        ] {
+            for path in paths {
+                report.push_str(&format!("\n  {}: {}", label, path.display()));
+            }
+        }
+
+        if self.created.is_empty() && self.modified.is_empty() && self.deleted.is_empty() {
+            report.push_str("\n  no files changed");
+        }
+
+        for (label, text) in [("stdout", &self.stdout), ("stderr", &self.stderr)] {
+            let lines: Vec<&str> = text.lines().collect();
+            if lines.is_empty() {
+                continue;
+            }
+            report.push_str(&format!("\n  {}:", label));
+            for line in lines.iter().take(20) {
+                report.push_str(&format!("\n    {}", line));
+            }
+            if lines.len() > 20 {
+                report.push_str(&format!("\n    ... ({} more lines)", lines.len() - 20));
+            }
+        }
+
+        report
+    }
+}
+
+/// Run `command` in a throwaway sandbox rooted at `cwd`
+///
+/// The sandbox has no network, read-only mounts everywhere, a private `/tmp` and an
+/// overlayfs over `cwd`, so writes land in a scratch directory that is
+/// inspected and then discarded. The real filesystem is never modified.
+pub fn run_in_sandbox(command: &str, cwd: &Path) -> Result<SandboxReport> {
+    let backend = SandboxBackend::detect()
+        .ok_or_else(|| anyhow!("No sandbox available: install bubblewrap (bwrap) or util-linux unshare (Linux only)"))?;
+
+    let cwd = cwd.canonicalize().context("Failed to resolve working directory")?;
+    let scratch = ScratchDir::create()?;
+    let upper = scratch.path.join("upper");
+    let work = scratch.path.join("work");
+    fs::create_dir_all(&upper).context("Failed to create sandbox upper directory")?;
+    fs::create_dir_all(&work).context("Failed to create sandbox work directory")?;
+
+    // A private /tmp would hide a working directory that lives under /tmp
+    let private_tmp = !cwd.starts_with("/tmp");
+
+    let mut sandbox = match backend {
+        SandboxBackend::Bubblewrap => bubblewrap_command(command, &cwd, &upper, &work, private_tmp),
+        SandboxBackend::Unshare => unshare_command(command, &cwd, &upper, &work, private_tmp),
+    };
+
+    let output = output_with_timeout(&mut sandbox, SANDBOX_TIMEOUT)
+        .with_context(|| format!("Failed to start {} sandbox", backend.as_str()))?;
+
+    let (exit_code, timed_out, stdout, stderr) = match output {
+        Some(output) => (
+            output.status.code(),
+            false,
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ),
+        None => (None, true, String::new(), String::new()),
+    };
+
+    let mut changes = Changes::default();
+    collect_changes(&upper, &cwd, Path::new(""), &mut changes)?;
+
+    Ok(SandboxReport {
+        backend,
+        exit_code,
+        timed_out,
+        stdout,
+        stderr,
+        created: changes.created,
+        modified: changes.modified,
+        deleted: changes.deleted,
+    })
+}
+
+fn bubblewrap_command(command: &str, cwd: &Path, upper: &Path, work: &Path, private_tmp: bool) -> Command {
+    let mut bwrap = Command::new("bwrap");
+    bwrap.args(["--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc"]);
+    if private_tmp {
+        bwrap.args(["--tmpfs", "/tmp"]);
+    }
+    bwrap
+        .arg("--overlay-src")
+        .arg(cwd)
+        .arg("--overlay")
+        .arg(upper)
+        .arg(work)
+        .arg(cwd)
+        .args(["--unshare-all", "--die-with-parent", "--chdir"])
+        .arg(cwd)
+        .args(["sh", "-c", command]);
+    bwrap
+}
+
+/// Inner script for the `unshare` backend; paths and the command are passed
+/// as positional arguments so nothing needs shell quoting
+///
+/// After the overlay is mounted, every other mount (a separate `/home`, `/var`,
+/// `/dev/shm`, ...) is remounted read-only, keeping the flags a user namespace
+/// may not clear. If any of them can't be, the command is not run.
+const UNSHARE_SCRIPT: &str = r#"set -e
+mount --make-rprivate /
+mount -t overlay overlay -o "lowerdir=$1,upperdir=$2,workdir=$3" "$1"
+while read -r _ target _ options _; do
+  target=$(printf '%b' "$target")
+  case "$target" in "$1"|"$1"/*) continue ;; esac
+  flags=$(printf '%s' "$options" | tr ',' '\n' | grep -xE 'nosuid|nodev|noexec|relatime|noatime|nodiratime|strictatime' | tr '\n' ',')
+  mount -o "remount,bind,ro,${flags%,}" "$target" || { echo "ez-sandbox: cannot make $target read-only" >&2; exit 125; }
+done < /proc/self/mounts
+if [ "$4" = "1" ]; then mount -t tmpfs tmpfs /tmp; fi
+cd "$1"
+set +e
+exec sh -c "$5""#;
+
+fn unshare_command(command: &str, cwd: &Path, upper: &Path, work: &Path, private_tmp: bool) -> Command {
+    let mut unshare = Command::new("unshare");
+    unshare
+        .args(["--user", "--map-root-user", "--mount", "--net", "--pid", "--fork"])
+        .args(["sh", "-c", UNSHARE_SCRIPT, "ez-sandbox"])
+        .arg(cwd)
+        .arg(upper)
+        .arg(work)
+        .arg(if private_tmp { "1" } else { "0" })
+        .arg(command);
+    unshare
+}
+
+#[derive(Debug, Default)]
+struct Changes {
+    created: Vec<PathBuf>,
+    modified: Vec<PathBuf>,
+    deleted: Vec<PathBuf>,
+}
+
+/// Walk the overlay upper directory and classify every entry against the real `lower` tree
+fn collect_changes(upper: &Path, lower: &Path, relative: &Path, changes: &mut Changes) -> Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(upper.join(relative))
+        .with_context(|| format!("Failed to read sandbox changes in {:?}", relative))?
+        .filter_map(|e| e.ok())
+        .collect();
+    entries.sort_by_key(|e| e.file_name());
+
+    for entry in entries {
+        let rel = relative.join(entry.file_name());
+        let metadata = entry.path().symlink_metadata()?;
+        let existed = lower.join(&rel).symlink_metadata().is_ok();
+
+        if is_whiteout(&metadata) {
+            changes.deleted.push(rel);
+        } else if metadata.is_dir() {
+            if !existed {
+                changes.created.push(rel.clone());
+            }
+            collect_changes(upper, lower, &rel, changes)?;
+        } else if existed {
+            changes.modified.push(rel);
+        } else {
+            changes.created.push(rel);
+        }
+    }
+
+    Ok(())
+}
+
+/// overlayfs records deletions as 0:0 character devices in the upper directory
+#[cfg(unix)]
+fn is_whiteout(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+    metadata.file_type().is_char_device() && metadata.rdev() == 0
+}
+
+#[cfg(not(unix))]
+fn is_whiteout(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Scratch directory for overlay upper/work dirs, removed on drop
+struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    fn create() -> Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let path = std::env::temp_dir().join(format!("ez-sandbox-{}-{}", std::process::id(), nanos));
+        fs::create_dir_all(&path).context("Failed to create sandbox scratch directory")?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_collect_changes_classifies_entries() {
+        let lower = TempDir::new().unwrap();
+        let upper = TempDir::new().unwrap();
+
+        fs::write(lower.path().join("existing.txt"), "old").unwrap();
+        fs::create_dir(lower.path().join("src")).unwrap();
+
+        fs::write(upper.path().join("existing.txt"), "new").unwrap();
+        fs::write(upper.path().join("new.txt"), "").unwrap();
+        fs::create_dir_all(upper.path().join("src")).unwrap();
+        fs::write(upper.path().join("src/lib.rs"), "").unwrap();
+        fs::create_dir_all(upper.path().join("out/bin")).unwrap();
+
+        let mut changes = Changes::default();
+        collect_changes(upper.path(), lower.path(), Path::new(""), &mut changes).unwrap();
+
+        assert_eq!(changes.modified, vec![PathBuf::from("existing.txt")]);
+        assert_eq!(
+            changes.created,
+            vec![
+                PathBuf::from("new.txt"),
+                PathBuf::from("out"),
+                PathBuf::from("out/bin"),
+                PathBuf::from("src/lib.rs"),
+            ]
+        );
+        assert!(changes.deleted.is_empty());
+    }
+
+    #[test]
+    fn test_report_format() {
+        let report = SandboxReport {
+            backend: SandboxBackend::Bubblewrap,
+            exit_code: Some(1),
+            timed_out: false,
+            stdout: "hello\n".to_string(),
+            stderr: String::new(),
+            created: vec![PathBuf::from("a.txt")],
+            modified: vec![],
+            deleted: vec![PathBuf::from("b.txt")],
+        };
+
+        let formatted = report.format();
+        assert!(formatted.starts_with("sandbox (bubblewrap): exit code 1"));
+        assert!(formatted.contains("created: a.txt"));
+        assert!(formatted.contains("deleted: b.txt"));
+        assert!(formatted.contains("stdout:\n    hello"));
+        assert!(!formatted.contains("stderr:"));
+    }
+
+    /// Namespaces may be disabled (e.g. restricted user namespaces on CI runners)
+    fn sandbox_works() -> bool {
+        let probe = TempDir::new().unwrap();
+        match run_in_sandbox("true", probe.path()) {
+            Ok(report) if report.succeeded() => true,
+            _ => {
+                println!("Skipping sandbox test - sandboxing is unavailable on this machine");
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_in_sandbox_leaves_cwd_untouched() {
+        if !sandbox_works() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("old.txt"), "old").unwrap();
+        fs::write(temp_dir.path().join("keep.txt"), "keep").unwrap();
+
+        let report = run_in_sandbox(
+            "echo hi > new.txt && rm old.txt && echo more >> keep.txt && echo done",
+            temp_dir.path(),
+        )
+        .unwrap();
+
+        assert!(report.succeeded(), "{}", report.format());
+        assert_eq!(report.stdout.trim(), "done");
+        assert_eq!(report.created, vec![PathBuf::from("new.txt")]);
+        assert_eq!(report.modified, vec![PathBuf::from("keep.txt")]);
+        assert_eq!(report.deleted, vec![PathBuf::from("old.txt")]);
+
+        // Nothing leaked to the real directory
+        assert!(temp_dir.path().join("old.txt").exists());
+        assert!(!temp_dir.path().join("new.txt").exists());
+        assert_eq!(fs::read_to_string(temp_dir.path().join("keep.txt")).unwrap(), "keep");
+    }
+
+    #[test]
+    fn test_sandbox_has_no_network_and_readonly_root() {
+        if !sandbox_works() {
+            return;
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let report = run_in_sandbox("touch /etc/ez-sandbox-probe", temp_dir.path()).unwrap();
+        assert!(!report.succeeded());
+        assert!(!Path::new("/etc/ez-sandbox-probe").exists());
+
+        // Separate mounts such as /dev/shm are read-only too, not just /
+        if Path::new("/dev/shm").is_dir() {
+            let probe = format!("/dev/shm/ez-sandbox-probe-{}", std::process::id());
+            let report = run_in_sandbox(&format!("touch {}", probe), temp_dir.path()).unwrap();
+            assert!(!report.succeeded(), "{}", report.format());
+            assert!(!Path::new(&probe).exists());
+        }
+
+        // Only the loopback device exists in a fresh network namespace
+        let report = run_in_sandbox("tail -n +3 /proc/net/dev | grep -v 'lo:'", temp_dir.path()).unwrap();
+        assert!(!report.succeeded(), "unexpected network interfaces: {}", report.format());
+    }
+}
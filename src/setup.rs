@@ -268,6 +268,8 @@ impl SetupWizard {
             },
             migration_completed: None,
             migration_declined: None,
+            // Keep settings the wizard doesn't ask about
+            ..Config::load_user().unwrap_or_default()
         };
 
         config.save()
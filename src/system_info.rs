@@ -1,11 +1,122 @@
+use std::fs;
+use std::path::Path;
 use sysinfo::System;
 
+use crate::core_utils::{self, CoreUtil};
+use crate::process_util::find_in_path;
+
+/// Package managers worth telling the model about, most "native" first
+const PACKAGE_MANAGERS: [&str; 10] = [
+    "apt", "dnf", "yum", "pacman", "zypper", "apk", "nix", "brew", "flatpak", "snap",
+];
+
+#[derive(Debug, Clone, Default)]
 pub struct SystemInfo {
     pub os: String,
     pub arch: String,
     pub shell: Option<String>,
     pub total_memory: u64,
     pub available_memory: u64,
+    /// Parsed `/etc/os-release`, Linux only
+    pub os_release: Option<OsRelease>,
+    /// Package managers found in `PATH`, in `PACKAGE_MANAGERS` order
+    pub package_managers: Vec<String>,
+    /// `systemd`, `openrc`, `runit` or `launchd`
+    pub init_system: Option<String>,
+    /// Container runtime we are running inside, if any
+    pub container: Option<String>,
+    pub wsl: bool,
+    /// `sudo` or `doas`, whichever is installed
+    pub privilege_command: Option<String>,
+    /// GNU/BSD/BusyBox flavor of sed, find, date, grep and awk
+    pub core_utils: Vec<CoreUtil>,
+}
+
+/// The interesting fields of `/etc/os-release`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OsRelease {
+    pub id: String,
+    pub version_id: Option<String>,
+    pub id_like: Vec<String>,
+    pub pretty_name: Option<String>,
+}
+
+impl OsRelease {
+    pub fn parse(content: &str) -> Self {
+        let mut release = OsRelease::default();
+
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+            match key.trim() {
+                "ID" => release.id = value,
+                "VERSION_ID" => release.version_id = Some(value),
+                "ID_LIKE" => release.id_like = value.split_whitespace().map(String::from).collect(),
+                "PRETTY_NAME" => release.pretty_name = Some(value),
+                _ => {}
+            }
+        }
+
+        release
+    }
+
+    /// `Ubuntu 24.04 LTS (ubuntu, like debian)`
+    pub fn describe(&self) -> String {
+        let name = self.pretty_name.clone().unwrap_or_else(|| {
+            format!("{} {}", self.id, self.version_id.as_deref().unwrap_or_default())
+                .trim()
+                .to_string()
+        });
+        if self.id_like.is_empty() {
+            format!("{} ({})", name, self.id)
+        } else {
+            format!("{} ({}, like {})", name, self.id, self.id_like.join(" "))
+        }
+    }
+}
+
+/// Init system, judged by the runtime directories each one creates under `root`
+pub fn detect_init_system(root: &Path) -> Option<String> {
+    let init = if root.join("run/systemd/system").is_dir() {
+        "systemd"
+    } else if root.join("run/openrc").is_dir() || root.join("sbin/openrc-run").exists() {
+        "openrc"
+    } else if root.join("run/runit").is_dir() || root.join("etc/runit/runsvdir").is_dir() {
+        "runit"
+    } else if root.join("sbin/launchd").exists() {
+        "launchd"
+    } else {
+        return None;
+    };
+    Some(init.to_string())
+}
+
+/// Container runtime, from the marker files runtimes drop and PID 1's cgroup
+pub fn detect_container(root: &Path, container_env: Option<&str>) -> Option<String> {
+    if root.join(".dockerenv").exists() {
+        return Some("docker".to_string());
+    }
+    if root.join("run/.containerenv").exists() {
+        return Some("podman".to_string());
+    }
+    if let Some(runtime) = container_env.filter(|r| !r.is_empty()) {
+        return Some(runtime.to_string());
+    }
+
+    let cgroup = fs::read_to_string(root.join("proc/1/cgroup")).unwrap_or_default();
+    ["kubepods", "docker", "lxc", "containerd"]
+        .into_iter()
+        .find(|marker| cgroup.contains(marker))
+        .map(|marker| if marker == "kubepods" { "kubernetes" } else { marker }.to_string())
+}
+
+/// WSL kernels say so in their release string
+pub fn detect_wsl(root: &Path) -> bool {
+    fs::read_to_string(root.join("proc/sys/kernel/osrelease"))
+        .map(|release| release.to_lowercase().contains("microsoft"))
+        .unwrap_or(false)
 }
 
 impl SystemInfo {
@@ -18,15 +129,49 @@ impl SystemInfo {
         let shell = std::env::var("SHELL").ok()
             .and_then(|s| s.split('/').last().map(|s| s.to_string()));
 
+        let root = Path::new("/");
+        let os_release = if os == "linux" {
+            fs::read_to_string("/etc/os-release")
+                .or_else(|_| fs::read_to_string("/usr/lib/os-release"))
+                .ok()
+                .map(|content| OsRelease::parse(&content))
+        } else {
+            None
+        };
+
+        let package_managers = PACKAGE_MANAGERS
+            .iter()
+            .filter(|pm| find_in_path(pm))
+            .map(|pm| pm.to_string())
+            .collect();
+
         Self {
+            total_memory: sys.total_memory(),
+            available_memory: sys.available_memory(),
+            os_release,
+            package_managers,
+            init_system: detect_init_system(root),
+            container: detect_container(root, std::env::var("container").ok().as_deref()),
+            wsl: detect_wsl(root) || std::env::var_os("WSL_DISTRO_NAME").is_some(),
+            privilege_command: ["sudo", "doas"].into_iter().find(|c| find_in_path(c)).map(String::from),
+            core_utils: core_utils::detect(&os),
             os,
             arch,
             shell,
-            total_memory: sys.total_memory(),
-            available_memory: sys.available_memory(),
         }
     }
 
+    /// OS and shell only, for the minimal privacy level
+    pub fn format_minimal_context(&self) -> String {
+        let mut context = format!("Operating System: {} ({})", self.os, self.arch);
+
+        if let Some(shell) = &self.shell {
+            context.push_str(&format!("\nShell: {}", shell));
+        }
+
+        context
+    }
+
     pub fn format_context(&self) -> String {
         let mut context = format!(
             "Operating System: {} ({})",
@@ -43,17 +188,72 @@ impl SystemInfo {
             self.available_memory as f64 / 1_073_741_824.0
         ));
 
+        if let Some(release) = &self.os_release {
+            context.push_str(&format!("\nDistribution: {}", release.describe()));
+        }
+        if !self.package_managers.is_empty() {
+            context.push_str(&format!("\nPackage managers: {}", self.package_managers.join(", ")));
+        }
+        if let Some(init) = &self.init_system {
+            context.push_str(&format!("\nInit system: {}", init));
+        }
+        if let Some(container) = &self.container {
+            context.push_str(&format!(
+                "\nRunning inside a {} container (services and hardware may not be available)",
+                container
+            ));
+        }
+        if self.wsl {
+            context.push_str("\nRunning under WSL (Windows drives are under /mnt/c, Windows tools via .exe)");
+        }
+        match &self.privilege_command {
+            Some(command) => context.push_str(&format!("\nPrivilege escalation: {}", command)),
+            None if self.os != "windows" && !self.package_managers.is_empty() => {
+                context.push_str("\nPrivilege escalation: none found (no sudo or doas)")
+            }
+            None => {}
+        }
+
+        if !self.core_utils.is_empty() {
+            let utils: Vec<String> = self.core_utils.iter().map(CoreUtil::describe).collect();
+            context.push_str(&format!("\nCore utilities: {}", utils.join(", ")));
+            for hint in self.core_utils.iter().filter_map(CoreUtil::hint) {
+                context.push_str(&format!("\n  {}", hint));
+            }
+        }
+
         // Add OS-specific command guidance
         let os_guidance = match self.os.as_str() {
-            "linux" => "\nOS Commands: Use Linux/GNU commands (apt/dnf for packages, systemctl for services, /proc for system info)",
-            "macos" => "\nOS Commands: Use macOS/BSD commands (brew for packages, launchctl for services, prefer BSD-style flags)",
-            "windows" => "\nOS Commands: Use Windows commands (PowerShell preferred, or cmd.exe built-ins)",
-            _ => "",
+            "linux" => format!(
+                "\nOS Commands: Use Linux/GNU commands ({} for packages, {} for services, /proc for system info)",
+                self.system_package_manager().unwrap_or("apt/dnf"),
+                self.service_command()
+            ),
+            "macos" => "\nOS Commands: Use macOS/BSD commands (brew for packages, launchctl for services, prefer BSD-style flags)".to_string(),
+            "windows" => "\nOS Commands: Use Windows commands (PowerShell preferred, or cmd.exe built-ins)".to_string(),
+            _ => String::new(),
         };
-        context.push_str(os_guidance);
+        context.push_str(&os_guidance);
 
         context
     }
+
+    /// The distribution's own package manager, if one was found
+    pub fn system_package_manager(&self) -> Option<&str> {
+        self.package_managers
+            .iter()
+            .map(String::as_str)
+            .find(|pm| !matches!(*pm, "nix" | "brew" | "flatpak" | "snap"))
+    }
+
+    /// How services are managed under the detected init system
+    fn service_command(&self) -> &'static str {
+        match self.init_system.as_deref() {
+            Some("openrc") => "rc-service/rc-update",
+            Some("runit") => "sv",
+            _ => "systemctl",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -91,6 +291,7 @@ mod tests {
             shell: Some("zsh".to_string()),
             total_memory: 16_000_000_000,
             available_memory: 8_000_000_000,
+            ..Default::default()
         };
 
         let context = info.format_context();
@@ -102,6 +303,22 @@ mod tests {
         assert!(context.contains("OS Commands: Use Linux/GNU commands"));
     }
 
+    #[test]
+    fn test_format_minimal_context() {
+        let info = SystemInfo {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            shell: Some("bash".to_string()),
+            total_memory: 16_000_000_000,
+            available_memory: 8_000_000_000,
+            ..Default::default()
+        };
+
+        let context = info.format_minimal_context();
+
+        assert_eq!(context, "Operating System: linux (x86_64)\nShell: bash");
+    }
+
     #[test]
     fn test_format_context_no_shell() {
         let info = SystemInfo {
@@ -110,6 +327,7 @@ mod tests {
             shell: None,
             total_memory: 16_000_000_000,
             available_memory: 12_000_000_000,
+            ..Default::default()
         };
 
         let context = info.format_context();
@@ -129,6 +347,7 @@ mod tests {
             shell: Some("powershell".to_string()),
             total_memory: 32_000_000_000,
             available_memory: 16_000_000_000,
+            ..Default::default()
         };
 
         let context = info.format_context();
@@ -139,4 +358,85 @@ mod tests {
         assert!(context.contains("Memory"));
         assert!(context.contains("OS Commands: Use Windows commands"));
     }
+
+    #[test]
+    fn test_parse_os_release() {
+        let release = OsRelease::parse(
+            "NAME=\"Ubuntu\"\nVERSION_ID=\"24.04\"\nID=ubuntu\nID_LIKE=debian\nPRETTY_NAME=\"Ubuntu 24.04.1 LTS\"\n",
+        );
+
+        assert_eq!(release.id, "ubuntu");
+        assert_eq!(release.version_id.as_deref(), Some("24.04"));
+        assert_eq!(release.id_like, vec!["debian"]);
+        assert_eq!(release.describe(), "Ubuntu 24.04.1 LTS (ubuntu, like debian)");
+
+        let release = OsRelease::parse("ID=alpine\nVERSION_ID=3.20.0\n");
+        assert_eq!(release.describe(), "alpine 3.20.0 (alpine)");
+    }
+
+    #[test]
+    fn test_detect_init_container_and_wsl() {
+        let root = tempfile::TempDir::new().unwrap();
+        assert_eq!(detect_init_system(root.path()), None);
+        assert_eq!(detect_container(root.path(), None), None);
+        assert!(!detect_wsl(root.path()));
+
+        std::fs::create_dir_all(root.path().join("run/openrc")).unwrap();
+        std::fs::create_dir_all(root.path().join("proc/1")).unwrap();
+        std::fs::create_dir_all(root.path().join("proc/sys/kernel")).unwrap();
+        std::fs::write(root.path().join("proc/1/cgroup"), "0::/kubepods/besteffort/pod1234\n").unwrap();
+        std::fs::write(
+            root.path().join("proc/sys/kernel/osrelease"),
+            "5.15.153.1-microsoft-standard-WSL2\n",
+        )
+        .unwrap();
+
+        assert_eq!(detect_init_system(root.path()).as_deref(), Some("openrc"));
+        assert_eq!(detect_container(root.path(), None).as_deref(), Some("kubernetes"));
+        assert_eq!(detect_container(root.path(), Some("podman")).as_deref(), Some("podman"));
+        assert!(detect_wsl(root.path()));
+
+        std::fs::write(root.path().join(".dockerenv"), "").unwrap();
+        assert_eq!(detect_container(root.path(), None).as_deref(), Some("docker"));
+    }
+
+    #[test]
+    fn test_format_context_linux_details() {
+        let info = SystemInfo {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            os_release: Some(OsRelease::parse("ID=alpine\nVERSION_ID=3.20.0\n")),
+            package_managers: vec!["apk".to_string(), "nix".to_string()],
+            init_system: Some("openrc".to_string()),
+            container: Some("docker".to_string()),
+            privilege_command: Some("doas".to_string()),
+            core_utils: vec![
+                CoreUtil {
+                    package: "sed".to_string(),
+                    binary: "sed".to_string(),
+                    flavor: core_utils::Flavor::BusyBox,
+                    version: None,
+                },
+                CoreUtil {
+                    package: "findutils".to_string(),
+                    binary: "find".to_string(),
+                    flavor: core_utils::Flavor::BusyBox,
+                    version: None,
+                },
+            ],
+            ..Default::default()
+        };
+
+        let context = info.format_context();
+
+        assert!(context.contains("Distribution: alpine 3.20.0 (alpine)"));
+        assert!(context.contains("Package managers: apk, nix"));
+        assert!(context.contains("Init system: openrc"));
+        assert!(context.contains("inside a docker container"));
+        assert!(context.contains("Privilege escalation: doas"));
+        assert!(context.contains("(apk for packages, rc-service/rc-update for services"));
+        assert!(context.contains("Core utilities: sed is BusyBox, find is BusyBox"));
+        assert!(context.contains("BusyBox find: no -printf"));
+        assert!(!context.contains("WSL"));
+    }
 }
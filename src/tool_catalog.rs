@@ -0,0 +1,281 @@
+//! Built-in catalogue of command-line tools worth telling the model about
+//!
+//! Each entry names the binary looked up in `$PATH`, a category, the classic
+//! tool it is a faster or friendlier replacement for, and query words that make
+//! it relevant. Users can add their own entries under `[[tools]]` in the config.
+
+/// A catalogue entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CatalogEntry {
+    pub name: &'static str,
+    pub category: &'static str,
+    /// Classic tool this one replaces, e.g. `grep` for `rg`
+    pub replaces: Option<&'static str>,
+    pub keywords: &'static [&'static str],
+}
+
+const fn tool(name: &'static str, category: &'static str, keywords: &'static [&'static str]) -> CatalogEntry {
+    CatalogEntry {
+        name,
+        category,
+        replaces: None,
+        keywords,
+    }
+}
+
+const fn alt(
+    name: &'static str,
+    category: &'static str,
+    replaces: &'static str,
+    keywords: &'static [&'static str],
+) -> CatalogEntry {
+    CatalogEntry {
+        name,
+        category,
+        replaces: Some(replaces),
+        keywords,
+    }
+}
+
+pub const CATALOG: &[CatalogEntry] = &[
+    // Search and files
+    alt("rg", "search", "grep", &["search", "grep", "text", "pattern", "contain", "occurrence"]),
+    alt("ag", "search", "grep", &["search", "grep", "pattern"]),
+    alt("fd", "files", "find", &["find", "file", "files", "directory", "extension", "name"]),
+    alt("fdfind", "files", "find", &["find", "file", "files", "directory", "extension"]),
+    tool("fzf", "files", &["fuzzy", "pick", "select", "interactive", "choose"]),
+    alt("bat", "files", "cat", &["view", "show", "highlight", "print", "cat"]),
+    alt("batcat", "files", "cat", &["view", "show", "highlight", "cat"]),
+    alt("eza", "files", "ls", &["list", "tree", "ls", "directory"]),
+    alt("exa", "files", "ls", &["list", "tree", "ls"]),
+    alt("lsd", "files", "ls", &["list", "ls"]),
+    tool("tree", "files", &["tree", "structure", "directory"]),
+    alt("dust", "files", "du", &["disk", "usage", "size", "space", "largest"]),
+    alt("duf", "files", "df", &["disk", "free", "space", "mount"]),
+    tool("ncdu", "files", &["disk", "usage", "size", "space"]),
+    alt("zoxide", "files", "cd", &["jump", "directory", "cd"]),
+    alt("sd", "text", "sed", &["replace", "substitute", "sed", "rename"]),
+    tool("rsync", "files", &["sync", "copy", "backup", "mirror", "transfer"]),
+    tool("rclone", "files", &["sync", "cloud", "s3", "drive", "remote", "copy"]),
+    tool("entr", "files", &["watch", "rerun", "change"]),
+    tool("watchexec", "files", &["watch", "rerun", "change"]),
+    tool("inotifywait", "files", &["watch", "change", "event"]),
+    alt("trash", "files", "rm", &["delete", "remove", "trash"]),
+    tool("file", "files", &["type", "mime", "binary"]),
+    tool("parallel", "shell", &["parallel", "concurrent", "jobs", "batch", "each"]),
+    tool("xargs", "shell", &["each", "batch", "arguments"]),
+    // Structured data
+    tool("jq", "data", &["json", "parse", "field", "filter", "api"]),
+    tool("yq", "data", &["yaml", "yml", "toml", "xml", "field"]),
+    tool("gojq", "data", &["json"]),
+    tool("jless", "data", &["json", "view"]),
+    tool("fx", "data", &["json", "view"]),
+    tool("dasel", "data", &["json", "yaml", "toml", "xml", "query"]),
+    tool("mlr", "data", &["csv", "tsv", "table", "columns"]),
+    tool("xsv", "data", &["csv", "columns", "table"]),
+    tool("qsv", "data", &["csv", "columns", "table"]),
+    tool("csvlook", "data", &["csv", "table"]),
+    tool("xmllint", "data", &["xml", "xpath", "html"]),
+    tool("xmlstarlet", "data", &["xml", "xpath"]),
+    tool("htmlq", "data", &["html", "scrape", "selector"]),
+    tool("pup", "data", &["html", "scrape", "selector"]),
+    tool("gron", "data", &["json", "grep", "flatten"]),
+    tool("sqlite3", "data", &["sqlite", "database", "sql", "db"]),
+    tool("duckdb", "data", &["sql", "parquet", "csv", "query", "analytics"]),
+    // HTTP and network
+    tool("curl", "network", &["http", "download", "request", "api", "url", "fetch", "post"]),
+    tool("wget", "network", &["download", "url", "mirror", "fetch"]),
+    alt("http", "network", "curl", &["http", "request", "api", "json", "post"]),
+    alt("https", "network", "curl", &["http", "request", "api"]),
+    alt("xh", "network", "curl", &["http", "request", "api", "json", "post"]),
+    tool("grpcurl", "network", &["grpc", "protobuf", "rpc"]),
+    tool("websocat", "network", &["websocket", "ws"]),
+    tool("aria2c", "network", &["download", "torrent", "parallel"]),
+    alt("dog", "network", "dig", &["dns", "lookup", "resolve", "record"]),
+    tool("dig", "network", &["dns", "lookup", "resolve", "record"]),
+    tool("nslookup", "network", &["dns", "lookup", "resolve"]),
+    tool("nmap", "network", &["scan", "port", "ports", "host"]),
+    tool("nc", "network", &["port", "socket", "listen", "tcp", "udp"]),
+    tool("socat", "network", &["socket", "forward", "tcp", "proxy"]),
+    alt("ss", "network", "netstat", &["port", "listening", "socket", "connection"]),
+    tool("netstat", "network", &["port", "listening", "connection"]),
+    tool("lsof", "network", &["port", "open", "file", "listening", "process"]),
+    tool("ip", "network", &["ip", "address", "interface", "route"]),
+    tool("mtr", "network", &["traceroute", "latency", "route", "ping"]),
+    tool("iperf3", "network", &["bandwidth", "throughput", "speed"]),
+    tool("tcpdump", "network", &["capture", "packet", "traffic", "sniff"]),
+    tool("ssh", "network", &["ssh", "remote", "server", "tunnel"]),
+    tool("mosh", "network", &["ssh", "remote"]),
+    tool("tailscale", "network", &["vpn", "tailnet", "tailscale"]),
+    tool("openssl", "security", &["certificate", "cert", "tls", "ssl", "hash", "encrypt"]),
+    tool("gpg", "security", &["encrypt", "decrypt", "sign", "key", "gpg"]),
+    tool("age", "security", &["encrypt", "decrypt"]),
+    tool("sops", "security", &["secret", "encrypt", "sops"]),
+    tool("pass", "security", &["password", "secret"]),
+    tool("op", "security", &["1password", "password", "secret"]),
+    tool("bw", "security", &["bitwarden", "password", "secret"]),
+    tool("vault", "security", &["vault", "secret"]),
+    tool("ssh-keygen", "security", &["key", "ssh", "generate"]),
+    tool("trivy", "security", &["scan", "vulnerability", "cve", "image"]),
+    // Version control
+    tool("git", "vcs", &["git", "commit", "branch", "merge", "repo", "diff", "log", "clone"]),
+    tool("gh", "vcs", &["github", "pr", "pull", "issue", "release", "workflow", "actions"]),
+    tool("glab", "vcs", &["gitlab", "mr", "merge", "issue", "pipeline"]),
+    tool("tea", "vcs", &["gitea", "pr", "issue"]),
+    tool("lazygit", "vcs", &["git", "interactive"]),
+    tool("tig", "vcs", &["git", "log", "history"]),
+    alt("delta", "vcs", "diff", &["diff", "compare"]),
+    alt("difft", "vcs", "diff", &["diff", "compare", "structural"]),
+    tool("git-lfs", "vcs", &["lfs", "large"]),
+    tool("pre-commit", "vcs", &["hook", "hooks", "lint"]),
+    // Containers and orchestration
+    tool("docker", "containers", &["docker", "container", "image", "compose"]),
+    tool("docker-compose", "containers", &["compose", "docker", "stack"]),
+    tool("podman", "containers", &["podman", "container", "image"]),
+    tool("nerdctl", "containers", &["container", "containerd"]),
+    tool("buildah", "containers", &["image", "build"]),
+    tool("skopeo", "containers", &["image", "registry", "copy", "inspect"]),
+    tool("dive", "containers", &["image", "layers", "size"]),
+    tool("lazydocker", "containers", &["docker", "container"]),
+    tool("kubectl", "kubernetes", &["kubernetes", "k8s", "pod", "deployment", "service", "cluster"]),
+    tool("k9s", "kubernetes", &["kubernetes", "k8s", "pod"]),
+    tool("helm", "kubernetes", &["helm", "chart", "release"]),
+    tool("kustomize", "kubernetes", &["kustomize", "overlay"]),
+    tool("kubectx", "kubernetes", &["context", "cluster", "switch"]),
+    tool("kubens", "kubernetes", &["namespace", "switch"]),
+    tool("stern", "kubernetes", &["logs", "tail", "pod"]),
+    tool("kind", "kubernetes", &["cluster", "local"]),
+    tool("minikube", "kubernetes", &["cluster", "local"]),
+    tool("k3d", "kubernetes", &["cluster", "local"]),
+    tool("argocd", "kubernetes", &["argocd", "gitops", "sync"]),
+    tool("flux", "kubernetes", &["flux", "gitops", "reconcile"]),
+    // Cloud and infrastructure
+    tool("aws", "cloud", &["aws", "s3", "ec2", "lambda", "iam"]),
+    tool("gcloud", "cloud", &["gcloud", "gcp", "google"]),
+    tool("gsutil", "cloud", &["gcs", "bucket", "google"]),
+    tool("az", "cloud", &["azure", "az"]),
+    tool("doctl", "cloud", &["digitalocean", "droplet"]),
+    tool("flyctl", "cloud", &["fly", "deploy"]),
+    tool("vercel", "cloud", &["vercel", "deploy"]),
+    tool("netlify", "cloud", &["netlify", "deploy"]),
+    tool("wrangler", "cloud", &["cloudflare", "worker", "workers"]),
+    tool("terraform", "infrastructure", &["terraform", "plan", "apply", "infrastructure"]),
+    tool("tofu", "infrastructure", &["terraform", "opentofu", "plan", "apply"]),
+    tool("pulumi", "infrastructure", &["pulumi", "stack", "infrastructure"]),
+    tool("ansible", "infrastructure", &["ansible", "playbook", "inventory"]),
+    tool("ansible-playbook", "infrastructure", &["ansible", "playbook"]),
+    tool("packer", "infrastructure", &["packer", "image", "ami"]),
+    tool("vagrant", "infrastructure", &["vagrant", "vm"]),
+    // Languages and build tools
+    tool("node", "languages", &["node", "javascript", "js"]),
+    tool("npm", "languages", &["npm", "package", "node"]),
+    tool("pnpm", "languages", &["pnpm", "package", "node"]),
+    tool("yarn", "languages", &["yarn", "package", "node"]),
+    tool("bun", "languages", &["bun", "javascript", "typescript"]),
+    tool("deno", "languages", &["deno", "typescript"]),
+    tool("python", "languages", &["python", "script"]),
+    tool("python3", "languages", &["python", "script"]),
+    tool("pip", "languages", &["pip", "python", "package"]),
+    tool("uv", "languages", &["uv", "python", "venv", "package"]),
+    tool("pipx", "languages", &["pipx", "python"]),
+    tool("poetry", "languages", &["poetry", "python"]),
+    tool("ruff", "languages", &["lint", "format", "python"]),
+    tool("cargo", "languages", &["cargo", "rust", "crate"]),
+    tool("rustc", "languages", &["rust"]),
+    tool("go", "languages", &["go", "golang"]),
+    tool("java", "languages", &["java", "jar", "jvm"]),
+    tool("mvn", "languages", &["maven", "java"]),
+    tool("gradle", "languages", &["gradle", "java", "kotlin"]),
+    tool("ruby", "languages", &["ruby"]),
+    tool("bundle", "languages", &["bundle", "gem", "ruby"]),
+    tool("php", "languages", &["php"]),
+    tool("composer", "languages", &["composer", "php"]),
+    tool("dotnet", "languages", &["dotnet", "csharp", "nuget"]),
+    tool("gcc", "languages", &["compile", "gcc"]),
+    tool("clang", "languages", &["compile", "clang"]),
+    tool("cmake", "languages", &["cmake", "build"]),
+    tool("make", "languages", &["make", "build", "target"]),
+    tool("just", "languages", &["just", "recipe"]),
+    tool("task", "languages", &["task", "taskfile"]),
+    tool("mise", "languages", &["version", "runtime", "install"]),
+    tool("asdf", "languages", &["version", "runtime", "install"]),
+    tool("direnv", "languages", &["env", "environment", "envrc"]),
+    tool("shellcheck", "languages", &["lint", "shell", "script"]),
+    tool("shfmt", "languages", &["format", "shell", "script"]),
+    tool("hyperfine", "languages", &["benchmark", "timing", "compare"]),
+    tool("tokei", "languages", &["lines", "loc", "count", "code"]),
+    // Editors
+    tool("vim", "editors", &["edit", "editor"]),
+    tool("nvim", "editors", &["edit", "editor"]),
+    tool("hx", "editors", &["edit", "editor"]),
+    tool("emacs", "editors", &["edit", "editor"]),
+    tool("nano", "editors", &["edit", "editor"]),
+    tool("code", "editors", &["edit", "editor", "open"]),
+    // System and processes
+    alt("btop", "system", "top", &["cpu", "memory", "process", "monitor", "usage"]),
+    alt("htop", "system", "top", &["cpu", "memory", "process", "monitor", "usage"]),
+    alt("procs", "system", "ps", &["process", "processes", "running"]),
+    tool("pgrep", "system", &["process", "find", "pid"]),
+    tool("pkill", "system", &["kill", "process", "stop"]),
+    tool("systemctl", "system", &["service", "daemon", "unit", "enable", "restart"]),
+    tool("journalctl", "system", &["logs", "journal", "service"]),
+    tool("launchctl", "system", &["service", "daemon", "agent"]),
+    tool("strace", "system", &["trace", "syscall", "debug"]),
+    tool("ltrace", "system", &["trace", "library"]),
+    tool("perf", "system", &["profile", "performance", "cpu"]),
+    tool("iotop", "system", &["disk", "io"]),
+    tool("lsblk", "system", &["disk", "block", "partition", "mount"]),
+    tool("smartctl", "system", &["disk", "health", "smart"]),
+    tool("tmux", "system", &["session", "terminal", "multiplex", "detach"]),
+    tool("screen", "system", &["session", "terminal", "detach"]),
+    tool("zellij", "system", &["session", "terminal", "multiplex"]),
+    alt("tldr", "system", "man", &["help", "example", "usage"]),
+    tool("watch", "system", &["repeat", "every", "monitor"]),
+    tool("timeout", "system", &["timeout", "limit"]),
+    tool("pv", "system", &["progress", "pipe", "throughput"]),
+    // Archives and media
+    tool("tar", "archives", &["archive", "tar", "compress", "extract", "tarball"]),
+    tool("zip", "archives", &["zip", "archive", "compress"]),
+    tool("unzip", "archives", &["unzip", "extract"]),
+    tool("7z", "archives", &["7z", "archive", "extract", "compress"]),
+    tool("zstd", "archives", &["compress", "zstd"]),
+    tool("xz", "archives", &["compress", "xz"]),
+    tool("pigz", "archives", &["compress", "gzip", "parallel"]),
+    tool("ffmpeg", "media", &["video", "audio", "convert", "transcode", "mp4", "mp3", "gif"]),
+    tool("ffprobe", "media", &["video", "audio", "duration", "codec"]),
+    tool("magick", "media", &["image", "resize", "convert", "png", "jpg", "thumbnail"]),
+    tool("convert", "media", &["image", "resize", "convert"]),
+    tool("exiftool", "media", &["exif", "metadata", "photo"]),
+    tool("yt-dlp", "media", &["youtube", "download", "video"]),
+    tool("pandoc", "media", &["markdown", "pdf", "docx", "convert", "document"]),
+    tool("pdftotext", "media", &["pdf", "text", "extract"]),
+    tool("qpdf", "media", &["pdf", "merge", "split"]),
+    // Databases
+    tool("psql", "databases", &["postgres", "postgresql", "sql", "database", "db"]),
+    tool("pg_dump", "databases", &["postgres", "backup", "dump"]),
+    tool("mysql", "databases", &["mysql", "mariadb", "sql", "database"]),
+    tool("mongosh", "databases", &["mongo", "mongodb"]),
+    tool("redis-cli", "databases", &["redis", "cache", "key"]),
+    tool("pgcli", "databases", &["postgres", "sql"]),
+    tool("usql", "databases", &["sql", "database"]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_has_unique_names_and_keywords() {
+        let mut names: Vec<&str> = CATALOG.iter().map(|t| t.name).collect();
+        names.sort_unstable();
+        let before = names.len();
+        names.dedup();
+        assert_eq!(names.len(), before, "duplicate catalogue entries");
+
+        assert!(CATALOG.len() > 150);
+        assert!(CATALOG.iter().all(|t| !t.keywords.is_empty()));
+        for name in ["rg", "fd", "jq", "yq", "bat", "eza", "http", "fzf", "parallel", "gh"] {
+            assert!(CATALOG.iter().any(|t| t.name == name), "{} missing", name);
+        }
+    }
+}
@@ -1,72 +1,263 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::history;
+use crate::paths;
+use crate::privacy::PrivacyLevel;
+use crate::process_util;
+use crate::tool_catalog::CATALOG;
+use crate::tool_versions::{self, VersionCache};
+
+/// At most this many tools are called out as relevant to a query
+const MAX_RELEVANT_TOOLS: usize = 8;
+
+/// Most recent history lines kept for picking few-shot examples
+const MAX_HISTORY: usize = 5000;
+
+/// Prompt budget for the history examples
+const EXAMPLE_TOKEN_BUDGET: usize = 200;
+
+/// A `[[tools]]` entry from the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomTool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Classic tool this one should be preferred over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+    /// Words in a query that make the tool relevant
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+}
+
+/// A tool we know something about, from the catalogue or the config
+#[derive(Debug, Clone, PartialEq)]
+struct ToolSpec {
+    name: String,
+    category: String,
+    replaces: Option<String>,
+    keywords: Vec<String>,
+    /// Where the binary was found in `PATH`
+    path: PathBuf,
+}
+
+impl ToolSpec {
+    /// How well the tool fits a query: named outright, matched by keyword,
+    /// or standing in for a classic tool the query mentions
+    fn relevance(&self, words: &[String]) -> f32 {
+        let has = |word: &str| words.iter().any(|w| w == word);
+
+        if has(&self.name) {
+            1.0
+        } else if self.keywords.iter().any(|k| has(k)) {
+            0.8
+        } else if self.replaces.as_deref().is_some_and(has) || has(&self.category) {
+            0.5
+        } else {
+            0.0
+        }
+    }
+
+    /// `rg (instead of grep)`
+    fn describe(&self) -> String {
+        match &self.replaces {
+            Some(classic) => format!("{} (instead of {})", self.name, classic),
+            None => self.name.clone(),
+        }
+    }
+}
 
+/// Built-in catalogue followed by the user's additions; a custom entry
+/// with a catalogue name replaces the built-in one
+fn tool_specs(custom: &[CustomTool]) -> Vec<ToolSpec> {
+    let mut specs: Vec<ToolSpec> = CATALOG
+        .iter()
+        .filter(|entry| !custom.iter().any(|c| c.name == entry.name))
+        .map(|entry| ToolSpec {
+            name: entry.name.to_string(),
+            category: entry.category.to_string(),
+            replaces: entry.replaces.map(String::from),
+            keywords: entry.keywords.iter().map(|k| k.to_string()).collect(),
+            path: PathBuf::new(),
+        })
+        .collect();
+
+    // Names are joined onto PATH directories, so they must not be paths themselves
+    let custom = custom.iter().filter(|tool| {
+        let valid = process_util::is_bare_name(&tool.name);
+        if !valid {
+            eprintln!("⚠️  Ignoring tool {:?}: names must be plain file names, not paths", tool.name);
+        }
+        valid
+    });
+    specs.extend(custom.map(|tool| ToolSpec {
+        name: tool.name.clone(),
+        category: tool.category.clone().unwrap_or_else(|| "custom".to_string()),
+        replaces: tool.replaces.clone(),
+        keywords: tool.keywords.iter().map(|k| k.to_lowercase()).collect(),
+        path: PathBuf::new(),
+    }));
+
+    specs
+}
+
+#[derive(Debug, Default)]
 pub struct ToolDetection {
     pub installed_tools: Vec<String>,
     pub frequent_commands: Vec<(String, usize)>,
+    /// Catalogue entries of the installed tools
+    specs: Vec<ToolSpec>,
+    /// Where probed tool versions are cached; no versions are collected without one
+    version_cache: Option<PathBuf>,
+    /// The last lines of the user's shell histories, each file oldest first
+    history: Vec<String>,
 }
 
 impl ToolDetection {
-    pub fn detect() -> Self {
-        let installed_tools = Self::scan_path();
-        let frequent_commands = Self::analyze_history();
+    pub fn detect(custom: &[CustomTool]) -> Self {
+        let specs = Self::scan_path(&tool_specs(custom));
+        let mut history = Self::home_history();
+        let frequent_commands = Self::count_commands(&history);
+        history.drain(..history.len().saturating_sub(MAX_HISTORY));
 
         Self {
-            installed_tools,
+            installed_tools: specs.iter().map(|s| s.name.clone()).collect(),
             frequent_commands,
+            specs,
+            version_cache: VersionCache::default_path(),
+            history,
+        }
+    }
+
+    /// Detect tools only if the privacy level allows it
+    pub fn detect_with_privacy(privacy: PrivacyLevel, custom: &[CustomTool]) -> Self {
+        if privacy.allows_full_context() {
+            Self::detect(custom)
+        } else {
+            Self::default()
         }
     }
 
-    fn scan_path() -> Vec<String> {
-        let path_var = match std::env::var("PATH") {
-            Ok(p) => p,
-            Err(_) => return Vec::new(),
+    /// Catalogue entries whose binary is in one of the `PATH` directories
+    fn scan_path(specs: &[ToolSpec]) -> Vec<ToolSpec> {
+        let path_var = match std::env::var_os("PATH") {
+            Some(p) => p,
+            None => return Vec::new(),
         };
+        let dirs: Vec<PathBuf> = std::env::split_paths(&path_var).collect();
+
+        specs
+            .iter()
+            .filter_map(|spec| {
+                let path = dirs.iter().map(|dir| dir.join(&spec.name)).find(|p| p.is_file())?;
+                Some(ToolSpec { path, ..spec.clone() })
+            })
+            .collect()
+    }
 
-        let mut tools = Vec::new();
-        let common_tools = [
-            "git", "docker", "npm", "node", "python", "python3", "cargo",
-            "rustc", "go", "java", "kubectl", "vim", "nvim", "code", "curl", "wget"
-        ];
-
-        for tool in &common_tools {
-            for dir in path_var.split(':') {
-                let tool_path = PathBuf::from(dir).join(tool);
-                if tool_path.exists() {
-                    tools.push(tool.to_string());
-                    break;
-                }
-            }
+    /// Name and binary path of each installed catalogue tool
+    pub fn installed_paths(&self) -> Vec<(String, PathBuf)> {
+        self.specs.iter().map(|s| (s.name.clone(), s.path.clone())).collect()
+    }
+
+    /// Installed tools that fit `query`, best first
+    fn ranked(&self, query: &str) -> Vec<&ToolSpec> {
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        let mut scored: Vec<(f32, &ToolSpec)> = self
+            .specs
+            .iter()
+            .map(|spec| (spec.relevance(&words), spec))
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        scored.into_iter().take(MAX_RELEVANT_TOOLS).map(|(_, spec)| spec).collect()
+    }
+
+    /// Installed tools that fit `query`, best first
+    pub fn relevant_tools(&self, query: &str) -> Vec<String> {
+        self.ranked(query).into_iter().map(ToolSpec::describe).collect()
+    }
+
+    /// Versions (and capability probes such as `docker compose`) of the tools
+    /// relevant to `query`, probed on first use and cached by path and mtime
+    pub fn relevant_versions(&self, query: &str) -> Vec<(String, String)> {
+        let Some(cache_path) = &self.version_cache else {
+            return Vec::new();
+        };
+
+        let probes: Vec<_> = self
+            .ranked(query)
+            .into_iter()
+            .flat_map(|spec| tool_versions::probes_for(&spec.name, &spec.path))
+            .collect();
+        if probes.is_empty() {
+            return Vec::new();
         }
 
-        tools
+        let mut cache = VersionCache::load(cache_path);
+        let versions = cache.versions(&probes);
+        cache.save(cache_path);
+        versions
     }
 
-    fn analyze_history() -> Vec<(String, usize)> {
-        let mut command_counts: HashMap<String, usize> = HashMap::new();
+    /// Past commands of the user's that resemble `query`, redacted and within the example budget
+    pub fn history_examples(&self, query: &str) -> Vec<String> {
+        history::similar_commands(&self.history, query, EXAMPLE_TOKEN_BUDGET)
+    }
 
-        // Try bash history
-        if let Ok(home) = std::env::var("HOME") {
-            let bash_history = PathBuf::from(&home).join(".bash_history");
-            if let Ok(contents) = fs::read_to_string(bash_history) {
-                for line in contents.lines() {
-                    if let Some(cmd) = line.split_whitespace().next() {
-                        *command_counts.entry(cmd.to_string()).or_insert(0) += 1;
-                    }
-                }
+    /// The lines naming the installed tools to prefer for `query`, their
+    /// versions and similar commands from the user's history, if any match
+    pub fn format_for_query(&self, query: &str) -> Option<String> {
+        let mut lines = Vec::new();
+
+        let relevant = self.relevant_tools(query);
+        if !relevant.is_empty() {
+            lines.push(format!("Prefer these installed tools for this request: {}", relevant.join(", ")));
+            for (tool, version) in self.relevant_versions(query) {
+                lines.push(format!("{} version: {}", tool, version));
             }
+        }
+
+        let examples = self.history_examples(query);
+        if !examples.is_empty() {
+            lines.push("Similar commands from the user's history (match their style):".to_string());
+            lines.extend(examples.iter().map(|example| format!("  {}", example)));
+        }
+
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    /// Every line of the user's shell histories, if `$HOME` is set
+    fn home_history() -> Vec<String> {
+        match paths::home_dir() {
+            Some(home) => Self::read_history(&home),
+            None => Vec::new(),
+        }
+    }
+
+    /// The bash, zsh, fish, nushell, PowerShell and `$HISTFILE` histories under `home`
+    fn read_history(home: &Path) -> Vec<String> {
+        history::history_sources(home)
+            .iter()
+            .flat_map(history::read_commands)
+            .collect()
+    }
 
-            // Try zsh history
-            let zsh_history = PathBuf::from(&home).join(".zsh_history");
-            if let Ok(contents) = fs::read_to_string(zsh_history) {
-                for line in contents.lines() {
-                    // zsh history format: : timestamp:0;command
-                    let cmd_part = line.split(';').nth(1).unwrap_or(line);
-                    if let Some(cmd) = cmd_part.split_whitespace().next() {
-                        *command_counts.entry(cmd.to_string()).or_insert(0) += 1;
-                    }
-                }
+    /// Top programs in `commands`
+    fn count_commands(commands: &[String]) -> Vec<(String, usize)> {
+        let mut command_counts: HashMap<String, usize> = HashMap::new();
+
+        for command in commands {
+            if let Some(cmd) = history::command_name(command) {
+                *command_counts.entry(cmd.to_string()).or_insert(0) += 1;
             }
         }
 
@@ -106,19 +297,29 @@ mod tests {
 
     #[test]
     fn test_detect_creates_tool_detection() {
-        let detection = ToolDetection::detect();
+        let detection = ToolDetection::detect(&[]);
         // Should at least have some structure
         assert!(detection.installed_tools.len() >= 0);
         assert!(detection.frequent_commands.len() >= 0);
     }
 
+    #[test]
+    fn test_detect_with_privacy_skips_collection() {
+        for privacy in [PrivacyLevel::Minimal, PrivacyLevel::None] {
+            let detection = ToolDetection::detect_with_privacy(privacy, &[]);
+            assert!(detection.installed_tools.is_empty());
+            assert!(detection.frequent_commands.is_empty());
+            assert_eq!(detection.format_context(), "");
+        }
+    }
+
     #[test]
     fn test_scan_path_empty_when_no_path() {
         // Temporarily unset PATH
         let original_path = std::env::var("PATH").ok();
         std::env::remove_var("PATH");
 
-        let tools = ToolDetection::scan_path();
+        let tools = ToolDetection::scan_path(&tool_specs(&[]));
         assert_eq!(tools.len(), 0);
 
         // Restore PATH
@@ -136,6 +337,7 @@ mod tests {
                 ("cd".to_string(), 80),
                 ("git".to_string(), 60),
             ],
+            ..Default::default()
         };
 
         let context = detection.format_context();
@@ -148,6 +350,7 @@ mod tests {
         let detection = ToolDetection {
             installed_tools: vec![],
             frequent_commands: vec![],
+            ..Default::default()
         };
 
         let context = detection.format_context();
@@ -159,6 +362,7 @@ mod tests {
         let detection = ToolDetection {
             installed_tools: vec!["git".to_string(), "docker".to_string()],
             frequent_commands: vec![],
+            ..Default::default()
         };
 
         let context = detection.format_context();
@@ -174,6 +378,7 @@ mod tests {
                 ("ls".to_string(), 50),
                 ("cd".to_string(), 30),
             ],
+            ..Default::default()
         };
 
         let context = detection.format_context();
@@ -198,6 +403,7 @@ mod tests {
                 ("cmd10".to_string(), 10),
                 ("cmd11".to_string(), 5), // Should not appear
             ],
+            ..Default::default()
         };
 
         let context = detection.format_context();
@@ -219,7 +425,7 @@ mod tests {
         )
         .unwrap();
 
-        let commands = ToolDetection::analyze_history();
+        let commands = ToolDetection::count_commands(&ToolDetection::home_history());
 
         // ls should appear 3 times, so it should be first
         assert!(!commands.is_empty());
@@ -241,7 +447,7 @@ mod tests {
         )
         .unwrap();
 
-        let commands = ToolDetection::analyze_history();
+        let commands = ToolDetection::count_commands(&ToolDetection::home_history());
 
         assert!(!commands.is_empty());
         let ls_count = commands.iter().find(|(cmd, _)| cmd == "ls");
@@ -249,13 +455,33 @@ mod tests {
         assert_eq!(ls_count.unwrap().1, 2);
     }
 
+    #[test]
+    fn test_analyze_history_with_fish_history() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let fish_dir = temp_dir.path().join(".local/share/fish");
+        fs::create_dir_all(&fish_dir).unwrap();
+        fs::write(
+            fish_dir.join("fish_history"),
+            "- cmd: rg TODO\n  when: 1700000000\n- cmd: rg -l fixme\n  when: 1700000001\n",
+        )
+        .unwrap();
+
+        let history: Vec<String> = history::sources_in(temp_dir.path(), |_| None)
+            .iter()
+            .flat_map(history::read_commands)
+            .collect();
+        let commands = ToolDetection::count_commands(&history);
+        assert_eq!(commands.iter().find(|(cmd, _)| cmd == "rg").map(|(_, n)| *n), Some(2));
+    }
+
     #[test]
     fn test_analyze_history_empty_when_no_home() {
         // Temporarily unset HOME
         let original_home = std::env::var("HOME").ok();
         std::env::remove_var("HOME");
 
-        let commands = ToolDetection::analyze_history();
+        let commands = ToolDetection::count_commands(&ToolDetection::home_history());
         assert_eq!(commands.len(), 0);
 
         // Restore HOME
@@ -263,4 +489,98 @@ mod tests {
             std::env::set_var("HOME", home);
         }
     }
+
+    #[test]
+    fn test_relevant_tools_for_query() {
+        let custom = vec![CustomTool {
+            name: "deployctl".to_string(),
+            category: Some("internal".to_string()),
+            replaces: None,
+            keywords: vec!["Deploy".to_string()],
+        }];
+        let specs: Vec<ToolSpec> = tool_specs(&custom)
+            .into_iter()
+            .filter(|s| ["git", "rg", "fd", "jq", "curl", "deployctl"].contains(&s.name.as_str()))
+            .collect();
+        let detection = ToolDetection {
+            installed_tools: specs.iter().map(|s| s.name.clone()).collect(),
+            specs,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            detection.relevant_tools("curl the API and pull fields out of the json"),
+            vec!["curl", "jq"]
+        );
+        assert_eq!(
+            detection.format_for_query("grep for TODO").as_deref(),
+            Some("Prefer these installed tools for this request: rg (instead of grep)")
+        );
+        assert_eq!(detection.relevant_tools("deploy to staging"), vec!["deployctl"]);
+        assert_eq!(detection.format_for_query("say hello"), None);
+    }
+
+    #[test]
+    fn test_format_for_query_includes_history_examples() {
+        let detection = ToolDetection {
+            history: vec![
+                "cd ~/src".to_string(),
+                "tar --zstd -cf backup.tar.zst photos/".to_string(),
+                "MYSQL_PASSWORD=hunter2 mysql -u root".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            detection.format_for_query("make a backup of my documents").as_deref(),
+            Some("Similar commands from the user's history (match their style):\n  tar --zstd -cf backup.tar.zst photos/")
+        );
+        assert!(!detection.format_for_query("open mysql as root").unwrap().contains("hunter2"));
+    }
+
+    #[test]
+    fn test_custom_tool_overrides_catalogue_entry() {
+        let custom = vec![CustomTool {
+            name: "rg".to_string(),
+            category: None,
+            replaces: None,
+            keywords: vec!["logs".to_string()],
+        }];
+        let specs = tool_specs(&custom);
+
+        assert_eq!(specs.iter().filter(|s| s.name == "rg").count(), 1);
+        let rg = specs.iter().find(|s| s.name == "rg").unwrap();
+        assert_eq!(rg.category, "custom");
+        assert_eq!(rg.relevance(&["logs".to_string()]), 0.8);
+
+        let escapes = CustomTool { name: "../../proc/self/cwd/evil".to_string(), ..custom[0].clone() };
+        assert!(!tool_specs(&[escapes]).iter().any(|s| s.name.contains("evil")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_format_for_query_includes_versions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let jq = dir.path().join("jq");
+        fs::write(&jq, "#!/bin/sh\necho jq-1.7.1\n").unwrap();
+        fs::set_permissions(&jq, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut specs = tool_specs(&[]);
+        specs.retain(|s| s.name == "jq");
+        specs[0].path = jq;
+        let detection = ToolDetection {
+            installed_tools: vec!["jq".to_string()],
+            specs,
+            version_cache: Some(dir.path().join("tool-versions.json")),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            detection.format_for_query("pretty print this json").as_deref(),
+            Some("Prefer these installed tools for this request: jq\njq version: jq-1.7.1")
+        );
+        assert!(dir.path().join("tool-versions.json").exists());
+    }
 }
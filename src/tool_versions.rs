@@ -0,0 +1,249 @@
+//! Versions of installed tools, cached by binary path and mtime
+//!
+//! `--version` probes are cheap but not free (`kubectl` and `docker` can take
+//! a few hundred milliseconds), so results are kept in
+//! `~/.cache/ez-term/tool-versions.json` and reused until the binary changes.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::paths;
+use crate::process_util::output_with_timeout;
+
+/// How long a single version probe may take
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Longest version line passed on to the model
+const MAX_VERSION_LEN: usize = 100;
+
+/// Arguments that print a tool's version, for tools that don't take `--version`
+fn version_args(tool: &str) -> &'static [&'static str] {
+    match tool {
+        "kubectl" => &["version", "--client"],
+        "go" | "helm" | "gh" | "glab" | "flux" | "k9s" | "kind" | "minikube" | "k3d" | "doctl" | "flyctl" | "argocd" => {
+            &["version"]
+        }
+        "java" => &["-version"],
+        "ssh" | "tmux" => &["-V"],
+        "ip" => &["-V"],
+        "dig" => &["-v"],
+        "lsof" => &["-v"],
+        "screen" => &["-v"],
+        _ => &["--version"],
+    }
+}
+
+/// Extra probes whose answer changes which syntax works, e.g. Compose v1 vs v2
+fn capability_probes(tool: &str) -> &'static [(&'static str, &'static [&'static str])] {
+    match tool {
+        "docker" => &[("docker compose", &["compose", "version"]), ("docker buildx", &["buildx", "version"])],
+        "podman" => &[("podman compose", &["compose", "version"])],
+        _ => &[],
+    }
+}
+
+/// The first output line that looks like it carries a version number
+pub fn extract_version(output: &str) -> Option<String> {
+    static VERSION: OnceLock<Regex> = OnceLock::new();
+    let version = VERSION.get_or_init(|| Regex::new(r"\d+\.\d+").unwrap());
+
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| version.is_match(line))
+        .map(|line| line.chars().take(MAX_VERSION_LEN).collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedVersion {
+    mtime: u64,
+    /// `None` when the probe ran but printed no version
+    version: Option<String>,
+}
+
+/// Version probe results keyed by `"<binary path> <args>"`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VersionCache {
+    entries: HashMap<String, CachedVersion>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl VersionCache {
+    /// `$XDG_CACHE_HOME/ez-term/tool-versions.json`, falling back to `~/.cache`
+    pub fn default_path() -> Option<PathBuf> {
+        Some(paths::cache_dir().ok()?.join("tool-versions.json"))
+    }
+
+    /// Load the cache; a missing or corrupt file gives an empty cache
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the cache back if any probe result changed
+    pub fn save(&self, path: &Path) {
+        if !self.dirty {
+            return;
+        }
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    /// Versions for `(label, binary, args)` probes, running only the ones not cached
+    ///
+    /// Probes run in parallel; ones that fail or time out are left out.
+    pub fn versions(&mut self, probes: &[(String, PathBuf, Vec<&'static str>)]) -> Vec<(String, String)> {
+        let mut results: Vec<Option<Option<String>>> = vec![None; probes.len()];
+        let mut pending = Vec::new();
+
+        for (index, (_, binary, args)) in probes.iter().enumerate() {
+            let Some(mtime) = mtime(binary) else {
+                continue;
+            };
+            match self.entries.get(&cache_key(binary, args)) {
+                Some(cached) if cached.mtime == mtime => results[index] = Some(cached.version.clone()),
+                _ => pending.push((index, mtime)),
+            }
+        }
+
+        let handles: Vec<_> = pending
+            .into_iter()
+            .map(|(index, mtime)| {
+                let (_, binary, args) = probes[index].clone();
+                (index, mtime, thread::spawn(move || probe(&binary, &args)))
+            })
+            .collect();
+
+        for (index, mtime, handle) in handles {
+            // A timed-out probe is not cached, so it is retried next run
+            let Some(version) = handle.join().ok().flatten() else {
+                continue;
+            };
+            let (_, binary, args) = &probes[index];
+            self.entries.insert(
+                cache_key(binary, args),
+                CachedVersion {
+                    mtime,
+                    version: version.clone(),
+                },
+            );
+            self.dirty = true;
+            results[index] = Some(version);
+        }
+
+        probes
+            .iter()
+            .zip(results)
+            .filter_map(|((label, _, _), version)| Some((label.clone(), version??)))
+            .collect()
+    }
+}
+
+/// Probes for a tool's version and its capability checks
+pub fn probes_for(tool: &str, binary: &Path) -> Vec<(String, PathBuf, Vec<&'static str>)> {
+    let mut probes = vec![(tool.to_string(), binary.to_path_buf(), version_args(tool).to_vec())];
+    probes.extend(
+        capability_probes(tool)
+            .iter()
+            .map(|(label, args)| (label.to_string(), binary.to_path_buf(), args.to_vec())),
+    );
+    probes
+}
+
+fn cache_key(binary: &Path, args: &[&str]) -> String {
+    format!("{} {}", binary.display(), args.join(" "))
+}
+
+fn mtime(binary: &Path) -> Option<u64> {
+    let modified = fs::metadata(binary).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// `Some(None)` when the tool ran but printed no version; `None` when it did not finish
+fn probe(binary: &Path, args: &[&str]) -> Option<Option<String>> {
+    let output = output_with_timeout(Command::new(binary).args(args), PROBE_TIMEOUT).ok()??;
+    // Some tools (java, ssh) print their version on stderr
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Some(extract_version(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_extract_version() {
+        assert_eq!(
+            extract_version("Docker version 27.1.1, build 6312585\n").as_deref(),
+            Some("Docker version 27.1.1, build 6312585")
+        );
+        assert_eq!(
+            extract_version("\nopenjdk version \"21.0.4\" 2024-07-16\n").as_deref(),
+            Some("openjdk version \"21.0.4\" 2024-07-16")
+        );
+        assert_eq!(extract_version("usage: foo [-h]\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_versions_are_cached_by_path_and_mtime() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        let counter = dir.path().join("runs");
+        let tool = dir.path().join("mytool");
+        fs::write(
+            &tool,
+            format!("#!/bin/sh\necho run >> {}\necho \"mytool 1.2.3\"\n", counter.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&tool, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let cache_path = dir.path().join("cache/tool-versions.json");
+        let probes = probes_for("mytool", &tool);
+
+        let mut cache = VersionCache::load(&cache_path);
+        assert_eq!(cache.versions(&probes), vec![("mytool".to_string(), "mytool 1.2.3".to_string())]);
+        cache.save(&cache_path);
+
+        let mut cache = VersionCache::load(&cache_path);
+        assert_eq!(cache.versions(&probes), vec![("mytool".to_string(), "mytool 1.2.3".to_string())]);
+        assert_eq!(fs::read_to_string(&counter).unwrap().lines().count(), 1);
+
+        // A changed binary is probed again
+        let file = fs::File::options().append(true).open(&tool).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(1_000_000)).unwrap();
+        drop(file);
+        cache.versions(&probes);
+        assert_eq!(fs::read_to_string(&counter).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn test_probes_for_docker_include_compose() {
+        let labels: Vec<String> = probes_for("docker", Path::new("/usr/bin/docker"))
+            .into_iter()
+            .map(|(label, _, _)| label)
+            .collect();
+        assert_eq!(labels, vec!["docker", "docker compose", "docker buildx"]);
+        assert_eq!(version_args("kubectl"), ["version", "--client"]);
+    }
+}
@@ -0,0 +1,230 @@
+use assert_cmd::Command;
+use httpmock::prelude::*;
+use predicates::prelude::*;
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+/// `ez` with its config under `home`, isolated from the host's environment
+fn ez(home: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("ez").unwrap();
+    cmd.env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env_remove("EZ_CONFIG_DIR")
+        .env_remove("EZ_BACKEND")
+        .env_remove("EZ_MODEL")
+        .env_remove("EZ_PROFILE")
+        .current_dir(home.path());
+    cmd
+}
+
+fn config_file(home: &TempDir) -> String {
+    fs::read_to_string(home.path().join(".config/ez-term/config.toml")).unwrap_or_default()
+}
+
+#[test]
+fn test_e2e_config_set_get_unset() {
+    let home = TempDir::new().unwrap();
+
+    ez(&home).args(["config", "set", "backend", "ollama"]).assert().success();
+    ez(&home).args(["config", "set", "privacy.groq", "minimal"]).assert().success();
+    ez(&home)
+        .args(["config", "get", "privacy.groq"])
+        .assert()
+        .success()
+        .stdout("minimal\n");
+
+    ez(&home).args(["config", "unset", "privacy.groq"]).assert().success();
+    ez(&home)
+        .args(["config", "get", "privacy.groq"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("privacy.groq is not set"));
+    assert_eq!(config_file(&home), "backend = \"ollama\"\n");
+}
+
+#[test]
+fn test_e2e_config_set_rejects_invalid_values() {
+    let home = TempDir::new().unwrap();
+
+    ez(&home)
+        .args(["config", "set", "backend", "olama"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown backend \"olama\""));
+    ez(&home)
+        .args(["config", "set", "ollama_url", "gpu-box:11434"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must start with http:// or https://"));
+    ez(&home)
+        .args(["config", "set", "flag_check", "loud"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected one of off, warn, regenerate"));
+    assert_eq!(config_file(&home), "");
+
+    ez(&home).args(["config", "set", "backend", "groq"]).assert().success();
+    ez(&home)
+        .args(["--set-backend", "gorq"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown backend \"gorq\""));
+    assert_eq!(config_file(&home), "backend = \"groq\"\n");
+}
+
+#[test]
+fn test_e2e_config_edit_validates_before_saving() {
+    let home = TempDir::new().unwrap();
+    ez(&home).args(["config", "set", "backend", "groq"]).assert().success();
+
+    ez(&home)
+        .env_remove("VISUAL")
+        .env("EDITOR", "sed -i s/groq/nope/")
+        .args(["config", "edit"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown backend \"nope\""));
+    assert_eq!(config_file(&home), "backend = \"groq\"\n");
+    assert!(!home.path().join(".config/ez-term/config.edit.toml").exists());
+
+    ez(&home)
+        .env_remove("VISUAL")
+        .env("EDITOR", "sed -i s/groq/ollama/")
+        .args(["config", "edit"])
+        .assert()
+        .success();
+    assert_eq!(config_file(&home), "backend = \"ollama\"\n");
+}
+
+#[test]
+fn test_e2e_config_validate_and_origin() {
+    let home = TempDir::new().unwrap();
+    ez(&home).args(["config", "set", "backend", "groq"]).assert().success();
+    fs::write(home.path().join(".ez-term.toml"), "flag_check = \"regenerate\"\n").unwrap();
+
+    ez(&home)
+        .arg("--model")
+        .arg("llama3")
+        .env("EZ_BINARY_CHECK", "off")
+        .args(["config", "show", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("backend = \"groq\"  # user: "))
+        .stdout(predicate::str::contains("binary_check = \"off\"  # env: EZ_BINARY_CHECK"))
+        .stdout(predicate::str::contains("flag_check = \"regenerate\"  # project: "))
+        .stdout(predicate::str::contains("model = \"llama3\"  # cli: --model"));
+
+    ez(&home)
+        .args(["config", "validate"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ project: "));
+
+    fs::write(home.path().join(".ez-term.toml"), "flag_chek = \"regenerate\"\n").unwrap();
+    ez(&home)
+        .args(["config", "validate"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("unknown key flag_chek"));
+}
+
+#[test]
+fn test_e2e_profile_create_use_list() {
+    let home = TempDir::new().unwrap();
+    let work = home.path().join("work");
+    fs::create_dir_all(work.join("api")).unwrap();
+
+    ez(&home).args(["config", "set", "backend", "groq"]).assert().success();
+    ez(&home)
+        .args(["profile", "create", "work", "--backend", "openai", "--privacy", "minimal", "--path"])
+        .arg(&work)
+        .assert()
+        .success();
+    ez(&home)
+        .args(["profile", "create", "offline", "--backend", "ollama", "--model", "llama3.2"])
+        .assert()
+        .success();
+    ez(&home)
+        .args(["profile", "create", "typo", "--backend", "olama"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("profiles.typo.backend: unknown backend \"olama\""));
+    ez(&home)
+        .args(["profile", "use", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown profile 'nope'"));
+    ez(&home).args(["profile", "use", "offline"]).assert().success();
+
+    ez(&home)
+        .args(["profile", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("* offline      ollama   llama3.2                 (default)"));
+    ez(&home)
+        .current_dir(work.join("api"))
+        .args(["config", "show", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("backend = \"openai\"  # profile: work"))
+        .stdout(predicate::str::contains("privacy.openai = \"minimal\"  # profile: work"));
+    ez(&home)
+        .current_dir(work.join("api"))
+        .env("EZ_PROFILE", "offline")
+        .env("EZ_MODEL", "qwen")
+        .args(["config", "show", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("backend = \"ollama\"  # profile: offline"))
+        .stdout(predicate::str::contains("model = \"qwen\"  # env: EZ_MODEL"));
+    ez(&home)
+        .args(["--profile", "nope", "config", "show"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown profile 'nope'"));
+}
+
+#[test]
+fn test_e2e_profile_openai_compatible_endpoint() {
+    let home = TempDir::new().unwrap();
+    let server = MockServer::start();
+    let content = json!({"command": "ls -la", "description": "Lists files."}).to_string();
+    let mock = server.mock(|when, then| {
+        when.method(POST)
+            .path("/v1/chat/completions")
+            .json_body_partial(r#"{"model": "qwen-coder"}"#)
+            .matches(|req| !req.headers.iter().flatten().any(|(name, _)| name.eq_ignore_ascii_case("authorization")));
+        then.status(200)
+            .header("content-type", "text/event-stream")
+            .body(format!(
+                "data: {}\n\ndata: [DONE]\n\n",
+                json!({"choices": [{"delta": {"content": content}}]})
+            ));
+    });
+
+    // A self-hosted server needs no API key
+    let work = home.path().join("work");
+    fs::create_dir_all(&work).unwrap();
+    fs::create_dir_all(home.path().join(".config/ez-term")).unwrap();
+    fs::write(
+        home.path().join(".config/ez-term/config.toml"),
+        format!(
+            "backend = \"groq\"\nbinary_check = \"off\"\nflag_check = \"off\"\n\n\
+             [profiles.work]\nbackend = \"openai\"\nmodel = \"qwen-coder\"\nopenai_url = \"{}/v1\"\npaths = [{:?}]\n",
+            server.base_url(),
+            work
+        ),
+    )
+    .unwrap();
+
+    ez(&home)
+        .current_dir(&work)
+        .env_remove("OPENAI_API_KEY")
+        .env_remove("OPENAI_BASE_URL")
+        .arg("list files")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ls -la"));
+    mock.assert();
+}
@@ -0,0 +1,111 @@
+use assert_cmd::Command;
+use httpmock::prelude::*;
+use predicates::prelude::*;
+use serde_json::json;
+use std::fs;
+use tempfile::TempDir;
+
+const CORPUS: &str = r#"
+cases:
+  - id: git-branch
+    query: show the current git branch
+    expect:
+      starts_with: git
+      contains: [branch]
+      risk: safe
+  - id: delete-logs
+    query: delete old log files
+    expect:
+      starts_with: find
+      not_contains: ["-delete"]
+      risk: safe
+"#;
+
+/// A mock Ollama that answers each corpus query with a fixed command
+fn mock_ollama(server: &MockServer) {
+    let answers = [
+        ("show the current git branch", "git branch --show-current"),
+        ("delete old log files", "find . -name '*.log' -delete"),
+    ];
+    for (query, command) in answers {
+        server.mock(|when, then| {
+            when.method(POST).path("/api/generate").body_contains(query);
+            then.status(200).json_body(json!({
+                "response": json!({"command": command, "description": "Does it."}).to_string(),
+                "done": true
+            }));
+        });
+    }
+}
+
+fn ez_eval(home: &TempDir, server: &MockServer) -> Command {
+    let mut cmd = Command::cargo_bin("ez").unwrap();
+    cmd.env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .env("OLLAMA_HOST", server.base_url())
+        .env_remove("EZ_CONFIG_DIR")
+        .env_remove("EZ_PROFILE")
+        .current_dir(home.path())
+        .args(["--backend", "ollama", "--model", "test-model", "eval", "corpus.yaml"]);
+    cmd
+}
+
+#[test]
+fn test_e2e_eval_report() {
+    let home = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_ollama(&server);
+    fs::write(home.path().join("corpus.yaml"), CORPUS).unwrap();
+
+    ez_eval(&home, &server)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"✓ git-branch +\d+\.\d\ds  git branch --show-current\n").unwrap())
+        .stdout(predicate::str::contains("✗ delete-logs"))
+        .stdout(predicate::str::contains("    not_contains: contains \"-delete\"\n    risk: risk is critical, expected safe\n"))
+        .stdout(predicate::str::contains("Backend: ollama  Model: test-model\nPassed 1/2 cases (50.0%)\n"))
+        .stdout(predicate::str::contains("  starts_with    2/2\n"))
+        .stdout(predicate::str::contains("  risk           1/2\n"))
+        .stdout(predicate::str::contains("Latency: mean "));
+
+    ez_eval(&home, &server)
+        .args(["--min-pass-rate", "90"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Pass rate 50.0% is below --min-pass-rate 90"));
+}
+
+#[test]
+fn test_e2e_eval_json_and_filter() {
+    let home = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_ollama(&server);
+    fs::write(home.path().join("corpus.yaml"), CORPUS).unwrap();
+
+    let output = ez_eval(&home, &server).args(["--json", "--filter", "git"]).output().unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["model"], "test-model");
+    assert_eq!(report["total"], 1);
+    assert_eq!(report["pass_rate"], 100.0);
+    assert_eq!(report["checks"]["contains"], json!({"passed": 1, "total": 1}));
+    assert_eq!(report["cases"][0]["command"], "git branch --show-current");
+}
+
+#[test]
+fn test_e2e_eval_no_sandbox_leaves_sandbox_checks_out() {
+    let home = TempDir::new().unwrap();
+    let server = MockServer::start();
+    mock_ollama(&server);
+    fs::write(
+        home.path().join("corpus.yaml"),
+        "cases:\n  - id: git-branch\n    query: show the current git branch\n    expect:\n      starts_with: git\n      sandbox: true\n",
+    )
+    .unwrap();
+
+    let output = ez_eval(&home, &server).args(["--json", "--no-sandbox"]).output().unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["pass_rate"], 100.0);
+    assert!(report["checks"].get("sandbox").is_none());
+}
@@ -188,6 +188,8 @@ fn test_update_preserves_config() {
     Command::cargo_bin("ez")
         .unwrap()
         .env("HOME", temp_dir.path())
+        .env("XDG_CONFIG_HOME", temp_dir.path().join(".config"))
+        .env_remove("EZ_CONFIG_DIR")
         .arg("--set-backend")
         .arg("ollama")
         .assert()
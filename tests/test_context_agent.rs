@@ -179,3 +179,95 @@ fn test_context_agent_keyword_detection() {
         );
     }
 }
+
+#[tokio::test]
+async fn test_context_agent_respects_privacy_level() {
+    use ez_cli::context_agent::ContextAgent;
+    use ez_cli::privacy::PrivacyLevel;
+
+    for privacy in [PrivacyLevel::Minimal, PrivacyLevel::None] {
+        let agent = ContextAgent::new().unwrap().with_privacy(privacy);
+        assert_eq!(
+            agent
+                .gather("show git branch and cargo crate and docker containers")
+                .await
+                .context,
+            ""
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_context_agent_gathers_external_providers() {
+    use ez_cli::context_agent::ContextAgent;
+    use ez_cli::providers::ExternalProviderConfig;
+
+    let providers: Vec<ExternalProviderConfig> = vec![toml::from_str(
+        r#"
+        name = "internal-cli"
+        title = "Internal CLI"
+        keywords = ["acme"]
+        command = "echo 'acmectl 2.1, default cluster: eu-1'"
+        "#,
+    )
+    .unwrap()];
+
+    let agent = ContextAgent::new().unwrap().with_external_providers(&providers);
+    assert!(agent.provider_names().contains(&"internal-cli"));
+
+    let context = agent.gather("list acme clusters").await.context;
+    assert!(context.contains("## Internal CLI\nacmectl 2.1, default cluster: eu-1"));
+    assert!(!agent.gather("list files").await.context.contains("Internal CLI"));
+}
+
+#[tokio::test]
+async fn test_context_agent_skips_slow_providers() {
+    use ez_cli::context_agent::{ContextAgent, SkipReason};
+    use ez_cli::providers::ExternalProviderConfig;
+    use std::time::{Duration, Instant};
+
+    let providers: Vec<ExternalProviderConfig> = vec![
+        toml::from_str(
+            r#"
+            name = "hung-daemon"
+            keywords = ["acme"]
+            command = "sleep 10"
+            timeout_secs = 0.3
+            "#,
+        )
+        .unwrap(),
+        toml::from_str(
+            r#"
+            name = "fast"
+            keywords = ["acme"]
+            command = "sleep 0.1; echo fast context"
+            "#,
+        )
+        .unwrap(),
+        toml::from_str(
+            r#"
+            name = "elsewhere"
+            keywords = ["acme"]
+            detect_files = ["does-not-exist.acme"]
+            command = "echo unreachable"
+            "#,
+        )
+        .unwrap(),
+    ];
+
+    let agent = ContextAgent::new().unwrap().with_external_providers(&providers);
+
+    let start = Instant::now();
+    let gathered = agent.gather("acme status").await;
+    assert!(start.elapsed() < Duration::from_secs(3), "gathering waited for the hung provider");
+
+    assert!(gathered.context.contains("fast context"));
+    assert!(gathered
+        .skipped
+        .iter()
+        .any(|s| s.name == "hung-daemon" && matches!(s.reason, SkipReason::TimedOut(_))));
+    assert!(gathered
+        .skipped
+        .iter()
+        .any(|s| s.name == "elsewhere" && s.reason == SkipReason::NotDetected));
+}